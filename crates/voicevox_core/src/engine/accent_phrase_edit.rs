@@ -0,0 +1,402 @@
+use super::model::{AccentPhraseModel, MoraModel, TextSpan};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// アクセント句の列に対する編集操作
+///
+/// `accent_phrase_index`は編集するアクセント句の位置を表す
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AccentPhraseEdit {
+    /// アクセント位置を`accent`に変更する
+    ChangeAccent {
+        accent_phrase_index: usize,
+        accent: usize,
+    },
+    /// アクセント句を次のアクセント句と結合する
+    Merge { accent_phrase_index: usize },
+    /// アクセント句を`mora_index`番目のモーラの前で分割する
+    Split {
+        accent_phrase_index: usize,
+        mora_index: usize,
+    },
+    /// アクセント句の後の息継ぎの有無を切り替える
+    TogglePause { accent_phrase_index: usize },
+    /// アクセント句を疑問文の語尾として扱うかを切り替える
+    ToggleInterrogative { accent_phrase_index: usize },
+}
+
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+pub enum AccentPhraseEditError {
+    #[error("accent phrase index must be less than {len}: {accent_phrase_index}")]
+    AccentPhraseIndexOutOfRange {
+        accent_phrase_index: usize,
+        len: usize,
+    },
+    #[error("accent phrase has no next accent phrase to merge: {accent_phrase_index}")]
+    NoNextAccentPhrase { accent_phrase_index: usize },
+    #[error("accent must be in 1..={mora_count}: {accent}")]
+    AccentOutOfRange { accent: usize, mora_count: usize },
+    #[error("mora index must be in 1..{mora_count}: {mora_index}")]
+    MoraIndexOutOfRange {
+        mora_index: usize,
+        mora_count: usize,
+    },
+}
+
+type EditResult<T> = std::result::Result<T, AccentPhraseEditError>;
+
+/// 音素の長さと音高を予測し直すとき、影響を受けるアクセント句の前後に文脈として含めるアクセント句の数
+const CONTEXT_ACCENT_PHRASES: usize = 2;
+
+/// 編集を適用した結果
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct AppliedEdit {
+    /// 音素の長さと音高を予測し直すアクセント句の範囲
+    pub(crate) affected: Range<usize>,
+    /// アクセント位置を推定し直すアクセント句の位置
+    pub(crate) missing_accent_indices: Vec<usize>,
+}
+
+impl AccentPhraseEdit {
+    pub fn accent_phrase_index(&self) -> usize {
+        match *self {
+            Self::ChangeAccent {
+                accent_phrase_index,
+                ..
+            }
+            | Self::Merge {
+                accent_phrase_index,
+            }
+            | Self::Split {
+                accent_phrase_index,
+                ..
+            }
+            | Self::TogglePause {
+                accent_phrase_index,
+            }
+            | Self::ToggleInterrogative {
+                accent_phrase_index,
+            } => accent_phrase_index,
+        }
+    }
+
+    /// 編集をアクセント句の列に適用する
+    ///
+    /// 分割したアクセント句のうち元のアクセント位置を含まない方は、アクセント位置を末尾とし、推定し直す対象とする
+    pub(crate) fn apply(
+        &self,
+        accent_phrases: &mut Vec<AccentPhraseModel>,
+    ) -> EditResult<AppliedEdit> {
+        let index = self.accent_phrase_index();
+        if index >= accent_phrases.len() {
+            return Err(AccentPhraseEditError::AccentPhraseIndexOutOfRange {
+                accent_phrase_index: index,
+                len: accent_phrases.len(),
+            });
+        }
+
+        match *self {
+            Self::ChangeAccent { accent, .. } => {
+                let accent_phrase = &mut accent_phrases[index];
+                let mora_count = accent_phrase.moras().len();
+                if !(1..=mora_count).contains(&accent) {
+                    return Err(AccentPhraseEditError::AccentOutOfRange { accent, mora_count });
+                }
+                accent_phrase.set_accent(accent);
+                Ok(AppliedEdit::new(index..index + 1, vec![]))
+            }
+            Self::Merge { .. } => {
+                if index + 1 == accent_phrases.len() {
+                    return Err(AccentPhraseEditError::NoNextAccentPhrase {
+                        accent_phrase_index: index,
+                    });
+                }
+                let next = accent_phrases.remove(index + 1);
+                accent_phrases[index] = merge(&accent_phrases[index], next);
+                Ok(AppliedEdit::new(index..index + 1, vec![]))
+            }
+            Self::Split { mora_index, .. } => {
+                let mora_count = accent_phrases[index].moras().len();
+                if !(1..mora_count).contains(&mora_index) {
+                    return Err(AccentPhraseEditError::MoraIndexOutOfRange {
+                        mora_index,
+                        mora_count,
+                    });
+                }
+                let (former, latter, missing_accent_index) =
+                    split(&accent_phrases[index], mora_index);
+                accent_phrases.splice(index..=index, [former, latter]);
+                Ok(AppliedEdit::new(
+                    index..index + 2,
+                    vec![index + missing_accent_index],
+                ))
+            }
+            Self::TogglePause { .. } => {
+                let accent_phrase = &mut accent_phrases[index];
+                if accent_phrase.pause_mora().is_some() {
+                    accent_phrase.set_pause_mora(None);
+                    accent_phrase.set_is_pause_length_explicit(false);
+                    accent_phrase.set_pause_punctuation(None);
+                } else {
+                    accent_phrase.set_pause_mora(Some(MoraModel::new(
+                        "、".into(),
+                        None,
+                        None,
+                        "pau".into(),
+                        0.,
+                        0.,
+                    )));
+                }
+                // 息継ぎの有無は次のアクセント句の始まりの長さと音高にも影響する
+                let end = (index + 2).min(accent_phrases.len());
+                Ok(AppliedEdit::new(index..end, vec![]))
+            }
+            Self::ToggleInterrogative { .. } => {
+                let accent_phrase = &mut accent_phrases[index];
+                accent_phrase.set_is_interrogative(!accent_phrase.is_interrogative());
+                Ok(AppliedEdit::new(index..index + 1, vec![]))
+            }
+        }
+    }
+}
+
+impl AppliedEdit {
+    fn new(affected: Range<usize>, missing_accent_indices: Vec<usize>) -> Self {
+        Self {
+            affected,
+            missing_accent_indices,
+        }
+    }
+
+    /// 影響を受けるアクセント句の範囲に、前後の文脈のアクセント句を加えた、推論に用いる範囲を返す
+    pub(crate) fn context_range(&self, len: usize) -> Range<usize> {
+        self.affected.start.saturating_sub(CONTEXT_ACCENT_PHRASES)
+            ..(self.affected.end + CONTEXT_ACCENT_PHRASES).min(len)
+    }
+}
+
+/// 2つのアクセント句を結合する
+///
+/// アクセント位置は最初に音高が下がる位置とし、息継ぎと疑問文の設定は後のアクセント句のものを用いる。
+/// 話速などのアクセント句ごとの値は前のアクセント句のものを用いる
+fn merge(former: &AccentPhraseModel, latter: AccentPhraseModel) -> AccentPhraseModel {
+    let former_mora_count = former.moras().len();
+    let accent = if *former.accent() < former_mora_count {
+        *former.accent()
+    } else {
+        former_mora_count + latter.accent()
+    };
+    let mut moras = former.moras().clone();
+    moras.extend(latter.moras().iter().cloned());
+
+    let mut accent_phrase = latter;
+    accent_phrase.set_source_span(join_spans(
+        *former.source_span(),
+        *accent_phrase.source_span(),
+    ));
+    accent_phrase.set_moras(moras);
+    accent_phrase.set_accent(accent);
+    accent_phrase.set_prosody(*former.prosody());
+    accent_phrase
+}
+
+/// アクセント句を`mora_index`番目のモーラの前で分割し、アクセント位置を推定し直す方が前後のどちらかを返す
+///
+/// 息継ぎと疑問文の設定は後のアクセント句に引き継ぎ、話速などのアクセント句ごとの値は両方に引き継ぐ
+fn split(
+    accent_phrase: &AccentPhraseModel,
+    mora_index: usize,
+) -> (AccentPhraseModel, AccentPhraseModel, usize) {
+    let (former_moras, latter_moras) = accent_phrase.moras().split_at(mora_index);
+    let accent = *accent_phrase.accent();
+    let (former_accent, latter_accent, missing_accent_index) = if accent <= mora_index {
+        (accent, latter_moras.len(), 1)
+    } else {
+        (mora_index, accent - mora_index, 0)
+    };
+
+    let mut former = accent_phrase.clone();
+    former.set_moras(former_moras.to_vec());
+    former.set_accent(former_accent);
+    former.set_source_span(moras_span(former_moras));
+    former.set_pause_mora(None);
+    former.set_is_pause_length_explicit(false);
+    former.set_pause_punctuation(None);
+    former.set_is_interrogative(false);
+
+    let mut latter = accent_phrase.clone();
+    latter.set_moras(latter_moras.to_vec());
+    latter.set_accent(latter_accent);
+    latter.set_source_span(moras_span(latter_moras));
+
+    (former, latter, missing_accent_index)
+}
+
+fn moras_span(moras: &[MoraModel]) -> Option<TextSpan> {
+    moras
+        .iter()
+        .map(|mora| *mora.source_span())
+        .fold(None, join_spans)
+}
+
+fn join_spans(span1: Option<TextSpan>, span2: Option<TextSpan>) -> Option<TextSpan> {
+    match (span1, span2) {
+        (Some(span1), Some(span2)) => Some(TextSpan {
+            start: span1.start.min(span2.start),
+            end: span1.end.max(span2.end),
+        }),
+        (span, None) | (None, span) => span,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::parse_kana;
+    use crate::*;
+    use pretty_assertions::assert_eq;
+
+    fn accents(accent_phrases: &[AccentPhraseModel]) -> Vec<(String, usize)> {
+        accent_phrases
+            .iter()
+            .map(|accent_phrase| {
+                let reading = accent_phrase
+                    .moras()
+                    .iter()
+                    .map(|mora| mora.text().as_str())
+                    .collect();
+                (reading, *accent_phrase.accent())
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case(
+        "コ'レワ/テス'ト/デ'ス",
+        AccentPhraseEdit::ChangeAccent { accent_phrase_index: 1, accent: 3 },
+        &[("コレワ", 1), ("テスト", 3), ("デス", 1)],
+        AppliedEdit::new(1..2, vec![])
+    )]
+    #[case(
+        "コ'レワ/テス'ト/デ'ス",
+        AccentPhraseEdit::Merge { accent_phrase_index: 1 },
+        &[("コレワ", 1), ("テストデス", 2)],
+        AppliedEdit::new(1..2, vec![])
+    )]
+    #[case(
+        "コレワ'/テス'ト",
+        AccentPhraseEdit::Merge { accent_phrase_index: 0 },
+        &[("コレワテスト", 5)],
+        AppliedEdit::new(0..1, vec![])
+    )]
+    #[case(
+        "コ'レワ/テス'ト/デ'ス",
+        AccentPhraseEdit::Split { accent_phrase_index: 0, mora_index: 2 },
+        &[("コレ", 1), ("ワ", 1), ("テスト", 2), ("デス", 1)],
+        AppliedEdit::new(0..2, vec![1])
+    )]
+    #[case(
+        "コ'レワ/テス'ト/デ'ス",
+        AccentPhraseEdit::Split { accent_phrase_index: 1, mora_index: 1 },
+        &[("コレワ", 1), ("テ", 1), ("スト", 1), ("デス", 1)],
+        AppliedEdit::new(1..3, vec![1])
+    )]
+    #[case(
+        "コ'レワ/テス'ト/デ'ス",
+        AccentPhraseEdit::TogglePause { accent_phrase_index: 0 },
+        &[("コレワ", 1), ("テスト", 2), ("デス", 1)],
+        AppliedEdit::new(0..2, vec![])
+    )]
+    #[case(
+        "コ'レワ/テス'ト/デ'ス",
+        AccentPhraseEdit::ToggleInterrogative { accent_phrase_index: 2 },
+        &[("コレワ", 1), ("テスト", 2), ("デス", 1)],
+        AppliedEdit::new(2..3, vec![])
+    )]
+    fn apply_works(
+        #[case] kana: &str,
+        #[case] edit: AccentPhraseEdit,
+        #[case] expected_accents: &[(&str, usize)],
+        #[case] expected_applied_edit: AppliedEdit,
+    ) {
+        let mut accent_phrases = parse_kana(kana).unwrap();
+        let applied_edit = edit.apply(&mut accent_phrases).unwrap();
+        assert_eq!(
+            accents(&accent_phrases),
+            expected_accents
+                .iter()
+                .map(|&(reading, accent)| (reading.to_owned(), accent))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(applied_edit, expected_applied_edit);
+    }
+
+    #[rstest]
+    #[case(0..1, 1, 0..1)]
+    #[case(0..1, 5, 0..3)]
+    #[case(3..4, 10, 1..6)]
+    #[case(4..6, 7, 2..7)]
+    fn context_range_works(
+        #[case] affected: Range<usize>,
+        #[case] len: usize,
+        #[case] expected: Range<usize>,
+    ) {
+        assert_eq!(
+            AppliedEdit::new(affected, vec![]).context_range(len),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn apply_moves_pause_and_interrogative_to_latter_accent_phrase() {
+        let mut accent_phrases = parse_kana("コ'レワ、テス'ト/デ'スカ？").unwrap();
+        AccentPhraseEdit::Split {
+            accent_phrase_index: 0,
+            mora_index: 1,
+        }
+        .apply(&mut accent_phrases)
+        .unwrap();
+        assert!(accent_phrases[0].pause_mora().is_none());
+        assert!(accent_phrases[1].pause_mora().is_some());
+
+        AccentPhraseEdit::Merge {
+            accent_phrase_index: 2,
+        }
+        .apply(&mut accent_phrases)
+        .unwrap();
+        assert!(*accent_phrases[2].is_interrogative());
+
+        AccentPhraseEdit::TogglePause {
+            accent_phrase_index: 1,
+        }
+        .apply(&mut accent_phrases)
+        .unwrap();
+        assert!(accent_phrases[1].pause_mora().is_none());
+    }
+
+    #[rstest]
+    #[case(
+        AccentPhraseEdit::ChangeAccent { accent_phrase_index: 0, accent: 0 },
+        AccentPhraseEditError::AccentOutOfRange { accent: 0, mora_count: 3 }
+    )]
+    #[case(
+        AccentPhraseEdit::Merge { accent_phrase_index: 1 },
+        AccentPhraseEditError::NoNextAccentPhrase { accent_phrase_index: 1 }
+    )]
+    #[case(
+        AccentPhraseEdit::Split { accent_phrase_index: 1, mora_index: 3 },
+        AccentPhraseEditError::MoraIndexOutOfRange { mora_index: 3, mora_count: 3 }
+    )]
+    #[case(
+        AccentPhraseEdit::TogglePause { accent_phrase_index: 2 },
+        AccentPhraseEditError::AccentPhraseIndexOutOfRange { accent_phrase_index: 2, len: 2 }
+    )]
+    fn apply_rejects_invalid_edits(
+        #[case] edit: AccentPhraseEdit,
+        #[case] expected: AccentPhraseEditError,
+    ) {
+        let mut accent_phrases = parse_kana("コ'レワ/テス'ト").unwrap();
+        assert_eq!(edit.apply(&mut accent_phrases).unwrap_err(), expected);
+    }
+}
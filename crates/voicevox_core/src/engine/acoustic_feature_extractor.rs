@@ -92,6 +92,11 @@ impl OjtPhoneme {
         PHONEME_MAP.len()
     }
 
+    /// 音声合成モデルが扱える音素かどうか
+    pub fn is_known_phoneme(phoneme: &str) -> bool {
+        PHONEME_MAP.contains_key(phoneme)
+    }
+
     pub fn space_phoneme() -> String {
         "pau".into()
     }
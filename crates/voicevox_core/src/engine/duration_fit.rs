@@ -0,0 +1,432 @@
+use std::ops::Range;
+
+use super::synthesis_engine::adjust_pause_length;
+use super::*;
+use serde::Serialize;
+
+/// ポーズの長さを、母音の長さに比べて何倍の割合で伸縮させるか
+const PAUSE_WEIGHT: f32 = 2.;
+
+/// 母音の長さを縮める倍率の下限
+const MIN_VOWEL_SCALE: f32 = 0.3;
+
+/// 母音の長さを下限で止めたときの誤差を小さくするため、伸縮を繰り返す回数
+const ITERATIONS: usize = 4;
+
+/// 文の終わりとして扱うポーズの句読点
+const SENTENCE_END_PUNCTUATIONS: &[&str] = &["。", "．", ".", "！", "？", "!", "?"];
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum TargetDurationError {
+    #[error("target duration must be a finite positive value: {0}")]
+    InvalidDuration(f32),
+    #[error("number of target durations must be the number of sentences {expected}: {actual}")]
+    SentenceCountMismatch { expected: usize, actual: usize },
+    #[error("target duration {target} cannot be reached with locked or shortest moras: {actual}")]
+    Unreachable { target: f32, actual: f32 },
+}
+
+/// 音声の目標の長さ(秒)
+#[derive(Clone, Debug, PartialEq)]
+pub enum TargetDuration {
+    /// 先頭と末尾の無音を含む、音声全体の長さ
+    Total(f32),
+    /// 文ごとの長さ。文は文末の句読点のポーズで区切り、文末のポーズを含む
+    Sentences(Vec<f32>),
+}
+
+/// 目標の長さに合わせたAudioQueryと、その音声の長さ
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DurationFit {
+    pub audio_query: AudioQueryModel,
+    /// 先頭と末尾の無音を含む、音声全体の長さ(秒)
+    pub duration: f32,
+    /// 文ごとの長さ(秒)
+    pub sentence_durations: Vec<f32>,
+}
+
+/// AudioQueryの母音とポーズの長さを伸縮させ、音声の長さを目標の長さに合わせる
+///
+/// 子音の長さと、先頭と末尾の無音の長さは変えない。ポーズは母音の[`PAUSE_WEIGHT`]倍の割合で伸縮させ、
+/// 母音は[`MIN_VOWEL_SCALE`]倍より短くしない。固定したモーラとポーズの長さも変えない。
+/// ポーズの長さはアクセント句ごとに明示したポーズの長さとして設定する。
+/// 音素の長さはフレーム単位に丸められるため、伸縮させた後に母音を1フレームずつ伸ばすか縮めて誤差を埋める。
+/// 固定したモーラや母音の長さの下限のため、目標の長さとの差が1フレームより大きく残るときはエラーとする
+pub(crate) fn fit_duration(
+    query: &AudioQueryModel,
+    interrogative_upspeak: Option<&InterrogativeUpspeak>,
+    target_duration: &TargetDuration,
+) -> Result<DurationFit> {
+    let mut query = with_explicit_pauses(query);
+    let sentences = sentence_ranges(query.accent_phrases());
+
+    let targets = match target_duration {
+        TargetDuration::Total(duration) => vec![(0..query.accent_phrases().len(), *duration)],
+        TargetDuration::Sentences(durations) => {
+            if durations.len() != sentences.len() {
+                return Err(TargetDurationError::SentenceCountMismatch {
+                    expected: sentences.len(),
+                    actual: durations.len(),
+                }
+                .into());
+            }
+            std::iter::zip(sentences.clone(), durations.iter().copied()).collect()
+        }
+    };
+    if let Some(&(_, duration)) = targets
+        .iter()
+        .find(|(_, duration)| !(duration.is_finite() && *duration > 0.))
+    {
+        return Err(TargetDurationError::InvalidDuration(duration).into());
+    }
+    // 全体の長さを指定したときは、先頭と末尾の無音も含めて合わせる
+    let is_total = matches!(target_duration, TargetDuration::Total(_));
+    let range_length = |segments: &[Segment], range: &Range<usize>| {
+        let mut length = segments[range.start + 1..range.end + 1]
+            .iter()
+            .fold(Segment::default(), Segment::add);
+        if is_total {
+            length = length.add(&segments[0]).add(segments.last().unwrap());
+        }
+        length
+    };
+
+    for _ in 0..ITERATIONS {
+        let segments = measure_segments(&query, interrogative_upspeak)?;
+        for (range, target) in &targets {
+            let current = range_length(&segments, range).duration;
+            scale_lengths(&mut query, range.clone(), target - current);
+        }
+    }
+
+    let segments = measure_segments(&query, interrogative_upspeak)?;
+    for (range, target) in &targets {
+        let target_frames = (target * PITCH_FRAME_RATE).round() as i64;
+        let current_frames = range_length(&segments, range).frames as i64;
+        adjust_frames(&mut query, range.clone(), target_frames - current_frames);
+    }
+
+    let segments = measure_segments(&query, interrogative_upspeak)?;
+    let to_seconds = |segment: Segment| segment.frames as f32 / PITCH_FRAME_RATE;
+    for (range, target) in &targets {
+        let target_frames = (target * PITCH_FRAME_RATE).round() as i64;
+        let length = range_length(&segments, range);
+        if (target_frames - length.frames as i64).abs() > 1 {
+            return Err(TargetDurationError::Unreachable {
+                target: *target,
+                actual: to_seconds(length),
+            }
+            .into());
+        }
+    }
+    Ok(DurationFit {
+        duration: to_seconds(segments.iter().fold(Segment::default(), Segment::add)),
+        sentence_durations: sentences
+            .iter()
+            .map(|range| to_seconds(range_length(&segments, range)))
+            .collect(),
+        audio_query: query,
+    })
+}
+
+/// 音声合成したときの区間の長さ
+#[derive(Clone, Copy, Default)]
+struct Segment {
+    /// フレーム単位に丸める前の長さ(秒)
+    duration: f32,
+    /// フレーム数
+    frames: usize,
+}
+
+impl Segment {
+    fn add(self, other: &Self) -> Self {
+        Self {
+            duration: self.duration + other.duration,
+            frames: self.frames + other.frames,
+        }
+    }
+}
+
+/// 全てのポーズの長さを、アクセント句ごとに明示したポーズの長さにする
+fn with_explicit_pauses(query: &AudioQueryModel) -> AudioQueryModel {
+    let mut new_query = query.clone();
+    for accent_phrase in new_query.accent_phrases_mut() {
+        if let Some(pause_length) = adjust_pause_length(query, accent_phrase) {
+            set_pause_length(accent_phrase, pause_length);
+        }
+    }
+    new_query.set_pause_length(None);
+    new_query.set_pause_length_scale(1.);
+    new_query
+}
+
+/// ポーズのモーラの母音の長さを設定し、明示したポーズの長さとする
+fn set_pause_length(accent_phrase: &mut AccentPhraseModel, pause_length: f32) {
+    if let Some(pause_mora) = accent_phrase.pause_mora_mut() {
+        pause_mora.set_vowel_length(pause_length);
+    }
+    accent_phrase.set_is_pause_length_explicit(true);
+}
+
+/// 文末の句読点のポーズで区切った、文ごとのアクセント句の範囲
+fn sentence_ranges(accent_phrases: &[AccentPhraseModel]) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for (i, accent_phrase) in accent_phrases.iter().enumerate() {
+        let is_sentence_end = accent_phrase.pause_mora().is_some()
+            && matches!(
+                accent_phrase.pause_punctuation(),
+                Some(punctuation) if SENTENCE_END_PUNCTUATIONS.contains(&punctuation.as_str())
+            );
+        if is_sentence_end || i + 1 == accent_phrases.len() {
+            sentences.push(start..i + 1);
+            start = i + 1;
+        }
+    }
+    sentences
+}
+
+/// 先頭の無音、各アクセント句、末尾の無音の順に、音声合成したときの長さを求める
+fn measure_segments(
+    query: &AudioQueryModel,
+    interrogative_upspeak: Option<&InterrogativeUpspeak>,
+) -> Result<Vec<Segment>> {
+    let decode_input = SynthesisEngine::create_decode_input(query, interrogative_upspeak)?;
+    let frame_lengths = frame_lengths(&decode_input.durations);
+    let mut start = 0;
+    Ok(decode_input
+        .segment_lengths
+        .iter()
+        .map(|&length| {
+            let phonemes = start..start + length;
+            start += length;
+            Segment {
+                duration: decode_input.durations[phonemes.clone()].iter().sum(),
+                frames: frame_lengths[phonemes].iter().sum(),
+            }
+        })
+        .collect())
+}
+
+/// `range`のアクセント句の母音とポーズの長さを伸縮させ、音声の長さを`difference`秒だけ変える
+fn scale_lengths(query: &mut AudioQueryModel, range: Range<usize>, difference: f32) {
+    let speed_scale = *query.speed_scale();
+    let accent_phrases = &mut query.accent_phrases_mut()[range];
+
+    // 話速を適用した、伸縮させる母音とポーズの長さの合計
+    let mut vowel_length = 0.;
+    let mut pause_length = 0.;
+    for accent_phrase in accent_phrases.iter() {
+        let speed_scale = accent_phrase.prosody().speed_scale.unwrap_or(speed_scale);
+        for mora in accent_phrase.moras() {
+            if !mora.is_locked() {
+                vowel_length += mora.vowel_length() / speed_scale;
+            }
+        }
+        if let Some(pause_mora) = accent_phrase.pause_mora() {
+            if !pause_mora.is_locked() {
+                pause_length += pause_mora.vowel_length() / speed_scale;
+            }
+        }
+    }
+    let elastic_length = vowel_length + PAUSE_WEIGHT * pause_length;
+    if elastic_length <= 0. {
+        return;
+    }
+
+    let ratio = difference / elastic_length;
+    let vowel_scale = (1. + ratio).max(MIN_VOWEL_SCALE);
+    let pause_scale = (1. + PAUSE_WEIGHT * ratio).max(0.);
+    for accent_phrase in accent_phrases {
+        for mora in accent_phrase.moras_mut() {
+            if !mora.is_locked() {
+                mora.set_vowel_length(mora.vowel_length() * vowel_scale);
+            }
+        }
+        if let Some(pause_mora) = accent_phrase.pause_mora() {
+            if !pause_mora.is_locked() {
+                let pause_length = pause_mora.vowel_length() * pause_scale;
+                set_pause_length(accent_phrase, pause_length);
+            }
+        }
+    }
+}
+
+/// `range`のアクセント句の固定していない母音を、長いものから順に1フレームずつ伸ばすか縮め、
+/// 音声の長さを`difference`フレームだけ変える
+fn adjust_frames(query: &mut AudioQueryModel, range: Range<usize>, difference: i64) {
+    let speed_scale = *query.speed_scale();
+    let accent_phrases = &mut query.accent_phrases_mut()[range];
+    let mut moras = accent_phrases
+        .iter()
+        .enumerate()
+        .flat_map(|(i, accent_phrase)| {
+            let speed_scale = accent_phrase.prosody().speed_scale.unwrap_or(speed_scale);
+            accent_phrase
+                .moras()
+                .iter()
+                .enumerate()
+                .filter(|(_, mora)| !mora.is_locked())
+                .map(move |(j, mora)| (i, j, mora.vowel_length() / speed_scale, speed_scale))
+        })
+        .collect::<Vec<_>>();
+    moras.sort_by(|(_, _, length1, _), (_, _, length2, _)| length2.total_cmp(length1));
+
+    let frame_length = difference.signum() as f32 / PITCH_FRAME_RATE;
+    for &(i, j, _, speed_scale) in moras
+        .iter()
+        .cycle()
+        .take(difference.unsigned_abs() as usize)
+    {
+        let mora = &mut accent_phrases[i].moras_mut()[j];
+        let vowel_length = mora.vowel_length() + frame_length * speed_scale;
+        // 1フレームより短くはしない
+        if vowel_length * PITCH_FRAME_RATE / speed_scale >= 1. {
+            mora.set_vowel_length(vowel_length);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn accent_phrase(pause: Option<&str>) -> AccentPhraseModel {
+        let mut accent_phrase = AccentPhraseModel::new(
+            vec![
+                MoraModel::new(
+                    "カ".into(),
+                    Some("k".into()),
+                    Some(0.05),
+                    "a".into(),
+                    0.1,
+                    5.,
+                ),
+                MoraModel::new("ア".into(), None, None, "a".into(), 0.1, 5.),
+            ],
+            1,
+            pause.map(|_| MoraModel::new("、".into(), None, None, "pau".into(), 0.2, 0.)),
+            false,
+        );
+        accent_phrase.set_pause_punctuation(pause.map(Into::into));
+        accent_phrase
+    }
+
+    fn query(accent_phrases: Vec<AccentPhraseModel>) -> AudioQueryModel {
+        AudioQueryModel::new(
+            accent_phrases,
+            1.,
+            0.,
+            1.,
+            1.,
+            0.1,
+            0.1,
+            SynthesisEngine::DEFAULT_SAMPLING_RATE,
+            false,
+            "".into(),
+        )
+    }
+
+    #[rstest]
+    fn sentence_ranges_works() {
+        let accent_phrases = [
+            accent_phrase(Some("、")),
+            accent_phrase(Some("。")),
+            accent_phrase(Some("？")),
+            accent_phrase(None),
+        ];
+        assert_eq!(sentence_ranges(&accent_phrases), [0..2, 2..3, 3..4]);
+        assert_eq!(sentence_ranges(&[]), Vec::<Range<usize>>::new());
+    }
+
+    #[rstest]
+    #[case(1.5)]
+    #[case(0.7)]
+    fn fit_duration_works_with_total(#[case] target: f32) {
+        let query = query(vec![accent_phrase(Some("。")), accent_phrase(None)]);
+        let fit = fit_duration(&query, None, &TargetDuration::Total(target)).unwrap();
+        assert!(
+            (fit.duration - target).abs() <= 1. / PITCH_FRAME_RATE,
+            "{fit:?}"
+        );
+
+        // 子音と先頭と末尾の無音の長さは変えず、ポーズは母音より大きな割合で伸縮させる
+        let fitted = &fit.audio_query;
+        assert_eq!(fitted.pre_phoneme_length(), query.pre_phoneme_length());
+        let moras = fitted
+            .accent_phrases()
+            .iter()
+            .flat_map(|accent_phrase| accent_phrase.moras())
+            .collect::<Vec<_>>();
+        assert_eq!(*moras[0].consonant_length(), Some(0.05));
+        let vowel_scale = moras.iter().map(|mora| mora.vowel_length()).sum::<f32>() / 0.4;
+        let pause_scale = fitted.accent_phrases()[0]
+            .pause_mora()
+            .as_ref()
+            .unwrap()
+            .vowel_length()
+            / 0.2;
+        assert!(
+            (pause_scale - 1.).abs() > (vowel_scale - 1.).abs(),
+            "{fit:?}"
+        );
+    }
+
+    #[rstest]
+    fn fit_duration_works_with_sentences() {
+        let mut locked = accent_phrase(None);
+        locked.moras_mut()[1].set_is_locked(true);
+        let query = query(vec![accent_phrase(Some("。")), locked]);
+        let fit = fit_duration(&query, None, &TargetDuration::Sentences(vec![0.4, 0.6])).unwrap();
+        for (actual, expected) in std::iter::zip(&fit.sentence_durations, [0.4, 0.6]) {
+            assert!(
+                (actual - expected).abs() <= 1. / PITCH_FRAME_RATE,
+                "{fit:?}"
+            );
+        }
+        assert_eq!(
+            *fit.audio_query.accent_phrases()[1].moras()[1].vowel_length(),
+            0.1
+        );
+    }
+
+    #[rstest]
+    #[case(
+        TargetDuration::Sentences(vec![1.]),
+        TargetDurationError::SentenceCountMismatch { expected: 2, actual: 1 }
+    )]
+    #[case(TargetDuration::Total(0.), TargetDurationError::InvalidDuration(0.))]
+    fn fit_duration_fails(
+        #[case] target_duration: TargetDuration,
+        #[case] expected: TargetDurationError,
+    ) {
+        let query = query(vec![accent_phrase(Some("。")), accent_phrase(None)]);
+        assert_eq!(
+            fit_duration(&query, None, &target_duration).unwrap_err(),
+            Error::InvalidTargetDuration(expected)
+        );
+    }
+
+    #[rstest]
+    #[case(TargetDuration::Total(0.05))]
+    #[case(TargetDuration::Sentences(vec![0.4, 3.]))]
+    fn fit_duration_fails_when_unreachable(#[case] target_duration: TargetDuration) {
+        // 先頭と末尾の無音と子音、固定したモーラの長さは変えないため、目標の長さにできない
+        let mut locked = accent_phrase(None);
+        for mora in locked.moras_mut() {
+            mora.set_is_locked(true);
+        }
+        let query = query(vec![accent_phrase(Some("。")), locked]);
+        assert!(
+            matches!(
+                fit_duration(&query, None, &target_duration),
+                Err(Error::InvalidTargetDuration(
+                    TargetDurationError::Unreachable { .. }
+                ))
+            ),
+            "{target_duration:?}"
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use crate::numerics::F32Ext as _;
+use serde::{Deserialize, Serialize};
+
+/// 音声合成モデルの1秒あたりのフレーム数(48000 / 512)
+pub const PITCH_FRAME_RATE: f32 = 93.75;
+
+/// 有声のフレームの音高の下限。約30Hzの基本周波数の自然対数
+///
+/// 音高を下げる調整で有声のフレームが0以下になり、無声として扱われないようにする
+pub(crate) const MIN_VOICED_PITCH: f32 = 3.4;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum FramePitchError {
+    #[error("frame-level pitch is not supported by the model of speaker {speaker_id}")]
+    UnsupportedModel { speaker_id: u32 },
+    #[error("number of frames must be {expected}: {actual}")]
+    FrameCountMismatch { expected: usize, actual: usize },
+    #[error("pitch must be a finite non-negative value: {0}")]
+    InvalidPitch(f32),
+    #[error("breakpoints must not be empty and must be sorted by time")]
+    InvalidBreakpoints,
+}
+
+/// 音高曲線の制御点
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PitchBreakpoint {
+    /// 音声の先頭からの時刻(秒)
+    pub time: f32,
+    /// 音高。0のときは無声とする
+    pub pitch: f32,
+}
+
+/// 音声合成モデルに入力するフレームごとの音高の指定
+#[derive(Clone, Debug, PartialEq)]
+pub enum FramePitch {
+    /// [`PITCH_FRAME_RATE`]のフレームごとの音高
+    Frames(Vec<f32>),
+    /// 制御点の間を線形補間した音高曲線
+    Breakpoints(Vec<PitchBreakpoint>),
+}
+
+impl FramePitch {
+    /// `num_frames`フレームの音高の列にする
+    pub(crate) fn to_frames(&self, num_frames: usize) -> Result<Vec<f32>, FramePitchError> {
+        let frames = match self {
+            Self::Frames(frames) => {
+                if frames.len() != num_frames {
+                    return Err(FramePitchError::FrameCountMismatch {
+                        expected: num_frames,
+                        actual: frames.len(),
+                    });
+                }
+                frames.clone()
+            }
+            Self::Breakpoints(breakpoints) => interpolate_breakpoints(breakpoints, num_frames)?,
+        };
+        if let Some(&pitch) = frames
+            .iter()
+            .find(|pitch| !pitch.is_finite() || **pitch < 0.)
+        {
+            return Err(FramePitchError::InvalidPitch(pitch));
+        }
+        Ok(frames)
+    }
+}
+
+/// モーラの音高の間の補間の方法
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PitchInterpolation {
+    /// 線形補間
+    #[default]
+    Linear,
+    /// 前後のモーラの音高の間をコサイン曲線で繋ぐ
+    Cosine,
+    /// 前後のモーラの音高の傾きを用いた3次エルミートスプライン(Catmull-Rom)
+    Spline,
+}
+
+/// モーラの音高を滑らかに繋いだフレームごとの音高の作り方
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PitchContour {
+    pub interpolation: PitchInterpolation,
+    /// 最初の有声のモーラから1秒あたりに下げる音高。下げた音高は[`MIN_VOICED_PITCH`]より低くしない
+    pub declination: f32,
+}
+
+impl PitchContour {
+    /// 音素ごとの音高と長さから、有声のモーラの母音の中心を制御点として補間したフレームごとの音高を作る
+    ///
+    /// `vowel_indices`は各モーラの母音の位置。
+    /// 無声のモーラは制御点とせず、その音素のフレームの音高は0とする
+    pub(crate) fn frame_pitches(
+        &self,
+        pitches: &[f32],
+        durations: &[f32],
+        vowel_indices: &[usize],
+    ) -> Vec<f32> {
+        let frame_lengths = frame_lengths(durations);
+        let frame_starts = frame_lengths
+            .iter()
+            .scan(0, |start, length| {
+                let frame_start = *start;
+                *start += length;
+                Some(frame_start)
+            })
+            .collect::<Vec<_>>();
+        let anchors = vowel_indices
+            .iter()
+            .filter(|&&i| pitches[i] > 0.)
+            .map(|&i| {
+                let center = frame_starts[i] as f32 + frame_lengths[i] as f32 / 2.;
+                (center, pitches[i])
+            })
+            .collect::<Vec<_>>();
+        let origin = anchors.first().map_or(0., |&(time, _)| time);
+
+        let mut segment = 0;
+        std::iter::zip(pitches, &frame_lengths)
+            .zip(&frame_starts)
+            .flat_map(|((&pitch, &length), &start)| {
+                (start..start + length).map(move |f| (pitch, f))
+            })
+            .map(|(pitch, frame)| {
+                if pitch <= 0. {
+                    return 0.;
+                }
+                if anchors.is_empty() {
+                    return pitch;
+                }
+                let time = frame as f32 + 0.5;
+                while segment + 1 < anchors.len() && anchors[segment + 1].0 <= time {
+                    segment += 1;
+                }
+                let declination = self.declination * (time - origin).max(0.) / PITCH_FRAME_RATE;
+                (self.interpolate(&anchors, segment, time) - declination).max(MIN_VOICED_PITCH)
+            })
+            .collect()
+    }
+
+    /// `anchors[segment]`と`anchors[segment + 1]`の間の時刻`time`の音高を求める
+    fn interpolate(&self, anchors: &[(f32, f32)], segment: usize, time: f32) -> f32 {
+        let (t0, p0) = anchors[segment];
+        if time < t0 {
+            return p0;
+        }
+        let Some(&(t1, p1)) = anchors.get(segment + 1) else {
+            return p0;
+        };
+        let ratio = (time - t0) / (t1 - t0);
+        match self.interpolation {
+            PitchInterpolation::Linear => p0 + (p1 - p0) * ratio,
+            PitchInterpolation::Cosine => {
+                p0 + (p1 - p0) * (1. - (std::f32::consts::PI * ratio).cos()) / 2.
+            }
+            PitchInterpolation::Spline => {
+                let slope = |i: usize| {
+                    let (prev_t, prev_p) = anchors[i.saturating_sub(1)];
+                    let (next_t, next_p) = anchors[(i + 1).min(anchors.len() - 1)];
+                    if next_t > prev_t {
+                        (next_p - prev_p) / (next_t - prev_t)
+                    } else {
+                        0.
+                    }
+                };
+                let h = t1 - t0;
+                let r2 = ratio * ratio;
+                let r3 = r2 * ratio;
+                (2. * r3 - 3. * r2 + 1.) * p0
+                    + (r3 - 2. * r2 + ratio) * h * slope(segment)
+                    + (-2. * r3 + 3. * r2) * p1
+                    + (r3 - r2) * h * slope(segment + 1)
+            }
+        }
+    }
+}
+
+/// 音素の長さから、音声合成モデルに入力するフレーム数を求める
+///
+/// `Status::length_regulator`と同じく、音素ごとにフレーム数を丸めてから足し合わせる
+pub(crate) fn frame_count(durations: &[f32]) -> usize {
+    frame_lengths(durations).iter().sum()
+}
+
+/// 音素ごとのフレーム数を求める
+pub(crate) fn frame_lengths(durations: &[f32]) -> Vec<usize> {
+    durations
+        .iter()
+        // numpy/pythonのroundと挙動を合わせるため、round_ties_even_を用いている
+        .map(|duration| (duration * PITCH_FRAME_RATE).round_ties_even_() as usize)
+        .collect()
+}
+
+/// 音素ごとの音高を、その音素のフレームに並べたフレームごとの音高にする
+pub(crate) fn phoneme_frame_pitches(pitches: &[f32], durations: &[f32]) -> Vec<f32> {
+    std::iter::zip(pitches, frame_lengths(durations))
+        .flat_map(|(&pitch, length)| vec![pitch; length])
+        .collect()
+}
+
+/// 各フレームの開始時刻での音高を、前後の制御点から線形補間して求める
+///
+/// 最初の制御点より前と最後の制御点より後はその制御点の音高とする。
+/// 有声と無声の間は補間せず、次の制御点まで前の制御点の音高とする
+fn interpolate_breakpoints(
+    breakpoints: &[PitchBreakpoint],
+    num_frames: usize,
+) -> Result<Vec<f32>, FramePitchError> {
+    let is_sorted = breakpoints
+        .windows(2)
+        .all(|pair| pair[0].time <= pair[1].time);
+    if breakpoints.is_empty() || !is_sorted || breakpoints.iter().any(|b| !b.time.is_finite()) {
+        return Err(FramePitchError::InvalidBreakpoints);
+    }
+
+    let mut next = 0;
+    Ok((0..num_frames)
+        .map(|frame| {
+            let time = frame as f32 / PITCH_FRAME_RATE;
+            while next < breakpoints.len() && breakpoints[next].time <= time {
+                next += 1;
+            }
+            match (
+                next.checked_sub(1).map(|i| breakpoints[i]),
+                breakpoints.get(next),
+            ) {
+                (Some(prev), Some(next)) if prev.pitch > 0. && next.pitch > 0. => {
+                    let ratio = (time - prev.time) / (next.time - prev.time);
+                    prev.pitch + (next.pitch - prev.pitch) * ratio
+                }
+                (Some(prev), _) => prev.pitch,
+                (None, next) => next.unwrap().pitch,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn breakpoint(time: f32, pitch: f32) -> PitchBreakpoint {
+        PitchBreakpoint { time, pitch }
+    }
+
+    #[rstest]
+    fn frame_count_works() {
+        // round(0.11 * 93.75) = 10, round(0.21 * 93.75) = 20
+        assert_eq!(frame_count(&[0.11, 0.21]), 30);
+    }
+
+    #[rstest]
+    fn to_frames_interpolates_breakpoints() {
+        let frame = 1. / PITCH_FRAME_RATE;
+        let frame_pitch = FramePitch::Breakpoints(vec![
+            breakpoint(frame, 5.),
+            breakpoint(3. * frame, 6.),
+            breakpoint(4. * frame, 0.),
+            breakpoint(5. * frame, 5.5),
+        ]);
+        assert_eq!(
+            frame_pitch.to_frames(7).unwrap(),
+            [5., 5., 5.5, 6., 0., 5.5, 5.5]
+        );
+    }
+
+    #[rstest]
+    #[case(PitchInterpolation::Linear, 0., [5., 5., 5., 5.125, 0., 0., 5.875, 6.])]
+    #[case(PitchInterpolation::Cosine, 0., [5., 5., 5., 5.03806, 0., 0., 5.96194, 6.])]
+    // 制御点が二つのときは線形補間と同じになる
+    #[case(PitchInterpolation::Spline, 0., [5., 5., 5., 5.125, 0., 0., 5.875, 6.])]
+    #[case(PitchInterpolation::Linear, PITCH_FRAME_RATE / 4., [5., 5., 5., 5., 0., 0., 5., 4.875])]
+    // 下げた音高は有声の音高の下限より低くしない
+    #[case(PitchInterpolation::Linear, PITCH_FRAME_RATE * 2., [5., 5., 5., 4.125, 0., 0., 3.4, 3.4])]
+    fn frame_pitches_works(
+        #[case] interpolation: PitchInterpolation,
+        #[case] declination: f32,
+        #[case] expected: [f32; 8],
+    ) {
+        let contour = PitchContour {
+            interpolation,
+            declination,
+        };
+        // 先頭の無音、「カ」、無声化した「ア」、「ア」、末尾の無音の順に、各音素2フレーム
+        let pitches = [0., 5., 5., 0., 6., 0.];
+        let durations = [2. / PITCH_FRAME_RATE; 6];
+        let frames = contour.frame_pitches(&pitches, &durations, &[0, 2, 3, 4, 5]);
+
+        assert_eq!(frames.len(), 12);
+        assert_eq!(frames[..2], [0., 0.]);
+        assert_eq!(frames[10..], [0., 0.]);
+        for (actual, expected) in std::iter::zip(&frames[2..10], expected) {
+            assert!((actual - expected).abs() < 1e-4, "{frames:?}");
+        }
+    }
+
+    #[rstest]
+    #[case(FramePitch::Frames(vec![5.; 3]), FramePitchError::FrameCountMismatch { expected: 4, actual: 3 })]
+    #[case(FramePitch::Frames(vec![5., -1., 5., 5.]), FramePitchError::InvalidPitch(-1.))]
+    #[case(FramePitch::Breakpoints(vec![]), FramePitchError::InvalidBreakpoints)]
+    #[case(
+        FramePitch::Breakpoints(vec![breakpoint(0.1, 5.), breakpoint(0., 5.)]),
+        FramePitchError::InvalidBreakpoints
+    )]
+    fn to_frames_fails(#[case] frame_pitch: FramePitch, #[case] expected: FramePitchError) {
+        assert_eq!(frame_pitch.to_frames(4).unwrap_err(), expected);
+    }
+}
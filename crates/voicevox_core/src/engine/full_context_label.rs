@@ -15,6 +15,9 @@ pub enum FullContextLabelError {
     #[error("invalid mora:{mora:?}")]
     InvalidMora { mora: Box<Mora> },
 
+    #[error("accent phrase has no mora")]
+    EmptyAccentPhrase,
+
     #[error(transparent)]
     OpenJtalk(#[from] open_jtalk::OpenJtalkError),
 }
@@ -146,7 +149,9 @@ impl AccentPhrase {
             }
         }
 
-        let mora = moras.get(0).unwrap();
+        let mora = moras
+            .first()
+            .ok_or(FullContextLabelError::EmptyAccentPhrase)?;
         let mut accent: usize = mora
             .vowel()
             .contexts()
@@ -0,0 +1,433 @@
+use super::full_context_label::{Phoneme, Utterance};
+use super::synthesis_engine::{mora_to_text, utterance_to_accent_phrases, MORA_PHONEME_LIST};
+use super::*;
+
+/// HTSラベルの時刻の単位(100ns)を秒にする係数
+const HTS_TIME_UNIT: f32 = 1e-7;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum HtsLabelError {
+    #[error("invalid label at line {line}: {text:?}")]
+    InvalidLine { line: usize, text: String },
+    #[error("label ends before it starts at line {line}")]
+    InvalidTime { line: usize },
+    #[error("unknown phoneme at line {line}: {phoneme:?}")]
+    UnknownPhoneme { line: usize, phoneme: String },
+    #[error("phoneme at line {line} is not a part of any mora: {phoneme:?}")]
+    InvalidMora { line: usize, phoneme: String },
+    #[error("labels do not match the accent phrases at line {line}: expected {expected:?}")]
+    PhonemeMismatch { line: usize, expected: String },
+    #[error("full context labels must end with a silence at line {line}")]
+    MissingTrailingSilence { line: usize },
+    #[error("number of pitches must be {expected}: {actual}")]
+    PitchCountMismatch { expected: usize, actual: usize },
+    #[error(transparent)]
+    FullContextLabel(#[from] FullContextLabelError),
+}
+
+type HtsLabelResult<T> = std::result::Result<T, HtsLabelError>;
+
+/// HTSラベルの1行
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtsLabel {
+    /// 行番号(1から数える)
+    line: usize,
+    /// 音素の長さ(秒)
+    duration: f32,
+    /// 音素。`sil`は`pau`とする
+    phoneme: String,
+    /// フルコンテキストラベルのときのコンテキスト
+    full_context: Option<Phoneme>,
+}
+
+/// モノラベル、またはフルコンテキストラベルの`開始時刻 終了時刻 ラベル`の行を解析する
+pub fn parse_hts_labels(text: &str) -> HtsLabelResult<Vec<HtsLabel>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(i, text)| parse_hts_label(i + 1, text))
+        .collect()
+}
+
+fn parse_hts_label(line: usize, text: &str) -> HtsLabelResult<HtsLabel> {
+    let invalid_line = || HtsLabelError::InvalidLine {
+        line,
+        text: text.into(),
+    };
+    let [start, end, label] = <[&str; 3]>::try_from(text.split_whitespace().collect::<Vec<_>>())
+        .map_err(|_| invalid_line())?;
+    let start = start.parse::<u64>().map_err(|_| invalid_line())?;
+    let end = end.parse::<u64>().map_err(|_| invalid_line())?;
+    if end < start {
+        return Err(HtsLabelError::InvalidTime { line });
+    }
+
+    let full_context = label
+        .contains("/A:")
+        .then(|| Phoneme::from_label(label))
+        .transpose()?;
+    let phoneme = match full_context.as_ref().map_or(label, Phoneme::phoneme) {
+        "sil" => "pau",
+        phoneme => phoneme,
+    };
+    if !OjtPhoneme::is_known_phoneme(phoneme) {
+        return Err(HtsLabelError::UnknownPhoneme {
+            line,
+            phoneme: phoneme.into(),
+        });
+    }
+
+    Ok(HtsLabel {
+        line,
+        duration: (end - start) as f32 * HTS_TIME_UNIT,
+        phoneme: phoneme.into(),
+        full_context,
+    })
+}
+
+/// HTSラベルから作ったアクセント句と前後の無音の長さ
+pub(crate) struct LabelAccentPhrases {
+    pub(crate) accent_phrases: Vec<AccentPhraseModel>,
+    /// モノラベルから作ったためにアクセント位置が分からないアクセント句
+    pub(crate) missing_accent_indices: Vec<usize>,
+    pub(crate) pre_phoneme_length: f32,
+    pub(crate) post_phoneme_length: f32,
+}
+
+/// HTSラベルから、ラベルの時刻を音素の長さとしたアクセント句を作る
+///
+/// 全てのラベルがフルコンテキストラベルのときは、そのアクセント句とアクセント位置を用いる。
+/// そうでないときは息継ぎの区切りごとに一つのアクセント句とする。
+/// `pitches`を指定したときは、各モーラの母音のラベルの値を音高とする
+pub(crate) fn label_accent_phrases(
+    labels: &[HtsLabel],
+    pitches: Option<&[f32]>,
+) -> HtsLabelResult<LabelAccentPhrases> {
+    if let Some(pitches) = pitches {
+        if pitches.len() != labels.len() {
+            return Err(HtsLabelError::PitchCountMismatch {
+                expected: labels.len(),
+                actual: pitches.len(),
+            });
+        }
+    }
+
+    let full_contexts = labels
+        .iter()
+        .map(|label| label.full_context.clone())
+        .collect::<Option<Vec<_>>>();
+    let (mut accent_phrases, missing_accent_indices) = match full_contexts {
+        Some(full_contexts) => {
+            validate_full_context_labels(labels)?;
+            let utterance = Utterance::from_phonemes(full_contexts)?;
+            (utterance_to_accent_phrases(&utterance, None, None), vec![])
+        }
+        None => {
+            let accent_phrases = mono_label_accent_phrases(labels)?;
+            let missing_accent_indices = (0..accent_phrases.len()).collect();
+            (accent_phrases, missing_accent_indices)
+        }
+    };
+
+    let mut labels = labels.iter().enumerate().peekable();
+    let pre_phoneme_length = take_pauses(&mut labels).unwrap_or(0.);
+    for accent_phrase in &mut accent_phrases {
+        for mora in accent_phrase.moras_mut() {
+            if let Some(consonant) = mora.consonant().clone() {
+                let (_, label) = next_label(&mut labels, &consonant)?;
+                mora.set_consonant_length(Some(label.duration));
+            }
+            let (index, label) = next_label(&mut labels, mora.vowel())?;
+            mora.set_vowel_length(label.duration);
+            if let Some(pitches) = pitches {
+                mora.set_pitch(pitches[index]);
+            }
+        }
+        if let Some(pause_mora) = accent_phrase.pause_mora_mut() {
+            let length =
+                take_pauses(&mut labels).ok_or_else(|| phoneme_mismatch(&mut labels, "pau"))?;
+            pause_mora.set_vowel_length(length);
+        }
+    }
+    let post_phoneme_length = take_pauses(&mut labels).unwrap_or(0.);
+    if let Some((_, label)) = labels.next() {
+        return Err(HtsLabelError::PhonemeMismatch {
+            line: label.line,
+            expected: "pau".into(),
+        });
+    }
+
+    Ok(LabelAccentPhrases {
+        accent_phrases,
+        missing_accent_indices,
+        pre_phoneme_length,
+        post_phoneme_length,
+    })
+}
+
+/// フルコンテキストラベルをアクセント句にまとめる前に、その構造を確かめる
+///
+/// 最後の息継ぎの区切りは末尾の無音で終わるため、末尾の無音がないラベルはエラーとする
+fn validate_full_context_labels(labels: &[HtsLabel]) -> HtsLabelResult<()> {
+    match labels.last() {
+        Some(HtsLabel {
+            line,
+            full_context: Some(full_context),
+            ..
+        }) if !full_context.is_pause() => {
+            Err(HtsLabelError::MissingTrailingSilence { line: *line })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 連続する無音のラベルを読み、その長さの合計を返す
+fn take_pauses<'a>(
+    labels: &mut std::iter::Peekable<impl Iterator<Item = (usize, &'a HtsLabel)>>,
+) -> Option<f32> {
+    let mut length = None;
+    while let Some((_, label)) = labels.next_if(|(_, label)| label.phoneme == "pau") {
+        *length.get_or_insert(0.) += label.duration;
+    }
+    length
+}
+
+fn next_label<'a>(
+    labels: &mut std::iter::Peekable<impl Iterator<Item = (usize, &'a HtsLabel)>>,
+    phoneme: &str,
+) -> HtsLabelResult<(usize, &'a HtsLabel)> {
+    labels
+        .next_if(|(_, label)| label.phoneme == phoneme)
+        .ok_or_else(|| phoneme_mismatch(labels, phoneme))
+}
+
+fn phoneme_mismatch<'a>(
+    labels: &mut std::iter::Peekable<impl Iterator<Item = (usize, &'a HtsLabel)>>,
+    expected: &str,
+) -> HtsLabelError {
+    HtsLabelError::PhonemeMismatch {
+        line: labels.peek().map_or(0, |(_, label)| label.line),
+        expected: expected.into(),
+    }
+}
+
+/// モノラベルの音素をモーラにまとめ、息継ぎの区切りごとに一つのアクセント句とする
+///
+/// アクセント位置はアクセント句の末尾とする
+fn mono_label_accent_phrases(labels: &[HtsLabel]) -> HtsLabelResult<Vec<AccentPhraseModel>> {
+    let mut accent_phrases = Vec::new();
+    let mut moras = Vec::new();
+    let mut consonant: Option<&HtsLabel> = None;
+    for label in labels {
+        let invalid_mora = |label: &HtsLabel| HtsLabelError::InvalidMora {
+            line: label.line,
+            phoneme: label.phoneme.clone(),
+        };
+        if label.phoneme == "pau" {
+            if let Some(consonant) = consonant {
+                return Err(invalid_mora(consonant));
+            }
+            if !moras.is_empty() {
+                accent_phrases.push(AccentPhraseModel::new(
+                    std::mem::take(&mut moras),
+                    0,
+                    Some(MoraModel::new(
+                        "、".into(),
+                        None,
+                        None,
+                        "pau".into(),
+                        0.,
+                        0.,
+                    )),
+                    false,
+                ));
+            }
+        } else if MORA_PHONEME_LIST.contains(&label.phoneme.as_str()) {
+            let consonant = consonant.take().map(|consonant| consonant.phoneme.clone());
+            let text = consonant.clone().unwrap_or_default() + &label.phoneme;
+            moras.push(MoraModel::new(
+                mora_to_text(text),
+                consonant.clone(),
+                consonant.map(|_| 0.),
+                label.phoneme.clone(),
+                0.,
+                0.,
+            ));
+        } else if consonant.is_some() {
+            return Err(invalid_mora(label));
+        } else {
+            consonant = Some(label);
+        }
+    }
+    if let Some(consonant) = consonant {
+        return Err(HtsLabelError::InvalidMora {
+            line: consonant.line,
+            phoneme: consonant.phoneme.clone(),
+        });
+    }
+    if !moras.is_empty() {
+        accent_phrases.push(AccentPhraseModel::new(moras, 0, None, false));
+    } else if let Some(last) = accent_phrases.last_mut() {
+        // 末尾の無音は息継ぎではない
+        last.set_pause_mora(None);
+    }
+    for accent_phrase in &mut accent_phrases {
+        accent_phrase.set_accent(accent_phrase.moras().len());
+    }
+    Ok(accent_phrases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use pretty_assertions::assert_eq;
+
+    const MONO_LABELS: &str = "
+        0 1000000 sil
+        1000000 1500000 k
+        1500000 2500000 o
+        2500000 3000000 N
+        3000000 5000000 pau
+        5000000 5500000 h
+        5500000 6500000 i
+        6500000 8000000 sil
+    ";
+
+    #[rstest]
+    fn parse_hts_labels_works() {
+        let labels = parse_hts_labels(MONO_LABELS).unwrap();
+        assert_eq!(labels.len(), 8);
+        assert_eq!(labels[0].phoneme, "pau");
+        assert_eq!(labels[0].line, 2);
+        assert!((labels[2].duration - 0.1).abs() < 1e-6);
+        assert_eq!(labels[2].full_context, None);
+    }
+
+    #[rstest]
+    fn parse_hts_labels_reads_full_context_labels() {
+        let labels = parse_hts_labels(
+            "0 500000 xx^sil-k+o=N/A:-4+1+5/B:xx-xx_xx/C:09_xx+xx/D:09+xx_xx/E:xx_xx!xx_xx-xx\
+             /F:5_5#0_xx@1_1|1_5/G:4_1%0_xx_0/H:xx_xx/I:1-5@1+2&1-2|1+9/J:1_4/K:2+2-9",
+        )
+        .unwrap();
+        assert_eq!(labels[0].phoneme, "k");
+        assert!(labels[0].full_context.is_some());
+    }
+
+    #[rstest]
+    #[case("0 1 sil extra", HtsLabelError::InvalidLine { line: 1, text: "0 1 sil extra".into() })]
+    #[case("a 1 sil", HtsLabelError::InvalidLine { line: 1, text: "a 1 sil".into() })]
+    #[case("2 1 sil", HtsLabelError::InvalidTime { line: 1 })]
+    #[case("\n0 1 xyz", HtsLabelError::UnknownPhoneme { line: 2, phoneme: "xyz".into() })]
+    fn parse_hts_labels_fails(#[case] text: &str, #[case] expected: HtsLabelError) {
+        assert_eq!(parse_hts_labels(text).unwrap_err(), expected);
+    }
+
+    #[rstest]
+    fn label_accent_phrases_works_with_mono_labels() {
+        let labels = parse_hts_labels(MONO_LABELS).unwrap();
+        let pitches = [0., 5., 5., 5.5, 0., 6., 6., 0.];
+        let LabelAccentPhrases {
+            accent_phrases,
+            missing_accent_indices,
+            pre_phoneme_length,
+            post_phoneme_length,
+        } = label_accent_phrases(&labels, Some(&pitches)).unwrap();
+
+        assert_eq!(missing_accent_indices, [0, 1]);
+        assert!((pre_phoneme_length - 0.1).abs() < 1e-6);
+        assert!((post_phoneme_length - 0.15).abs() < 1e-6);
+        assert_eq!(accent_phrases.len(), 2);
+        assert_eq!(*accent_phrases[0].accent(), 2);
+        let moras = to_flatten_moras(&accent_phrases);
+        let texts = moras
+            .iter()
+            .map(|mora| mora.text().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["コ", "ン", "、", "ヒ"]);
+        let pitches = moras.iter().map(|mora| *mora.pitch()).collect::<Vec<_>>();
+        assert_eq!(pitches, [5., 5.5, 0., 6.]);
+        assert!((moras[2].vowel_length() - 0.2).abs() < 1e-6);
+        assert!((moras[0].consonant_length().unwrap() - 0.05).abs() < 1e-6);
+    }
+
+    #[rstest]
+    #[case("0 1 k\n1 2 t\n2 3 a", HtsLabelError::InvalidMora { line: 2, phoneme: "t".into() })]
+    #[case("0 1 a\n1 2 k", HtsLabelError::InvalidMora { line: 2, phoneme: "k".into() })]
+    fn label_accent_phrases_fails(#[case] text: &str, #[case] expected: HtsLabelError) {
+        let labels = parse_hts_labels(text).unwrap();
+        assert_eq!(label_accent_phrases(&labels, None).err().unwrap(), expected);
+    }
+
+    /// 音素`phoneme`の、アクセント句中のモーラの位置が`a2`、アクセント句のモーラ数が`f1`、アクセント位置が`f2`の
+    /// フルコンテキストラベル
+    fn full_context_label(start: u64, phoneme: &str, a2: &str, f1: &str, f2: &str) -> String {
+        format!(
+            "{start} {end} xx^xx-{phoneme}+xx=xx/A:xx+{a2}+xx/B:xx-xx_xx/C:xx_xx+xx/D:xx+xx_xx\
+             /E:xx_xx!xx_xx-xx/F:{f1}_{f2}#0_xx@1_1|1_1/G:xx_xx%xx_xx_xx/H:xx_xx\
+             /I:1-1@1+1&1-1|1+1/J:xx_xx/K:1+1-1",
+            end = start + 1000000,
+        )
+    }
+
+    fn full_context_labels(labels: &[(&str, &str, &str, &str)]) -> Vec<HtsLabel> {
+        let text = labels
+            .iter()
+            .enumerate()
+            .map(|(i, &(phoneme, a2, f1, f2))| {
+                full_context_label(i as u64 * 1000000, phoneme, a2, f1, f2)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        parse_hts_labels(&text).unwrap()
+    }
+
+    #[rstest]
+    fn label_accent_phrases_works_with_full_context_labels() {
+        let labels = full_context_labels(&[
+            ("sil", "xx", "xx", "xx"),
+            ("k", "1", "1", "1"),
+            ("o", "1", "1", "1"),
+            ("sil", "xx", "xx", "xx"),
+        ]);
+        let LabelAccentPhrases {
+            accent_phrases,
+            missing_accent_indices,
+            ..
+        } = label_accent_phrases(&labels, None).unwrap();
+        assert_eq!(missing_accent_indices, Vec::<usize>::new());
+        assert_eq!(accent_phrases.len(), 1);
+        assert_eq!(*accent_phrases[0].accent(), 1);
+        assert_eq!(accent_phrases[0].moras()[0].text(), "コ");
+    }
+
+    #[rstest]
+    #[case(
+        &[("sil", "xx", "xx", "xx"), ("k", "1", "1", "1"), ("o", "1", "1", "1")],
+        HtsLabelError::MissingTrailingSilence { line: 3 }
+    )]
+    #[case(
+        &[("sil", "xx", "xx", "xx"), ("o", "49", "1", "1"), ("sil", "xx", "xx", "xx")],
+        HtsLabelError::FullContextLabel(FullContextLabelError::EmptyAccentPhrase)
+    )]
+    fn label_accent_phrases_fails_with_full_context_labels(
+        #[case] labels: &[(&str, &str, &str, &str)],
+        #[case] expected: HtsLabelError,
+    ) {
+        let labels = full_context_labels(labels);
+        assert_eq!(label_accent_phrases(&labels, None).err().unwrap(), expected);
+    }
+
+    #[rstest]
+    fn label_accent_phrases_checks_pitch_count() {
+        let labels = parse_hts_labels(MONO_LABELS).unwrap();
+        assert_eq!(
+            label_accent_phrases(&labels, Some(&[0.])).err().unwrap(),
+            HtsLabelError::PitchCountMismatch {
+                expected: 8,
+                actual: 1
+            }
+        );
+    }
+}
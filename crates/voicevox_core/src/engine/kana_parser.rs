@@ -1,21 +1,101 @@
-use crate::engine::model::{AccentPhraseModel, MoraModel};
+use crate::engine::model::{AccentPhraseModel, MoraModel, TextSpan};
 use crate::engine::mora_list::MORA_LIST_MINIMUM;
+use derive_getters::Getters;
 use once_cell::sync::Lazy;
+use serde::Serialize;
 use std::collections::HashMap;
 
 const UNVOICE_SYMBOL: char = '_';
 const ACCENT_SYMBOL: char = '\'';
 const NOPAUSE_DELIMITER: char = '/';
 const PAUSE_DELIMITER: char = '、';
+const PERIOD_PAUSE_DELIMITER: char = '。';
 const WIDE_INTERROGATION_MARK: char = '？';
+const PAUSE_LENGTH_START: char = '[';
+const PAUSE_LENGTH_END: char = ']';
+const PAUSE_LENGTH_UNIT: &str = "ms";
 const LOOP_LIMIT: usize = 300;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct KanaParseError(String);
+const HALFWIDTH_KATAKANA: &str = "ｦｧｨｩｪｫｬｭｮｯｰｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝ";
+const FULLWIDTH_KATAKANA: &str = "ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン";
+/// 濁点を付けると、文字コードが1つ後の文字になるカタカナ
+const VOICEABLE_KATAKANA: &str = "カキクケコサシスセソタチツテトハヒフヘホ";
+/// 半濁点を付けると、文字コードが2つ後の文字になるカタカナ
+const SEMI_VOICEABLE_KATAKANA: &str = "ハヒフヘホ";
+const VOICED_SOUND_MARKS: &[char] = &['ﾞ', '゛'];
+const SEMI_VOICED_SOUND_MARKS: &[char] = &['ﾟ', '゜'];
+
+/// kanaの解析エラーの種類
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KanaParseErrorKind {
+    /// アクセント句の先頭にアクセントがある
+    AccentAtBeginning,
+    /// 一つのアクセント句に複数のアクセントがある
+    MultipleAccents,
+    /// アクセント句にアクセントがない
+    AccentNotFound,
+    /// 解釈できない文字がある
+    UnknownText,
+    /// 空のアクセント句がある
+    EmptyAccentPhrase,
+    /// 疑問符がアクセント句の末尾以外にある
+    MisplacedInterrogativeMark,
+    /// ポーズの長さの表記が不正
+    InvalidPauseLength,
+    /// ポーズの長さの括弧が閉じられていない
+    UnclosedPauseLength,
+    /// アクセント句が長すぎる
+    TooLongAccentPhrase,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Getters)]
+pub struct KanaParseError {
+    kind: KanaParseErrorKind,
+    message: String,
+    /// 入力のうちエラーとなった箇所の、文字単位の開始位置
+    offset: usize,
+    /// 入力のうちエラーとなった箇所の文字数
+    length: usize,
+    /// エラーとなったアクセント句の番号
+    accent_phrase_index: usize,
+}
+
+impl KanaParseError {
+    fn new(kind: KanaParseErrorKind, message: String, offset: usize, length: usize) -> Self {
+        Self {
+            kind,
+            message,
+            offset,
+            length,
+            accent_phrase_index: 0,
+        }
+    }
+
+    /// アクセント句内の位置を、正規化したkana全体での位置に直す
+    fn in_accent_phrase(self, phrase_start: usize, accent_phrase_index: usize) -> Self {
+        Self {
+            offset: phrase_start + self.offset,
+            accent_phrase_index,
+            ..self
+        }
+    }
+
+    /// 正規化したkanaでの位置を、元の入力での位置に直す
+    fn in_original_text(self, original_offsets: &[usize]) -> Self {
+        let offset = original_offsets[self.offset];
+        let end = original_offsets[self.offset + self.length];
+        Self {
+            offset,
+            length: end - offset,
+            ..self
+        }
+    }
+}
 
 impl std::fmt::Display for KanaParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Parse Error: {}", self.0)
+        write!(f, "Parse Error: {}", self.message)
     }
 }
 
@@ -59,8 +139,96 @@ static TEXT2MORA_WITH_UNVOICE: Lazy<HashMap<String, MoraModel>> = Lazy::new(|| {
     text2mora_with_unvoice
 });
 
-fn text_to_accent_phrase(phrase: &str) -> KanaParseResult<AccentPhraseModel> {
-    let phrase_vec: Vec<char> = phrase.chars().collect();
+static KANA_NORMALIZATION: Lazy<HashMap<char, char>> = Lazy::new(|| {
+    let mut kana_normalization: HashMap<char, char> = HALFWIDTH_KATAKANA
+        .chars()
+        .zip(FULLWIDTH_KATAKANA.chars())
+        .collect();
+    for (alternative, punctuation) in [
+        ('?', WIDE_INTERROGATION_MARK),
+        (',', PAUSE_DELIMITER),
+        ('，', PAUSE_DELIMITER),
+        ('､', PAUSE_DELIMITER),
+        ('.', PERIOD_PAUSE_DELIMITER),
+        ('．', PERIOD_PAUSE_DELIMITER),
+        ('｡', PERIOD_PAUSE_DELIMITER),
+    ] {
+        kana_normalization.insert(alternative, punctuation);
+    }
+    kana_normalization
+});
+
+/// ひらがなと半角カタカナを全角カタカナに、句読点と疑問符の別表記をkanaで用いる記号に置き換える
+///
+/// 正規化後の各文字の元の入力での位置も返す。末尾には元の入力の文字数が入る
+fn normalize_kana(text: &str) -> (String, Vec<usize>) {
+    let mut normalized = String::with_capacity(text.len());
+    let mut original_offsets = Vec::new();
+    let mut in_pause_length = false;
+    let mut original_length = 0;
+    for (offset, letter) in text.chars().enumerate() {
+        original_length = offset + 1;
+        if in_pause_length {
+            in_pause_length = letter != PAUSE_LENGTH_END;
+            normalized.push(letter);
+            original_offsets.push(offset);
+            continue;
+        }
+        let composed = if VOICED_SOUND_MARKS.contains(&letter) {
+            normalized
+                .chars()
+                .last()
+                .and_then(|last| compose_sound_mark(last, VOICEABLE_KATAKANA, 1))
+        } else if SEMI_VOICED_SOUND_MARKS.contains(&letter) {
+            normalized
+                .chars()
+                .last()
+                .and_then(|last| compose_sound_mark(last, SEMI_VOICEABLE_KATAKANA, 2))
+        } else {
+            None
+        };
+        if let Some(composed) = composed {
+            normalized.pop();
+            normalized.push(composed);
+            continue;
+        }
+        match letter {
+            PAUSE_LENGTH_START => {
+                in_pause_length = true;
+                normalized.push(letter);
+            }
+            'ぁ'..='ゖ' => normalized
+                .push(char::from_u32(letter as u32 + ('ァ' as u32 - 'ぁ' as u32)).unwrap()),
+            _ => normalized.push(*KANA_NORMALIZATION.get(&letter).unwrap_or(&letter)),
+        }
+        original_offsets.push(offset);
+    }
+    original_offsets.push(original_length);
+    (normalized, original_offsets)
+}
+
+/// 濁点か半濁点を合成した文字を返す。合成できないときは`None`を返す
+fn compose_sound_mark(letter: char, targets: &str, offset: u32) -> Option<char> {
+    if targets.contains(letter) {
+        char::from_u32(letter as u32 + offset)
+    } else if letter == 'ウ' && offset == 1 {
+        Some('ヴ')
+    } else {
+        None
+    }
+}
+
+/// `allow_missing_accent`が`true`のとき、アクセントの指定がないアクセント句は平板型とする
+///
+/// `original_offsets`はアクセント句の各文字の元の入力での位置で、モーラの範囲に用いる
+fn text_to_accent_phrase(
+    phrase_vec: &[char],
+    allow_missing_accent: bool,
+    original_offsets: &[usize],
+) -> KanaParseResult<AccentPhraseModel> {
+    use KanaParseErrorKind::*;
+
+    let phrase = phrase_vec.iter().collect::<String>();
     let mut accent_index: Option<usize> = None;
     let mut moras: Vec<MoraModel> = Vec::new();
     let mut stack = String::new();
@@ -73,14 +241,20 @@ fn text_to_accent_phrase(phrase: &str) -> KanaParseResult<AccentPhraseModel> {
         let letter = phrase_vec[index];
         if letter == ACCENT_SYMBOL {
             if index == 0 {
-                return Err(KanaParseError(format!(
-                    "accent cannot be set at beginning of accent phrase: {phrase}"
-                )));
+                return Err(KanaParseError::new(
+                    AccentAtBeginning,
+                    format!("accent cannot be set at beginning of accent phrase: {phrase}"),
+                    index,
+                    1,
+                ));
             }
             if accent_index.is_some() {
-                return Err(KanaParseError(format!(
-                    "second accent cannot be set at an accent phrase: {phrase}"
-                )));
+                return Err(KanaParseError::new(
+                    MultipleAccents,
+                    format!("second accent cannot be set at an accent phrase: {phrase}"),
+                    index,
+                    1,
+                ));
             }
             accent_index = Some(moras.len());
             index += 1;
@@ -97,22 +271,42 @@ fn text_to_accent_phrase(phrase: &str) -> KanaParseResult<AccentPhraseModel> {
             }
         }
         if let Some(matched_text) = matched_text.take() {
+            let mora_start = index;
             index += matched_text.chars().count();
-            moras.push(text2mora.get(&matched_text).unwrap().clone());
+            let mut mora = text2mora.get(&matched_text).unwrap().clone();
+            mora.set_source_span(Some(TextSpan {
+                start: original_offsets[mora_start],
+                end: original_offsets[index],
+            }));
+            moras.push(mora);
             stack.clear();
         } else {
-            return Err(KanaParseError(format!(
-                "unknown text in accent phrase: {phrase}"
-            )));
+            return Err(KanaParseError::new(
+                UnknownText,
+                format!("unknown text in accent phrase: {phrase}"),
+                index,
+                1,
+            ));
         }
         if loop_count > LOOP_LIMIT {
-            return Err(KanaParseError("detected infinity loop!".to_string()));
+            return Err(KanaParseError::new(
+                TooLongAccentPhrase,
+                format!("accent phrase is too long: {phrase}"),
+                0,
+                phrase_vec.len(),
+            ));
         }
     }
+    if accent_index.is_none() && allow_missing_accent {
+        accent_index = Some(moras.len());
+    }
     if accent_index.is_none() {
-        return Err(KanaParseError(format!(
-            "accent not found in accent phrase: {phrase}"
-        )));
+        return Err(KanaParseError::new(
+            AccentNotFound,
+            format!("accent not found in accent phrase: {phrase}"),
+            0,
+            phrase_vec.len(),
+        ));
     }
     Ok(AccentPhraseModel::new(
         moras,
@@ -123,56 +317,176 @@ fn text_to_accent_phrase(phrase: &str) -> KanaParseResult<AccentPhraseModel> {
 }
 
 pub fn parse_kana(text: &str) -> KanaParseResult<Vec<AccentPhraseModel>> {
-    const TERMINATOR: char = '\0';
+    let (normalized_text, original_offsets) = normalize_kana(text);
+    parse_normalized_kana(
+        &normalized_text.chars().collect::<Vec<_>>(),
+        &original_offsets,
+        false,
+    )
+    .map(|(accent_phrases, _)| accent_phrases)
+    .map_err(|error| error.in_original_text(&original_offsets))
+}
+
+/// アクセントの指定がないアクセント句を許容してkanaを解析する
+///
+/// アクセントの指定がないアクセント句は平板型とし、それらのアクセント句の番号を合わせて返す
+pub fn parse_kana_lenient(text: &str) -> KanaParseResult<(Vec<AccentPhraseModel>, Vec<usize>)> {
+    let (normalized_text, original_offsets) = normalize_kana(text);
+    parse_normalized_kana(
+        &normalized_text.chars().collect::<Vec<_>>(),
+        &original_offsets,
+        true,
+    )
+    .map_err(|error| error.in_original_text(&original_offsets))
+}
+
+fn parse_normalized_kana(
+    letters: &[char],
+    original_offsets: &[usize],
+    allow_missing_accent: bool,
+) -> KanaParseResult<(Vec<AccentPhraseModel>, Vec<usize>)> {
+    use KanaParseErrorKind::*;
+
     let mut parsed_result = Vec::new();
-    let chars_of_text = text.chars().chain([TERMINATOR]);
-    let mut phrase = String::new();
-    for letter in chars_of_text {
-        if letter == TERMINATOR || letter == PAUSE_DELIMITER || letter == NOPAUSE_DELIMITER {
-            if phrase.is_empty() {
-                return Err(KanaParseError(format!(
-                    "accent phrase at position of {} is empty",
-                    parsed_result.len()
-                )));
-            }
-            let is_interrogative = phrase.contains(WIDE_INTERROGATION_MARK);
-            if is_interrogative {
-                if phrase.find(WIDE_INTERROGATION_MARK).unwrap()
-                    != phrase.len() - WIDE_INTERROGATION_MARK.len_utf8()
-                {
-                    return Err(KanaParseError(format!(
-                        "interrogative mark cannot be set at not end of accent phrase: {phrase}"
-                    )));
-                }
-                phrase.pop(); // remove WIDE_INTERROGATION_MARK
+    let mut missing_accent_indices = Vec::new();
+    let mut phrase_start = 0;
+    let mut index = 0;
+    while index <= letters.len() {
+        // 末尾に達したときは`None`とし、区切りとして扱う
+        let letter = letters.get(index).copied();
+        let is_pause = letter == Some(PAUSE_DELIMITER) || letter == Some(PERIOD_PAUSE_DELIMITER);
+        if !(letter.is_none() || is_pause || letter == Some(NOPAUSE_DELIMITER)) {
+            index += 1;
+            continue;
+        }
+
+        let accent_phrase_index = parsed_result.len();
+        let mut phrase = &letters[phrase_start..index];
+        if phrase.is_empty() {
+            return Err(KanaParseError::new(
+                EmptyAccentPhrase,
+                format!("accent phrase at position of {accent_phrase_index} is empty"),
+                index,
+                letter.is_some() as usize,
+            )
+            .in_accent_phrase(0, accent_phrase_index));
+        }
+        let phrase_span = TextSpan {
+            start: original_offsets[phrase_start],
+            end: original_offsets[index],
+        };
+        let is_interrogative = phrase.contains(&WIDE_INTERROGATION_MARK);
+        if is_interrogative {
+            let mark_index = phrase
+                .iter()
+                .position(|&letter| letter == WIDE_INTERROGATION_MARK)
+                .unwrap();
+            if mark_index != phrase.len() - 1 {
+                return Err(KanaParseError::new(
+                    MisplacedInterrogativeMark,
+                    format!(
+                        "interrogative mark cannot be set at not end of accent phrase: {}",
+                        phrase.iter().collect::<String>()
+                    ),
+                    mark_index,
+                    1,
+                )
+                .in_accent_phrase(phrase_start, accent_phrase_index));
             }
-            let accent_phrase = {
-                let mut accent_phrase = text_to_accent_phrase(&phrase)?;
-                if letter == PAUSE_DELIMITER {
-                    accent_phrase.set_pause_mora(Some(MoraModel::new(
-                        PAUSE_DELIMITER.to_string(),
-                        None,
-                        None,
-                        "pau".to_string(),
-                        0.,
-                        0.,
-                    )));
-                }
-                accent_phrase.set_is_interrogative(is_interrogative);
-                accent_phrase
-            };
-            parsed_result.push(accent_phrase);
-            phrase.clear();
+            phrase = &phrase[..mark_index]; // remove WIDE_INTERROGATION_MARK
+        }
+        let mut accent_phrase = text_to_accent_phrase(
+            phrase,
+            allow_missing_accent,
+            &original_offsets[phrase_start..],
+        )
+        .map_err(|error| error.in_accent_phrase(phrase_start, accent_phrase_index))?;
+        if !phrase.contains(&ACCENT_SYMBOL) {
+            missing_accent_indices.push(accent_phrase_index);
+        }
+        let delimiter_index = index;
+        index += 1;
+
+        let explicit_pause_length = if is_pause && letters.get(index) == Some(&PAUSE_LENGTH_START) {
+            let pause_length_start = index;
+            let pause_length_end = letters[pause_length_start..]
+                .iter()
+                .position(|&letter| letter == PAUSE_LENGTH_END)
+                .map(|position| pause_length_start + position)
+                .ok_or_else(|| {
+                    KanaParseError::new(
+                        UnclosedPauseLength,
+                        format!(
+                            "pause length is not closed: {}",
+                            letters[pause_length_start..].iter().collect::<String>()
+                        ),
+                        pause_length_start,
+                        letters.len() - pause_length_start,
+                    )
+                    .in_accent_phrase(0, accent_phrase_index)
+                })?;
+            index = pause_length_end + 1;
+            let pause_length = &letters[pause_length_start..index];
+            let explicit_pause_length = parse_pause_length(
+                &pause_length[1..pause_length.len() - 1]
+                    .iter()
+                    .collect::<String>(),
+            )
+            .ok_or_else(|| {
+                KanaParseError::new(
+                    InvalidPauseLength,
+                    format!(
+                        "invalid pause length: {}",
+                        pause_length.iter().collect::<String>()
+                    ),
+                    pause_length_start,
+                    pause_length.len(),
+                )
+                .in_accent_phrase(0, accent_phrase_index)
+            })?;
+            Some(explicit_pause_length)
         } else {
-            phrase.push(letter);
+            None
+        };
+
+        if let Some(letter) = letter.filter(|_| is_pause) {
+            let mut pause_mora = MoraModel::new(
+                PAUSE_DELIMITER.to_string(),
+                None,
+                None,
+                "pau".to_string(),
+                explicit_pause_length.unwrap_or(0.),
+                0.,
+            );
+            // 句読点とポーズの長さの表記を合わせた範囲とする
+            pause_mora.set_source_span(Some(TextSpan {
+                start: original_offsets[delimiter_index],
+                end: original_offsets[index],
+            }));
+            accent_phrase.set_pause_mora(Some(pause_mora));
+            accent_phrase.set_pause_punctuation(Some(letter.to_string()));
         }
+        accent_phrase.set_is_interrogative(is_interrogative);
+        accent_phrase.set_source_span(Some(phrase_span));
+        accent_phrase.set_is_pause_length_explicit(explicit_pause_length.is_some());
+        parsed_result.push(accent_phrase);
+        phrase_start = index;
     }
-    Ok(parsed_result)
+    Ok((parsed_result, missing_accent_indices))
+}
+
+/// `300ms`のようなポーズの長さを秒数に変換する
+fn parse_pause_length(pause_length: &str) -> Option<f32> {
+    pause_length
+        .strip_suffix(PAUSE_LENGTH_UNIT)
+        .and_then(|milliseconds| milliseconds.parse::<f32>().ok())
+        .filter(|milliseconds| milliseconds.is_finite() && *milliseconds >= 0.)
+        .map(|milliseconds| milliseconds / 1000.)
 }
 
 pub fn create_kana(accent_phrases: &[AccentPhraseModel]) -> String {
     let mut text = String::new();
-    for phrase in accent_phrases {
+    for (phrase_index, phrase) in accent_phrases.iter().enumerate() {
         let moras = phrase.moras();
         for (index, mora) in moras.iter().enumerate() {
             if ["A", "E", "I", "O", "U"].contains(&(*mora.vowel()).as_ref()) {
@@ -186,13 +500,29 @@ pub fn create_kana(accent_phrases: &[AccentPhraseModel]) -> String {
         if *phrase.is_interrogative() {
             text.push(WIDE_INTERROGATION_MARK);
         }
-        text.push(if phrase.pause_mora().is_some() {
-            PAUSE_DELIMITER
+        if phrase_index + 1 == accent_phrases.len() {
+            break; // 最後のアクセント句の後には区切りを付けない
+        }
+        if let Some(pause_mora) = phrase.pause_mora() {
+            let is_period = matches!(
+                phrase.pause_punctuation(),
+                Some(punctuation) if punctuation.starts_with(PERIOD_PAUSE_DELIMITER)
+            );
+            text.push(if is_period {
+                PERIOD_PAUSE_DELIMITER
+            } else {
+                PAUSE_DELIMITER
+            });
+            if *phrase.is_pause_length_explicit() {
+                let milliseconds = (pause_mora.vowel_length() * 1000. * 1000.).round() / 1000.;
+                text.push_str(&format!(
+                    "{PAUSE_LENGTH_START}{milliseconds}{PAUSE_LENGTH_UNIT}{PAUSE_LENGTH_END}"
+                ));
+            }
         } else {
-            NOPAUSE_DELIMITER
-        });
+            text.push(NOPAUSE_DELIMITER);
+        }
     }
-    text.pop(); // remove last delimiter
     text
 }
 
@@ -237,7 +567,9 @@ mod tests {
     #[case("'アクセントハジマリ", false)]
     #[case("不明な'文字", false)]
     fn test_text_to_accent_phrase(#[case] text: &str, #[case] result_is_ok_expected: bool) {
-        let result = text_to_accent_phrase(text);
+        let letters = text.chars().collect::<Vec<_>>();
+        let original_offsets = (0..=letters.len()).collect::<Vec<_>>();
+        let result = text_to_accent_phrase(&letters, false, &original_offsets);
         assert_eq!(result.is_ok(), result_is_ok_expected, "{:?}", result);
     }
 
@@ -245,15 +577,167 @@ mod tests {
     #[case("テ'ス_ト/テ_ス'ト、_テ'_スト？/テ'ス_ト？", true)]
     #[case("クウハクノ'//フレーズ'", false)]
     #[case("フレー？ズノ'/トチュウニ'、ギモ'ンフ", false)]
+    #[case("て'す_と/ﾃ_ｽ'ﾄ,_テ'_スト?/ﾃﾞ'ｽ_ト?", true)]
+    #[case("テ'スト、[300ms]テ'スト。[0.5ms]テ'スト", true)]
+    #[case("テ'スト/[300ms]テ'スト", false)]
+    #[case("テ'スト、[300]テ'スト", false)]
+    #[case("テ'スト、[-1ms]テ'スト", false)]
+    #[case("テ'スト、[300ms", false)]
     fn test_parse_kana(#[case] text: &str, #[case] result_is_ok_expected: bool) {
         let result = parse_kana(text);
         assert_eq!(result.is_ok(), result_is_ok_expected, "{:?}", result);
     }
+
+    #[rstest]
+    #[case("'テスト", KanaParseErrorKind::AccentAtBeginning, 0, 1, 0)]
+    #[case("テ'スト/テ'ス'ト", KanaParseErrorKind::MultipleAccents, 8, 1, 1)]
+    #[case("テ'スト、テスト", KanaParseErrorKind::AccentNotFound, 5, 3, 1)]
+    #[case("テ'スト/テ'ス不", KanaParseErrorKind::UnknownText, 8, 1, 1)]
+    #[case("ﾃﾞ'ｽﾄ/ﾃﾞ'ｽ不", KanaParseErrorKind::UnknownText, 10, 1, 1)]
+    #[case("テ'スト//テ'スト", KanaParseErrorKind::EmptyAccentPhrase, 5, 1, 1)]
+    #[case("テ'スト/", KanaParseErrorKind::EmptyAccentPhrase, 5, 0, 1)]
+    #[case("テ？'スト", KanaParseErrorKind::MisplacedInterrogativeMark, 1, 1, 0)]
+    #[case(
+        "テ'スト、[300]テ'スト",
+        KanaParseErrorKind::InvalidPauseLength,
+        5,
+        5,
+        0
+    )]
+    #[case("テ'スト、[300ms", KanaParseErrorKind::UnclosedPauseLength, 5, 6, 0)]
+    fn test_parse_kana_error(
+        #[case] text: &str,
+        #[case] kind: KanaParseErrorKind,
+        #[case] offset: usize,
+        #[case] length: usize,
+        #[case] accent_phrase_index: usize,
+    ) {
+        let error = parse_kana(text).unwrap_err();
+        assert_eq!(
+            (
+                *error.kind(),
+                *error.offset(),
+                *error.length(),
+                *error.accent_phrase_index()
+            ),
+            (kind, offset, length, accent_phrase_index),
+        );
+    }
+
+    #[rstest]
+    fn test_parse_kana_too_long_accent_phrase() {
+        let text = "ア'".to_string() + &"ア".repeat(LOOP_LIMIT);
+        let error = parse_kana(&text).unwrap_err();
+        assert_eq!(*error.kind(), KanaParseErrorKind::TooLongAccentPhrase);
+        assert_eq!(*error.length(), text.chars().count());
+    }
+
+    #[rstest]
+    #[case("ア_シタ'ワ/ユウヒガ、_キレイ？", &[1, 2], &[3, 4, 3])]
+    #[case("アシタワ", &[0], &[4])]
+    #[case("ア'シタワ", &[], &[1])]
+    fn test_parse_kana_lenient(
+        #[case] text: &str,
+        #[case] expected_missing_accent_indices: &[usize],
+        #[case] expected_accents: &[usize],
+    ) {
+        let (accent_phrases, missing_accent_indices) = parse_kana_lenient(text).unwrap();
+        assert_eq!(missing_accent_indices, expected_missing_accent_indices);
+        assert_eq!(
+            accent_phrases
+                .iter()
+                .map(|accent_phrase| *accent_phrase.accent())
+                .collect::<Vec<_>>(),
+            expected_accents,
+        );
+    }
+
+    #[rstest]
+    fn test_parse_kana_lenient_keeps_other_errors() {
+        let error = parse_kana_lenient("アシタワ/'ユウヒガ").unwrap_err();
+        assert_eq!(*error.kind(), KanaParseErrorKind::AccentAtBeginning);
+    }
+
+    #[rstest]
+    fn test_parse_kana_source_spans() {
+        let span = |start, end| Some(TextSpan { start, end });
+        let accent_phrases = parse_kana("ｶﾞｯｺ'ｳ/あ'め、[300ms]ア'メ？").unwrap();
+
+        let mora_spans = accent_phrases
+            .iter()
+            .map(|accent_phrase| {
+                accent_phrase
+                    .moras()
+                    .iter()
+                    .map(|mora| *mora.source_span())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            mora_spans,
+            [
+                vec![span(0, 2), span(2, 3), span(3, 4), span(5, 6)],
+                vec![span(7, 8), span(9, 10)],
+                vec![span(18, 19), span(20, 21)],
+            ]
+        );
+        assert_eq!(
+            accent_phrases
+                .iter()
+                .map(|accent_phrase| *accent_phrase.source_span())
+                .collect::<Vec<_>>(),
+            [span(0, 6), span(7, 10), span(18, 22)]
+        );
+        assert_eq!(
+            *accent_phrases[1]
+                .pause_mora()
+                .as_ref()
+                .unwrap()
+                .source_span(),
+            span(10, 18)
+        );
+    }
+
+    #[rstest]
+    #[case("あしたわ", "アシタワ")]
+    #[case("ｱﾝﾄﾞﾛｲﾄﾞ", "アンドロイド")]
+    #[case("ｳﾞｧｲｵﾘﾝ", "ヴァイオリン")]
+    #[case("ﾊﾟﾝﾄﾞﾗ", "パンドラ")]
+    #[case("ﾞア", "ﾞア")]
+    #[case("テ'スト,テ'スト.テ'スト?", "テ'スト、テ'スト。テ'スト？")]
+    #[case("テ'スト、[0.5ms]テ'スト", "テ'スト、[0.5ms]テ'スト")]
+    fn test_normalize_kana(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(normalize_kana(text).0, expected);
+    }
+
+    #[rstest]
+    fn test_parse_kana_pause() {
+        let phrases = parse_kana("テ'スト、[300ms]テ'スト。テ'スト").unwrap();
+        assert_eq!(phrases[0].pause_mora().as_ref().unwrap().text(), "、");
+        assert_eq!(*phrases[0].pause_punctuation(), Some("、".into()));
+        assert!(*phrases[0].is_pause_length_explicit());
+        assert_eq!(
+            *phrases[0].pause_mora().as_ref().unwrap().vowel_length(),
+            0.3
+        );
+        assert_eq!(phrases[1].pause_mora().as_ref().unwrap().text(), "、");
+        assert_eq!(*phrases[1].pause_punctuation(), Some("。".into()));
+        assert!(!*phrases[1].is_pause_length_explicit());
+        assert!(phrases[2].pause_mora().is_none());
+    }
+
     #[rstest]
-    fn test_create_kana() {
-        let text = "アンドロ'イドワ、デンキ'/ヒ'_ツジノ/ユメ'オ/ミ'ルカ？";
+    #[case("アンドロ'イドワ、デンキ'/ヒ'_ツジノ/ユメ'オ/ミ'ルカ？")]
+    #[case("テ'スト、[300ms]テ'スト。[12.5ms]テ'スト？、テ'スト")]
+    fn test_create_kana(#[case] text: &str) {
         let phrases = parse_kana(text).unwrap();
         let text_created = create_kana(&phrases);
         assert_eq!(text, &text_created);
     }
+
+    #[rstest]
+    fn test_create_kana_normalized() {
+        let phrases = parse_kana("あんどろ'いどわ,ﾃﾞﾝｷ'?").unwrap();
+        assert_eq!(create_kana(&phrases), "アンドロ'イドワ、デンキ'？");
+    }
 }
@@ -1,16 +1,38 @@
+mod accent_phrase_edit;
 mod acoustic_feature_extractor;
+mod duration_fit;
+mod frame_pitch;
 mod full_context_label;
+mod hts_label;
 mod kana_parser;
 mod model;
 mod mora_list;
 mod open_jtalk;
+mod pitch_shift;
+mod prosody_preset;
+mod prosody_variation;
+mod score;
+mod source_span;
 mod synthesis_engine;
+mod synthesis_session;
+mod vibrato;
+mod wave;
 
 use super::*;
 
+pub use self::accent_phrase_edit::*;
 pub use self::acoustic_feature_extractor::*;
+pub use self::duration_fit::*;
+pub use self::frame_pitch::*;
 pub use self::full_context_label::*;
+pub use self::hts_label::*;
 pub use self::kana_parser::*;
 pub use self::model::*;
 pub use self::open_jtalk::OpenJtalk;
+pub use self::prosody_preset::*;
+pub use self::prosody_variation::*;
+pub use self::score::*;
 pub use self::synthesis_engine::*;
+pub use self::synthesis_session::*;
+pub use self::vibrato::*;
+pub use self::wave::*;
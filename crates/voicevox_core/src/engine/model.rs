@@ -1,10 +1,74 @@
+use super::pitch_shift::{MAX_PITCH_SHIFT, TARGET_MEAN_F0_RANGE};
+use super::prosody_preset::ProsodyPreset;
+use super::prosody_variation::ProsodyVariation;
+use super::synthesis_engine::MORA_PHONEME_LIST;
+use super::vibrato::Vibrato;
 use derive_getters::Getters;
 use derive_new::new;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /* 各フィールドのjsonフィールド名はsnake_caseとする*/
 
-#[derive(Clone, Debug, new, Getters, Deserialize, Serialize)]
+/// 入力テキスト中の範囲。位置は文字単位で数え、`end`の位置の文字は含まない
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// モデルの値の検証エラー
+///
+/// `field`は検証したモデルからの値の位置を`accent_phrases[0].moras[1].pitch`のような形式で表す
+#[derive(thiserror::Error, Clone, Debug, PartialEq)]
+pub enum ModelValidationError {
+    #[error("accent phrase has no moras: {field}")]
+    EmptyAccentPhrase { field: String },
+    #[error("accent must be in 1..={mora_count}: {field} = {accent}")]
+    AccentOutOfRange {
+        field: String,
+        accent: usize,
+        mora_count: usize,
+    },
+    #[error("unknown vowel: {field} = {vowel:?}")]
+    UnknownVowel { field: String, vowel: String },
+    #[error("invalid value: {field} = {value}")]
+    InvalidValue { field: String, value: f32 },
+}
+
+type ValidationResult = std::result::Result<(), ModelValidationError>;
+
+/// `field`の値が有限で、`min`以上であることを検証する
+fn validate_value(field: impl FnOnce() -> String, value: f32, min: f32) -> ValidationResult {
+    if value.is_finite() && value >= min {
+        Ok(())
+    } else {
+        Err(ModelValidationError::InvalidValue {
+            field: field(),
+            value,
+        })
+    }
+}
+
+/// ビブラートの各値が0以上の有限の値であることを検証する
+fn validate_vibrato(path: &str, vibrato: &Vibrato) -> ValidationResult {
+    let field = |name: &str| format!("{path}{name}");
+    validate_value(|| field("rate"), vibrato.rate, 0.)?;
+    validate_value(|| field("depth"), vibrato.depth, 0.)?;
+    validate_value(|| field("delay"), vibrato.delay, 0.)?;
+    validate_value(|| field("min_length"), vibrato.min_length, 0.)?;
+    validate_value(|| field("jitter"), vibrato.jitter, 0.)
+}
+
+/// アクセント句の列の各値が音声合成に使える範囲にあることを検証する
+pub(crate) fn validate_accent_phrases(accent_phrases: &[AccentPhraseModel]) -> ValidationResult {
+    for (i, accent_phrase) in accent_phrases.iter().enumerate() {
+        accent_phrase.validate_at(&format!("accent_phrases[{i}]."))?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, new, Getters, Deserialize, Serialize)]
 pub struct MoraModel {
     text: String,
     consonant: Option<String>,
@@ -12,28 +76,220 @@ pub struct MoraModel {
     vowel: String,
     vowel_length: f32,
     pitch: f32,
+    /// このモーラの元になった入力テキスト中の範囲。漢字などはその読み全体で同じ範囲となる
+    #[new(default)]
+    #[serde(default)]
+    source_span: Option<TextSpan>,
+    /// 手動で編集した値を保持するために、音素の長さと音高を推論し直さないようにする
+    #[new(default)]
+    #[serde(default)]
+    is_locked: bool,
+    /// このモーラの母音に加えるビブラート。指定したときはAudioQueryの`vibrato`の代わりに用いる
+    #[new(default)]
+    #[serde(default)]
+    vibrato: Option<Vibrato>,
+}
+
+impl MoraModel {
+    pub fn set_text(&mut self, text: String) {
+        self.text = text;
+    }
+
+    pub fn set_consonant(&mut self, consonant: Option<String>) {
+        self.consonant = consonant;
+    }
+
+    pub fn set_consonant_length(&mut self, consonant_length: Option<f32>) {
+        self.consonant_length = consonant_length;
+    }
+
+    pub fn set_vowel(&mut self, vowel: String) {
+        self.vowel = vowel;
+    }
+
+    pub fn set_vowel_length(&mut self, vowel_length: f32) {
+        self.vowel_length = vowel_length;
+    }
+
+    pub fn set_pitch(&mut self, pitch: f32) {
+        self.pitch = pitch;
+    }
+
+    pub fn set_source_span(&mut self, source_span: Option<TextSpan>) {
+        self.source_span = source_span;
+    }
+
+    pub fn set_is_locked(&mut self, is_locked: bool) {
+        self.is_locked = is_locked;
+    }
+
+    pub fn set_vibrato(&mut self, vibrato: Option<Vibrato>) {
+        self.vibrato = vibrato;
+    }
+
+    /// 母音が既知の音素で、音素の長さと音高が0以上の有限の値であることを検証する
+    pub fn validate(&self) -> ValidationResult {
+        self.validate_at("", false)
+    }
+
+    fn validate_at(&self, path: &str, is_pause_mora: bool) -> ValidationResult {
+        let field = |name: &str| format!("{path}{name}");
+        let is_known_vowel = if is_pause_mora {
+            self.vowel == "pau"
+        } else {
+            MORA_PHONEME_LIST.contains(&self.vowel.as_str())
+        };
+        if !is_known_vowel {
+            return Err(ModelValidationError::UnknownVowel {
+                field: field("vowel"),
+                vowel: self.vowel.clone(),
+            });
+        }
+        if let Some(consonant_length) = self.consonant_length {
+            validate_value(|| field("consonant_length"), consonant_length, 0.)?;
+        }
+        validate_value(|| field("vowel_length"), self.vowel_length, 0.)?;
+        validate_value(|| field("pitch"), self.pitch, 0.)?;
+        if let Some(vibrato) = &self.vibrato {
+            validate_vibrato(&field("vibrato."), vibrato)?;
+        }
+        Ok(())
+    }
 }
 
-#[derive(Clone, Debug, new, Getters, Deserialize, Serialize)]
+/// アクセント句ごとに指定する話速、音高、抑揚、音量
+///
+/// 指定した値は、そのアクセント句でAudioQueryの同じ名前の値の代わりに用いる
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AccentPhraseProsody {
+    pub speed_scale: Option<f32>,
+    pub pitch_scale: Option<f32>,
+    pub intonation_scale: Option<f32>,
+    pub volume_scale: Option<f32>,
+}
+
+impl AccentPhraseProsody {
+    fn validate_at(&self, path: &str) -> ValidationResult {
+        let field = |name: &str| format!("{path}{name}");
+        if let Some(speed_scale) = self.speed_scale {
+            if !(speed_scale.is_finite() && speed_scale > 0.) {
+                return Err(ModelValidationError::InvalidValue {
+                    field: field("speed_scale"),
+                    value: speed_scale,
+                });
+            }
+        }
+        if let Some(pitch_scale) = self.pitch_scale {
+            validate_value(|| field("pitch_scale"), pitch_scale, f32::MIN)?;
+        }
+        if let Some(intonation_scale) = self.intonation_scale {
+            validate_value(|| field("intonation_scale"), intonation_scale, 0.)?;
+        }
+        if let Some(volume_scale) = self.volume_scale {
+            validate_value(|| field("volume_scale"), volume_scale, 0.)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, new, Getters, Deserialize, Serialize)]
 pub struct AccentPhraseModel {
     moras: Vec<MoraModel>,
     accent: usize,
     pause_mora: Option<MoraModel>,
     is_interrogative: bool,
+    /// ポーズの長さがkanaの`、[300ms]`のように明示されたか
+    ///
+    /// 明示された長さはポーズのモーラの母音の長さに設定し、音素の長さの推論やAudioQueryのポーズの長さの指定で置き換えない
+    #[new(default)]
+    #[serde(default)]
+    is_pause_length_explicit: bool,
+    /// ポーズの元になった入力テキスト中の句読点。ポーズのモーラの`text`は常に`"、"`となる
+    #[new(default)]
+    #[serde(default)]
+    pause_punctuation: Option<String>,
+    /// このアクセント句の元になった入力テキスト中の範囲
+    #[new(default)]
+    #[serde(default)]
+    source_span: Option<TextSpan>,
+    /// このアクセント句だけに用いる話速、音高、抑揚、音量
+    #[new(default)]
+    #[serde(default)]
+    prosody: AccentPhraseProsody,
 }
 
 impl AccentPhraseModel {
-    pub(super) fn set_pause_mora(&mut self, pause_mora: Option<MoraModel>) {
+    pub fn moras_mut(&mut self) -> &mut Vec<MoraModel> {
+        &mut self.moras
+    }
+
+    pub fn pause_mora_mut(&mut self) -> Option<&mut MoraModel> {
+        self.pause_mora.as_mut()
+    }
+
+    pub fn set_moras(&mut self, moras: Vec<MoraModel>) {
+        self.moras = moras;
+    }
+
+    pub fn set_accent(&mut self, accent: usize) {
+        self.accent = accent;
+    }
+
+    pub fn set_pause_mora(&mut self, pause_mora: Option<MoraModel>) {
         self.pause_mora = pause_mora;
     }
 
-    pub(super) fn set_is_interrogative(&mut self, is_interrogative: bool) {
+    pub fn set_is_interrogative(&mut self, is_interrogative: bool) {
         self.is_interrogative = is_interrogative;
     }
+
+    pub fn set_is_pause_length_explicit(&mut self, is_pause_length_explicit: bool) {
+        self.is_pause_length_explicit = is_pause_length_explicit;
+    }
+
+    pub fn set_pause_punctuation(&mut self, pause_punctuation: Option<String>) {
+        self.pause_punctuation = pause_punctuation;
+    }
+
+    pub fn set_source_span(&mut self, source_span: Option<TextSpan>) {
+        self.source_span = source_span;
+    }
+
+    pub fn set_prosody(&mut self, prosody: AccentPhraseProsody) {
+        self.prosody = prosody;
+    }
+
+    /// モーラが1つ以上あり、アクセント位置がモーラの範囲内にあることと、各モーラの値を検証する
+    pub fn validate(&self) -> ValidationResult {
+        self.validate_at("")
+    }
+
+    fn validate_at(&self, path: &str) -> ValidationResult {
+        if self.moras.is_empty() {
+            return Err(ModelValidationError::EmptyAccentPhrase {
+                field: format!("{path}moras"),
+            });
+        }
+        if !(1..=self.moras.len()).contains(&self.accent) {
+            return Err(ModelValidationError::AccentOutOfRange {
+                field: format!("{path}accent"),
+                accent: self.accent,
+                mora_count: self.moras.len(),
+            });
+        }
+        for (i, mora) in self.moras.iter().enumerate() {
+            mora.validate_at(&format!("{path}moras[{i}]."), false)?;
+        }
+        if let Some(pause_mora) = &self.pause_mora {
+            pause_mora.validate_at(&format!("{path}pause_mora."), true)?;
+        }
+        self.prosody.validate_at(&format!("{path}prosody."))
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
-#[derive(Clone, new, Getters, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, new, Getters, Deserialize, Serialize)]
 pub struct AudioQueryModel {
     accent_phrases: Vec<AccentPhraseModel>,
     speed_scale: f32,
@@ -45,6 +301,252 @@ pub struct AudioQueryModel {
     output_sampling_rate: u32,
     output_stereo: bool,
     kana: String,
+    /// 指定されたとき、kanaで長さを明示したポーズを除く全てのポーズの長さをこの秒数で置き換える
+    #[new(default)]
+    #[serde(default)]
+    pause_length: Option<f32>,
+    /// ポーズの長さに掛ける倍率
+    #[new(value = "1.")]
+    #[serde(default = "default_pause_length_scale")]
+    pause_length_scale: f32,
+    /// ポーズの元になった句読点ごとの、ポーズの秒数
+    #[new(default)]
+    #[serde(default)]
+    punctuation_pause_lengths: BTreeMap<String, f32>,
+    /// 長く伸ばした母音に加えるビブラート。`SynthesisSystem::V2`のモデルでのみ使え、それ以外のモデルでは無視する
+    #[new(default)]
+    #[serde(default)]
+    vibrato: Option<Vibrato>,
+    /// 有声の音高をずらす半音の数。1セントは0.01となる。-24以上24以下とする
+    #[new(default)]
+    #[serde(default)]
+    pitch_shift: f32,
+    /// 指定されたとき、有声のフレームの平均の基本周波数がこの値(Hz)になるように音高をずらす。50以上1000以下とする。
+    /// `pitch_scale`を適用した後の、音素の長さで重み付けした音高の平均を合わせ、その後に`pitch_shift`だけずらす
+    #[new(default)]
+    #[serde(default)]
+    target_mean_f0: Option<f32>,
+    /// 音声合成の前にアクセント句に適用する話し方の変換
+    #[new(default)]
+    #[serde(default)]
+    prosody_preset: Option<ProsodyPreset>,
+    /// 推論したモーラの長さと音高、ポーズの長さに加える、シードから再現できる揺らぎ
+    #[new(default)]
+    #[serde(default)]
+    prosody_variation: Option<ProsodyVariation>,
+}
+
+impl AudioQueryModel {
+    pub fn accent_phrases_mut(&mut self) -> &mut Vec<AccentPhraseModel> {
+        &mut self.accent_phrases
+    }
+
+    pub fn set_accent_phrases(&mut self, accent_phrases: Vec<AccentPhraseModel>) {
+        self.accent_phrases = accent_phrases;
+    }
+
+    pub fn set_speed_scale(&mut self, speed_scale: f32) {
+        self.speed_scale = speed_scale;
+    }
+
+    pub fn set_pitch_scale(&mut self, pitch_scale: f32) {
+        self.pitch_scale = pitch_scale;
+    }
+
+    pub fn set_intonation_scale(&mut self, intonation_scale: f32) {
+        self.intonation_scale = intonation_scale;
+    }
+
+    pub fn set_volume_scale(&mut self, volume_scale: f32) {
+        self.volume_scale = volume_scale;
+    }
+
+    pub fn set_pre_phoneme_length(&mut self, pre_phoneme_length: f32) {
+        self.pre_phoneme_length = pre_phoneme_length;
+    }
+
+    pub fn set_post_phoneme_length(&mut self, post_phoneme_length: f32) {
+        self.post_phoneme_length = post_phoneme_length;
+    }
+
+    pub fn set_output_sampling_rate(&mut self, output_sampling_rate: u32) {
+        self.output_sampling_rate = output_sampling_rate;
+    }
+
+    pub fn set_output_stereo(&mut self, output_stereo: bool) {
+        self.output_stereo = output_stereo;
+    }
+
+    pub fn set_kana(&mut self, kana: String) {
+        self.kana = kana;
+    }
+
+    pub fn set_pause_length(&mut self, pause_length: Option<f32>) {
+        self.pause_length = pause_length;
+    }
+
+    pub fn set_pause_length_scale(&mut self, pause_length_scale: f32) {
+        self.pause_length_scale = pause_length_scale;
+    }
+
+    pub fn set_punctuation_pause_lengths(
+        &mut self,
+        punctuation_pause_lengths: BTreeMap<String, f32>,
+    ) {
+        self.punctuation_pause_lengths = punctuation_pause_lengths;
+    }
+
+    pub fn set_vibrato(&mut self, vibrato: Option<Vibrato>) {
+        self.vibrato = vibrato;
+    }
+
+    pub fn set_pitch_shift(&mut self, pitch_shift: f32) {
+        self.pitch_shift = pitch_shift;
+    }
+
+    /// 有声の音高をずらす量をセント単位で設定する
+    pub fn set_pitch_shift_cents(&mut self, cents: f32) {
+        self.pitch_shift = cents / 100.;
+    }
+
+    pub fn set_target_mean_f0(&mut self, target_mean_f0: Option<f32>) {
+        self.target_mean_f0 = target_mean_f0;
+    }
+
+    pub fn set_prosody_preset(&mut self, prosody_preset: Option<ProsodyPreset>) {
+        self.prosody_preset = prosody_preset;
+    }
+
+    pub fn set_prosody_variation(&mut self, prosody_variation: Option<ProsodyVariation>) {
+        self.prosody_variation = prosody_variation;
+    }
+
+    /// 各アクセント句と、話速や音量などの値が音声合成に使える範囲にあることを検証する
+    ///
+    /// 音声合成や長さの調整はAudioQueryを検証せずに用いるため、外部から受け取ったAudioQueryは必要に応じてこの関数で検証する
+    pub fn validate(&self) -> ValidationResult {
+        validate_accent_phrases(&self.accent_phrases)?;
+        if !(self.speed_scale.is_finite() && self.speed_scale > 0.) {
+            return Err(ModelValidationError::InvalidValue {
+                field: "speed_scale".into(),
+                value: self.speed_scale,
+            });
+        }
+        validate_value(|| "pitch_scale".into(), self.pitch_scale, f32::MIN)?;
+        validate_value(|| "intonation_scale".into(), self.intonation_scale, 0.)?;
+        validate_value(|| "volume_scale".into(), self.volume_scale, 0.)?;
+        validate_value(|| "pre_phoneme_length".into(), self.pre_phoneme_length, 0.)?;
+        validate_value(
+            || "post_phoneme_length".into(),
+            self.post_phoneme_length,
+            0.,
+        )?;
+        if self.output_sampling_rate == 0 {
+            return Err(ModelValidationError::InvalidValue {
+                field: "output_sampling_rate".into(),
+                value: 0.,
+            });
+        }
+        if let Some(pause_length) = self.pause_length {
+            validate_value(|| "pause_length".into(), pause_length, 0.)?;
+        }
+        validate_value(|| "pause_length_scale".into(), self.pause_length_scale, 0.)?;
+        for (punctuation, &pause_length) in &self.punctuation_pause_lengths {
+            validate_value(
+                || format!("punctuation_pause_lengths[{punctuation:?}]"),
+                pause_length,
+                0.,
+            )?;
+        }
+        if let Some(vibrato) = &self.vibrato {
+            validate_vibrato("vibrato.", vibrato)?;
+        }
+        if !(-MAX_PITCH_SHIFT..=MAX_PITCH_SHIFT).contains(&self.pitch_shift) {
+            return Err(ModelValidationError::InvalidValue {
+                field: "pitch_shift".into(),
+                value: self.pitch_shift,
+            });
+        }
+        if let Some(target_mean_f0) = self.target_mean_f0 {
+            if !TARGET_MEAN_F0_RANGE.contains(&target_mean_f0) {
+                return Err(ModelValidationError::InvalidValue {
+                    field: "target_mean_f0".into(),
+                    value: target_mean_f0,
+                });
+            }
+        }
+        if let Some(prosody_variation) = &self.prosody_variation {
+            prosody_variation.validate_at("prosody_variation.")?;
+        }
+        Ok(())
+    }
+}
+
+/// 音声合成モデルを使わずにテキストを解析した結果
+#[derive(Clone, Debug, PartialEq, new, Getters, Deserialize, Serialize)]
+pub struct TextAnalysisModel {
+    /// 読みとアクセントのみを持つアクセント句。音素の長さと音高は全て0となる
+    accent_phrases: Vec<AccentPhraseModel>,
+    /// アクセント句から生成したAquesTalk風記法のテキスト
+    kana: String,
+}
+
+/// OpenJTalkのNJDのノード。1つの単語にあたる
+#[derive(Clone, Debug, PartialEq, new, Getters, Deserialize, Serialize)]
+pub struct NjdNodeModel {
+    /// 表層形
+    surface: String,
+    /// 品詞
+    part_of_speech: String,
+    /// 読み
+    reading: String,
+    /// 発音
+    pronunciation: String,
+    /// アクセント型。0は平板型
+    accent_type: usize,
+    /// モーラ数
+    mora_count: usize,
+    /// 前の単語と同じアクセント句にまとめられるか
+    chain_flag: bool,
+}
+
+fn default_pause_length_scale() -> f32 {
+    1.
+}
+
+/// 疑問文の語尾の抑揚の付け方
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpspeakContour {
+    /// 最後のモーラの母音を繰り返したモーラを追加し、その音高を上げる
+    #[default]
+    AppendMora,
+    /// モーラを追加せずに、最後のモーラの音高を上げる
+    RaiseLastMora,
+}
+
+/// 疑問文の語尾を上げる調整のパラメータ
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct InterrogativeUpspeak {
+    pub contour: UpspeakContour,
+    /// 語尾の母音の長さ。`RaiseLastMora`では最後のモーラの母音がこれより短いときのみ伸ばす
+    pub vowel_length: f32,
+    /// 語尾の音高に加える値
+    pub pitch_delta: f32,
+    /// 語尾の音高の上限
+    pub max_pitch: f32,
+}
+
+impl Default for InterrogativeUpspeak {
+    fn default() -> Self {
+        Self {
+            contour: UpspeakContour::default(),
+            vowel_length: 0.15,
+            pitch_delta: 0.3,
+            max_pitch: 6.5,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -61,6 +563,140 @@ mod tests {
         check_json_field_snake_case(&val);
     }
 
+    #[rstest]
+    fn check_text_analysis_model_json_field_snake_case() {
+        let text_analysis_model = TextAnalysisModel::new(vec![], "".into());
+        let val = serde_json::to_value(text_analysis_model).unwrap();
+        check_json_field_snake_case(&val);
+    }
+
+    #[rstest]
+    fn check_njd_node_model_json_field_snake_case() {
+        let njd_node_model =
+            NjdNodeModel::new("".into(), "".into(), "".into(), "".into(), 0, 0, false);
+        let val = serde_json::to_value(njd_node_model).unwrap();
+        check_json_field_snake_case(&val);
+    }
+
+    fn audio_query_model(accent_phrases: Vec<AccentPhraseModel>) -> AudioQueryModel {
+        AudioQueryModel::new(
+            accent_phrases,
+            1.,
+            0.,
+            1.,
+            1.,
+            0.1,
+            0.1,
+            48000,
+            false,
+            "".into(),
+        )
+    }
+
+    fn accent_phrase_model(accent: usize) -> AccentPhraseModel {
+        AccentPhraseModel::new(
+            vec![
+                MoraModel::new("ア".into(), None, None, "a".into(), 0.1, 5.),
+                MoraModel::new(
+                    "メ".into(),
+                    Some("m".into()),
+                    Some(0.05),
+                    "e".into(),
+                    0.1,
+                    5.,
+                ),
+            ],
+            accent,
+            Some(MoraModel::new(
+                "、".into(),
+                None,
+                None,
+                "pau".into(),
+                0.3,
+                0.,
+            )),
+            false,
+        )
+    }
+
+    #[rstest]
+    #[case(|_: &mut AudioQueryModel| {}, Ok(()))]
+    #[case(
+        |query: &mut AudioQueryModel| query.accent_phrases_mut()[0].set_accent(0),
+        Err(ModelValidationError::AccentOutOfRange {
+            field: "accent_phrases[0].accent".into(),
+            accent: 0,
+            mora_count: 2,
+        })
+    )]
+    #[case(
+        |query: &mut AudioQueryModel| query.accent_phrases_mut()[0].set_moras(vec![]),
+        Err(ModelValidationError::EmptyAccentPhrase {
+            field: "accent_phrases[0].moras".into(),
+        })
+    )]
+    #[case(
+        |query: &mut AudioQueryModel| query.accent_phrases_mut()[0].moras_mut()[1].set_pitch(f32::NAN),
+        Err(ModelValidationError::InvalidValue {
+            field: "accent_phrases[0].moras[1].pitch".into(),
+            value: f32::NAN,
+        })
+    )]
+    #[case(
+        |query: &mut AudioQueryModel| query.accent_phrases_mut()[0].moras_mut()[0].set_vowel("x".into()),
+        Err(ModelValidationError::UnknownVowel {
+            field: "accent_phrases[0].moras[0].vowel".into(),
+            vowel: "x".into(),
+        })
+    )]
+    #[case(
+        |query: &mut AudioQueryModel| query.accent_phrases_mut()[0].pause_mora_mut().unwrap().set_vowel("a".into()),
+        Err(ModelValidationError::UnknownVowel {
+            field: "accent_phrases[0].pause_mora.vowel".into(),
+            vowel: "a".into(),
+        })
+    )]
+    #[case(
+        |query: &mut AudioQueryModel| query.set_speed_scale(0.),
+        Err(ModelValidationError::InvalidValue {
+            field: "speed_scale".into(),
+            value: 0.,
+        })
+    )]
+    #[case(
+        |query: &mut AudioQueryModel| query.set_pitch_shift(-96.),
+        Err(ModelValidationError::InvalidValue {
+            field: "pitch_shift".into(),
+            value: -96.,
+        })
+    )]
+    #[case(
+        |query: &mut AudioQueryModel| query.set_target_mean_f0(Some(0.01)),
+        Err(ModelValidationError::InvalidValue {
+            field: "target_mean_f0".into(),
+            value: 0.01,
+        })
+    )]
+    #[case(
+        |query: &mut AudioQueryModel| query.set_pause_length(Some(-0.1)),
+        Err(ModelValidationError::InvalidValue {
+            field: "pause_length".into(),
+            value: -0.1,
+        })
+    )]
+    fn audio_query_model_validate_works(
+        #[case] edit: fn(&mut AudioQueryModel),
+        #[case] expected: std::result::Result<(), ModelValidationError>,
+    ) {
+        let mut query = audio_query_model(vec![accent_phrase_model(2)]);
+        edit(&mut query);
+        // NaNは等しくならないので、表示で比較する
+        assert_eq!(
+            query.validate().map_err(|e| e.to_string()),
+            expected.map_err(|e| e.to_string())
+        );
+    }
+
     fn check_json_field_snake_case(val: &serde_json::Value) {
         use serde_json::Value::*;
 
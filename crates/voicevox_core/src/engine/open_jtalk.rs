@@ -2,6 +2,8 @@ use std::path::{Path, PathBuf};
 
 use ::open_jtalk::*;
 
+use super::model::NjdNodeModel;
+
 #[derive(thiserror::Error, Debug)]
 pub enum OpenJtalkError {
     #[error("open_jtalk load error")]
@@ -71,18 +73,53 @@ impl OpenJtalk {
         result
     }
 
+    /// テキストをNJDまで解析し、NJDのノードを返す
+    pub fn extract_njd_nodes(&mut self, text: impl AsRef<str>) -> Result<Vec<NjdNodeModel>> {
+        let result = self.analyze_njd(text.as_ref()).map(|()| {
+            self.njd
+                .nodes()
+                .map(|node| {
+                    NjdNodeModel::new(
+                        node.string().into(),
+                        node.pos().into(),
+                        node.read().into(),
+                        node.pron().into(),
+                        node.acc().max(0) as usize,
+                        node.mora_size().max(0) as usize,
+                        node.chain_flag() == 1,
+                    )
+                })
+                .collect()
+        });
+        self.njd.refresh();
+        self.mecab.refresh();
+        result
+    }
+
     fn extract_fullcontext_non_reflesh(&mut self, text: impl AsRef<str>) -> Result<Vec<String>> {
-        let mecab_text =
-            text2mecab(text.as_ref()).map_err(|e| OpenJtalkError::ExtractFullContext {
+        self.analyze_njd(text.as_ref())?;
+        self.jpcommon.njd2jpcommon(&self.njd);
+        self.jpcommon.make_label();
+        self.jpcommon
+            .get_label_feature_to_iter()
+            .ok_or_else(|| OpenJtalkError::ExtractFullContext {
                 text: text.as_ref().into(),
-                source: Some(e.into()),
-            })?;
+                source: None,
+            })
+            .map(|iter| iter.map(|s| s.to_string()).collect())
+    }
+
+    fn analyze_njd(&mut self, text: &str) -> Result<()> {
+        let mecab_text = text2mecab(text).map_err(|e| OpenJtalkError::ExtractFullContext {
+            text: text.into(),
+            source: Some(e.into()),
+        })?;
         if self.mecab.analysis(mecab_text) {
             self.njd.mecab2njd(
                 self.mecab
                     .get_feature()
                     .ok_or(OpenJtalkError::ExtractFullContext {
-                        text: text.as_ref().into(),
+                        text: text.into(),
                         source: None,
                     })?,
                 self.mecab.get_size(),
@@ -93,18 +130,10 @@ impl OpenJtalk {
             self.njd.set_accent_type();
             self.njd.set_unvoiced_vowel();
             self.njd.set_long_vowel();
-            self.jpcommon.njd2jpcommon(&self.njd);
-            self.jpcommon.make_label();
-            self.jpcommon
-                .get_label_feature_to_iter()
-                .ok_or_else(|| OpenJtalkError::ExtractFullContext {
-                    text: text.as_ref().into(),
-                    source: None,
-                })
-                .map(|iter| iter.map(|s| s.to_string()).collect())
+            Ok(())
         } else {
             Err(OpenJtalkError::ExtractFullContext {
-                text: text.as_ref().into(),
+                text: text.into(),
                 source: None,
             })
         }
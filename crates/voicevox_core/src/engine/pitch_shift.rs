@@ -0,0 +1,133 @@
+use super::*;
+
+/// `pitch_shift`で指定できる半音の数の絶対値の上限
+pub(crate) const MAX_PITCH_SHIFT: f32 = 24.;
+
+/// `target_mean_f0`で指定できる基本周波数(Hz)の範囲
+pub(crate) const TARGET_MEAN_F0_RANGE: std::ops::RangeInclusive<f32> = 50.0..=1000.0;
+
+/// 半音の数を、音高(基本周波数の自然対数)の差にする
+pub(crate) fn semitones_to_pitch(semitones: f32) -> f32 {
+    semitones / 12. * std::f32::consts::LN_2
+}
+
+/// AudioQueryの`pitch_shift`と`target_mean_f0`から、有声の音高に加える値を求める
+///
+/// `target_mean_f0`を指定したときは、フレームごとの音高`frame_pitches`のうち有声の音高の平均がその基本周波数になるようにずらし、
+/// さらに`pitch_shift`だけずらす。有声の音高がないときは`pitch_shift`だけずらす。
+/// 音声合成ではAudioQueryを検証しないため、範囲外の値は範囲内に丸め、有限でない値は指定しなかったものとする
+pub(crate) fn pitch_offset(
+    query: &AudioQueryModel,
+    frame_pitches: impl IntoIterator<Item = f32>,
+) -> f32 {
+    let pitch_shift = Some(*query.pitch_shift())
+        .filter(|pitch_shift| pitch_shift.is_finite())
+        .map_or(0., |pitch_shift| {
+            pitch_shift.clamp(-MAX_PITCH_SHIFT, MAX_PITCH_SHIFT)
+        });
+    let shift = semitones_to_pitch(pitch_shift);
+    let Some(target_mean_f0) = query
+        .target_mean_f0()
+        .filter(|target_mean_f0| target_mean_f0.is_finite())
+        .map(|target_mean_f0| {
+            target_mean_f0.clamp(*TARGET_MEAN_F0_RANGE.start(), *TARGET_MEAN_F0_RANGE.end())
+        })
+    else {
+        return shift;
+    };
+    let (sum, count) = frame_pitches
+        .into_iter()
+        .filter(|&pitch| pitch > 0.)
+        .fold((0., 0), |(sum, count), pitch| (sum + pitch, count + 1));
+    if count == 0 {
+        return shift;
+    }
+    target_mean_f0.ln() - sum / count as f32 + shift
+}
+
+/// 有声の音高に`offset`を加える。ずらした音高は[`MIN_VOICED_PITCH`]より低くしない
+pub(crate) fn shift_voiced_pitches(pitches: &mut [f32], offset: f32) {
+    if offset == 0. {
+        return;
+    }
+    for pitch in pitches.iter_mut().filter(|pitch| **pitch > 0.) {
+        *pitch = (*pitch + offset).max(MIN_VOICED_PITCH);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn query(pitch_shift: f32, target_mean_f0: Option<f32>) -> AudioQueryModel {
+        let mut query = AudioQueryModel::new(
+            vec![],
+            1.,
+            0.,
+            1.,
+            1.,
+            0.1,
+            0.1,
+            SynthesisEngine::DEFAULT_SAMPLING_RATE,
+            false,
+            "".into(),
+        );
+        query.set_pitch_shift(pitch_shift);
+        query.set_target_mean_f0(target_mean_f0);
+        query
+    }
+
+    #[rstest]
+    #[case(0., None, 0.)]
+    #[case(12., None, std::f32::consts::LN_2)]
+    #[case(-6., None, -std::f32::consts::LN_2 / 2.)]
+    // 有声の音高の平均は5.5
+    #[case(0., Some(5.5_f32.exp()), 0.)]
+    #[case(0., Some(6_f32.exp()), 0.5)]
+    #[case(12., Some(6_f32.exp()), 0.5 + std::f32::consts::LN_2)]
+    fn pitch_offset_works(
+        #[case] pitch_shift: f32,
+        #[case] target_mean_f0: Option<f32>,
+        #[case] expected: f32,
+    ) {
+        let offset = pitch_offset(&query(pitch_shift, target_mean_f0), [0., 5., 6., 0.]);
+        assert!((offset - expected).abs() < 1e-5, "{offset} != {expected}");
+    }
+
+    #[rstest]
+    #[case(36., None, 2. * std::f32::consts::LN_2)]
+    #[case(-36., None, -2. * std::f32::consts::LN_2)]
+    #[case(f32::NAN, None, 0.)]
+    #[case(0., Some(10.), 50_f32.ln() - 5.5)]
+    #[case(0., Some(1e6), 1000_f32.ln() - 5.5)]
+    #[case(0., Some(f32::INFINITY), 0.)]
+    fn pitch_offset_clamps_out_of_range_values(
+        #[case] pitch_shift: f32,
+        #[case] target_mean_f0: Option<f32>,
+        #[case] expected: f32,
+    ) {
+        let offset = pitch_offset(&query(pitch_shift, target_mean_f0), [0., 5., 6., 0.]);
+        assert!((offset - expected).abs() < 1e-5, "{offset} != {expected}");
+    }
+
+    #[rstest]
+    fn pitch_offset_without_voiced_pitches_uses_only_pitch_shift() {
+        let offset = pitch_offset(&query(12., Some(200.)), [0., 0.]);
+        assert_eq!(offset, std::f32::consts::LN_2);
+    }
+
+    #[rstest]
+    fn shift_voiced_pitches_keeps_unvoiced() {
+        let mut pitches = [0., 5., 6., 0.];
+        shift_voiced_pitches(&mut pitches, 0.5);
+        assert_eq!(pitches, [0., 5.5, 6.5, 0.]);
+    }
+
+    #[rstest]
+    fn shift_voiced_pitches_keeps_voiced() {
+        let mut pitches = [0., 5., 6.];
+        shift_voiced_pitches(&mut pitches, -2.);
+        assert_eq!(pitches, [0., MIN_VOICED_PITCH, 4.]);
+    }
+}
@@ -0,0 +1,170 @@
+use super::pitch_shift::semitones_to_pitch;
+use super::synthesis_engine::UNVOICED_MORA_PHONEME_LIST;
+use super::*;
+use serde::{Deserialize, Serialize};
+
+/// 音高の基準とする音(A4)の基本周波数(Hz)
+const REFERENCE_F0: f32 = 440.;
+
+/// 音声合成の前にアクセント句に適用する話し方の変換
+///
+/// プリセットを増やすときは、列挙子と[`ProsodyPreset::apply`]での変換を追加する
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProsodyPreset {
+    /// 有声のモーラの音高を全て平均の音高にし、抑揚をなくす
+    Monotone,
+    /// 全てのモーラを無声にし、ささやき声にする
+    Whisper,
+    /// 有声のモーラの音高を全て、平均の音高に最も近い平均律の音にする
+    Robot,
+}
+
+impl ProsodyPreset {
+    /// アクセント句のモーラの音高と母音を変換する。ポーズのモーラは変えない
+    pub(crate) fn apply(&self, accent_phrases: &mut [AccentPhraseModel]) {
+        match self {
+            Self::Monotone => {
+                if let Some(mean_pitch) = mean_voiced_pitch(accent_phrases) {
+                    flatten_pitches(accent_phrases, mean_pitch);
+                }
+            }
+            Self::Whisper => devoice(accent_phrases),
+            Self::Robot => {
+                if let Some(mean_pitch) = mean_voiced_pitch(accent_phrases) {
+                    flatten_pitches(accent_phrases, nearest_note_pitch(mean_pitch));
+                }
+            }
+        }
+    }
+}
+
+fn moras_mut(accent_phrases: &mut [AccentPhraseModel]) -> impl Iterator<Item = &mut MoraModel> {
+    accent_phrases
+        .iter_mut()
+        .flat_map(|accent_phrase| accent_phrase.moras_mut())
+}
+
+/// 有声のモーラの音高の平均。有声のモーラがないときは`None`
+fn mean_voiced_pitch(accent_phrases: &[AccentPhraseModel]) -> Option<f32> {
+    let (sum, count) = accent_phrases
+        .iter()
+        .flat_map(|accent_phrase| accent_phrase.moras())
+        .map(|mora| *mora.pitch())
+        .filter(|&pitch| pitch > 0.)
+        .fold((0., 0), |(sum, count), pitch| (sum + pitch, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
+
+fn flatten_pitches(accent_phrases: &mut [AccentPhraseModel], pitch: f32) {
+    for mora in moras_mut(accent_phrases).filter(|mora| *mora.pitch() > 0.) {
+        mora.set_pitch(pitch);
+    }
+}
+
+/// 音高を、最も近い平均律の音の音高にする
+fn nearest_note_pitch(pitch: f32) -> f32 {
+    let reference_pitch = REFERENCE_F0.ln();
+    let semitones = (pitch - reference_pitch) / semitones_to_pitch(1.);
+    reference_pitch + semitones_to_pitch(semitones.round())
+}
+
+/// 全てのモーラの音高を0にし、母音を無声化した母音にする
+fn devoice(accent_phrases: &mut [AccentPhraseModel]) {
+    for mora in moras_mut(accent_phrases) {
+        let unvoiced_vowel = mora.vowel().to_uppercase();
+        if UNVOICED_MORA_PHONEME_LIST.contains(&unvoiced_vowel.as_str()) {
+            mora.set_vowel(unvoiced_vowel);
+        }
+        mora.set_pitch(0.);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn accent_phrases() -> Vec<AccentPhraseModel> {
+        vec![AccentPhraseModel::new(
+            vec![
+                MoraModel::new(
+                    "カ".into(),
+                    Some("k".into()),
+                    Some(0.05),
+                    "a".into(),
+                    0.1,
+                    5.,
+                ),
+                MoraModel::new("ン".into(), None, None, "N".into(), 0.1, 5.5),
+                MoraModel::new(
+                    "シ".into(),
+                    Some("sh".into()),
+                    Some(0.05),
+                    "I".into(),
+                    0.1,
+                    0.,
+                ),
+                MoraModel::new("イ".into(), None, None, "i".into(), 0.1, 6.),
+            ],
+            1,
+            Some(MoraModel::new(
+                "、".into(),
+                None,
+                None,
+                "pau".into(),
+                0.2,
+                0.,
+            )),
+            false,
+        )]
+    }
+
+    fn vowels_and_pitches(accent_phrases: &[AccentPhraseModel]) -> Vec<(String, f32)> {
+        accent_phrases[0]
+            .moras()
+            .iter()
+            .map(|mora| (mora.vowel().clone(), *mora.pitch()))
+            .collect()
+    }
+
+    #[rstest]
+    #[case(
+        ProsodyPreset::Monotone,
+        [("a", 5.5), ("N", 5.5), ("I", 0.), ("i", 5.5)]
+    )]
+    #[case(
+        ProsodyPreset::Whisper,
+        [("A", 0.), ("N", 0.), ("I", 0.), ("I", 0.)]
+    )]
+    fn apply_works(#[case] preset: ProsodyPreset, #[case] expected: [(&str, f32); 4]) {
+        let mut converted = accent_phrases();
+        preset.apply(&mut converted);
+        let expected = expected
+            .iter()
+            .map(|&(vowel, pitch)| (vowel.to_owned(), pitch))
+            .collect::<Vec<_>>();
+        assert_eq!(vowels_and_pitches(&converted), expected);
+        assert_eq!(converted[0].pause_mora(), accent_phrases()[0].pause_mora());
+    }
+
+    #[rstest]
+    fn robot_uses_nearest_note() {
+        let mut accent_phrases = accent_phrases();
+        ProsodyPreset::Robot.apply(&mut accent_phrases);
+        let pitches = vowels_and_pitches(&accent_phrases)
+            .into_iter()
+            .map(|(_, pitch)| pitch)
+            .collect::<Vec<_>>();
+        // 平均の音高5.5(約245Hz)に最も近い音はB3(約247Hz)
+        let b3 = (REFERENCE_F0 * 2_f32.powf(-10. / 12.)).ln();
+        assert!((pitches[0] - b3).abs() < 1e-5, "{pitches:?}");
+        assert_eq!(pitches, [pitches[0], pitches[0], 0., pitches[0]]);
+    }
+
+    #[rstest]
+    fn nearest_note_pitch_keeps_notes() {
+        assert_eq!(nearest_note_pitch(REFERENCE_F0.ln()), REFERENCE_F0.ln());
+    }
+}
@@ -0,0 +1,231 @@
+use super::pitch_shift::semitones_to_pitch;
+use super::*;
+use crate::numerics::SplitMix64;
+use serde::{Deserialize, Serialize};
+
+/// 推論したモーラの長さと音高、ポーズの長さに加える、シードから再現できる小さな揺らぎ
+///
+/// 音高を平坦にする話し方の変換([`ProsodyPreset`])を指定したときは、揺らぎを加えた後に変換する
+///
+/// 同じシードと同じAudioQueryからは、常に同じ揺らぎになる
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProsodyVariation {
+    pub seed: u64,
+    /// モーラの子音と母音の長さを変える割合の最大値
+    pub length: f32,
+    /// 有声のモーラの音高をずらす最大値(半音)
+    pub pitch: f32,
+    /// ポーズの長さを変える割合の最大値
+    pub pause_length: f32,
+}
+
+/// 音高をずらす最大値の上限(半音)
+const MAX_PITCH: f32 = 12.;
+
+impl Default for ProsodyVariation {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            length: 0.05,
+            pitch: 0.2,
+            pause_length: 0.15,
+        }
+    }
+}
+
+impl ProsodyVariation {
+    /// アクセント句のモーラとポーズのモーラに揺らぎを加える。固定したモーラは変えない
+    ///
+    /// 揺らぎはモーラごとに決まった数の乱数から求めるため、あるモーラの値を変えても他のモーラの揺らぎは変わらない。
+    /// 音声合成ではAudioQueryを検証しないため、範囲外の値は[`Self::validate_at`]の範囲に丸め、長さを負にしない
+    pub(crate) fn apply(&self, accent_phrases: &mut [AccentPhraseModel]) {
+        let length = clamp_or_zero(self.length, 1.);
+        let pitch = clamp_or_zero(self.pitch, MAX_PITCH);
+        let pause_length = clamp_or_zero(self.pause_length, 1.);
+        let mut rng = SplitMix64::new(self.seed);
+        for accent_phrase in accent_phrases {
+            for mora in accent_phrase.moras_mut() {
+                let length_scale = (1. + length * rng.next_signed_unit()).max(0.);
+                let pitch_offset = semitones_to_pitch(pitch * rng.next_signed_unit());
+                if *mora.is_locked() {
+                    continue;
+                }
+                if let Some(consonant_length) = *mora.consonant_length() {
+                    mora.set_consonant_length(Some(consonant_length * length_scale));
+                }
+                mora.set_vowel_length(mora.vowel_length() * length_scale);
+                if *mora.pitch() > 0. {
+                    mora.set_pitch(mora.pitch() + pitch_offset);
+                }
+            }
+            if let Some(pause_mora) = accent_phrase.pause_mora() {
+                let pause_length_scale = (1. + pause_length * rng.next_signed_unit()).max(0.);
+                if !pause_mora.is_locked() {
+                    let mut pause_mora = pause_mora.clone();
+                    pause_mora.set_vowel_length(pause_mora.vowel_length() * pause_length_scale);
+                    accent_phrase.set_pause_mora(Some(pause_mora));
+                }
+            }
+        }
+    }
+
+    /// 各値が0以上で、割合が1未満、音高が[`MAX_PITCH`]半音未満であることを検証する
+    pub(crate) fn validate_at(&self, path: &str) -> std::result::Result<(), ModelValidationError> {
+        for (name, value, max) in [
+            ("length", self.length, 1.),
+            ("pitch", self.pitch, MAX_PITCH),
+            ("pause_length", self.pause_length, 1.),
+        ] {
+            if !(value.is_finite() && (0. ..max).contains(&value)) {
+                return Err(ModelValidationError::InvalidValue {
+                    field: format!("{path}{name}"),
+                    value,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 有限の値を0以上`max`以下に丸める。有限でない値は0とする
+fn clamp_or_zero(value: f32, max: f32) -> f32 {
+    if value.is_finite() {
+        value.clamp(0., max)
+    } else {
+        0.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn accent_phrases() -> Vec<AccentPhraseModel> {
+        let mut locked = MoraModel::new("ア".into(), None, None, "a".into(), 0.1, 5.);
+        locked.set_is_locked(true);
+        vec![AccentPhraseModel::new(
+            vec![
+                MoraModel::new(
+                    "カ".into(),
+                    Some("k".into()),
+                    Some(0.05),
+                    "a".into(),
+                    0.1,
+                    5.,
+                ),
+                MoraModel::new(
+                    "ス".into(),
+                    Some("s".into()),
+                    Some(0.05),
+                    "U".into(),
+                    0.1,
+                    0.,
+                ),
+                locked,
+            ],
+            1,
+            Some(MoraModel::new(
+                "、".into(),
+                None,
+                None,
+                "pau".into(),
+                0.2,
+                0.,
+            )),
+            false,
+        )]
+    }
+
+    fn varied(variation: ProsodyVariation) -> Vec<AccentPhraseModel> {
+        let mut accent_phrases = accent_phrases();
+        variation.apply(&mut accent_phrases);
+        accent_phrases
+    }
+
+    #[rstest]
+    fn apply_is_reproducible() {
+        let variation = ProsodyVariation {
+            seed: 42,
+            ..Default::default()
+        };
+        assert_eq!(varied(variation), varied(variation));
+        assert_ne!(
+            varied(variation),
+            varied(ProsodyVariation {
+                seed: 43,
+                ..variation
+            })
+        );
+    }
+
+    #[rstest]
+    fn apply_is_bounded() {
+        let variation = ProsodyVariation {
+            seed: 7,
+            ..Default::default()
+        };
+        let original = accent_phrases();
+        let varied = varied(variation);
+        let moras = std::iter::zip(original[0].moras(), varied[0].moras()).collect::<Vec<_>>();
+
+        let (mora, varied_mora) = moras[0];
+        assert_ne!(mora, varied_mora);
+        let length_scale = varied_mora.vowel_length() / mora.vowel_length();
+        assert!((length_scale - 1.).abs() <= variation.length);
+        assert_eq!(
+            varied_mora.consonant_length().unwrap() / mora.consonant_length().unwrap(),
+            length_scale
+        );
+        assert!((varied_mora.pitch() - mora.pitch()).abs() <= semitones_to_pitch(variation.pitch));
+
+        // 無声のモーラの音高と、固定したモーラは変えない
+        assert_eq!(*moras[1].1.pitch(), 0.);
+        assert_eq!(moras[2].0, moras[2].1);
+
+        let pause_length = *varied[0].pause_mora().as_ref().unwrap().vowel_length();
+        assert!((pause_length / 0.2 - 1.).abs() <= variation.pause_length);
+    }
+
+    #[rstest]
+    #[case(ProsodyVariation { length: 5., pitch: 100., pause_length: 5., ..Default::default() })]
+    #[case(ProsodyVariation { length: -1., pitch: f32::NAN, pause_length: f32::INFINITY, ..Default::default() })]
+    fn apply_clamps_out_of_range_values(#[case] variation: ProsodyVariation) {
+        for seed in 0..16 {
+            let variation = ProsodyVariation { seed, ..variation };
+            let original = accent_phrases();
+            let varied = varied(variation);
+            for (mora, varied_mora) in std::iter::zip(original[0].moras(), varied[0].moras()) {
+                assert!(*varied_mora.vowel_length() >= 0.);
+                assert!(varied_mora.consonant_length().unwrap_or(0.) >= 0.);
+                let pitch_delta = (varied_mora.pitch() - mora.pitch()).abs();
+                assert!(
+                    pitch_delta <= semitones_to_pitch(MAX_PITCH),
+                    "{pitch_delta}"
+                );
+            }
+            let pause_length = *varied[0].pause_mora().as_ref().unwrap().vowel_length();
+            assert!((0. ..=0.4).contains(&pause_length), "{pause_length}");
+        }
+    }
+
+    #[rstest]
+    #[case(ProsodyVariation { length: 1., ..Default::default() }, "length", 1.)]
+    #[case(ProsodyVariation { pitch: -0.1, ..Default::default() }, "pitch", -0.1)]
+    #[case(ProsodyVariation { pitch: 12., ..Default::default() }, "pitch", 12.)]
+    fn validate_fails(
+        #[case] variation: ProsodyVariation,
+        #[case] field: &str,
+        #[case] value: f32,
+    ) {
+        assert_eq!(
+            variation.validate_at("").unwrap_err(),
+            ModelValidationError::InvalidValue {
+                field: field.into(),
+                value,
+            }
+        );
+    }
+}
@@ -0,0 +1,365 @@
+use super::mora_list::mora2text;
+use super::*;
+use crate::numerics::F32Ext as _;
+use serde::{Deserialize, Serialize};
+
+mod midi;
+mod music_xml;
+
+/// 休符として扱う音符の間隔の最小値(秒)。これより短い間隔は前の音符を伸ばして埋める
+const MIN_REST_LENGTH: f32 = 1. / PITCH_FRAME_RATE;
+
+/// 前の音符の母音を伸ばすことを表す歌詞
+const MELISMA_LYRICS: &[&str] = &["ー", "-", "〜", "～"];
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ScoreError {
+    #[error("invalid MIDI file: {0}")]
+    InvalidMidi(&'static str),
+    #[error("unsupported MIDI time division: {0:#06x}")]
+    UnsupportedTimeDivision(u16),
+    #[error("MIDI lyrics must be encoded in UTF-8")]
+    NonUtf8Lyric,
+    #[error("invalid MusicXML: {0}")]
+    InvalidMusicXml(String),
+    #[error("no notes with lyrics found")]
+    NoLyrics,
+    #[error("note {index} has no lyric and no previous mora to continue")]
+    MissingLyric { index: usize },
+    #[error("invalid lyric of note {index}: {source}")]
+    InvalidLyric {
+        index: usize,
+        #[source]
+        source: KanaParseError,
+    },
+}
+
+type ScoreResult<T> = std::result::Result<T, ScoreError>;
+
+/// 歌詞の付いた単旋律の楽譜
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Score {
+    notes: Vec<ScoreNote>,
+}
+
+/// 楽譜の音符
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ScoreNote {
+    /// 歌詞のかな。`None`または`ー`のときは前の音符の母音を伸ばす
+    pub lyric: Option<String>,
+    /// MIDIのノート番号。69が440Hzのラ
+    pub note_number: u8,
+    /// 楽譜の先頭からの開始時刻(秒)
+    pub start: f32,
+    /// 楽譜の先頭からの終了時刻(秒)
+    pub end: f32,
+}
+
+impl Score {
+    /// 音符を開始時刻の順に並べ、次の音符と重なる音符はその開始時刻で終わらせる
+    pub fn new(mut notes: Vec<ScoreNote>) -> Self {
+        notes.retain(|note| note.end > note.start);
+        notes.sort_by(|a, b| a.start.total_cmp(&b.start));
+        for i in 1..notes.len() {
+            let start = notes[i].start;
+            let previous = &mut notes[i - 1];
+            previous.end = previous.end.min(start);
+        }
+        notes.retain(|note| note.end > note.start);
+        Self { notes }
+    }
+
+    pub fn notes(&self) -> &[ScoreNote] {
+        &self.notes
+    }
+
+    /// Standard MIDI Fileを読み込む
+    ///
+    /// 歌詞のメタイベントを含む最初のトラックを用い、歌詞は音符の開始と同じか、それより前の位置に置く。
+    /// 歌詞はUTF-8でなければならない
+    pub fn from_midi(bytes: &[u8]) -> ScoreResult<Self> {
+        midi::parse_midi(bytes).map(Self::new)
+    }
+
+    /// partwise形式のMusicXMLを読み込む
+    ///
+    /// 最初のパートの、最初の音符の声部を用い、和音の2音目以降と装飾音は無視する。
+    /// `<voice>`のない音符は声部`1`とする
+    pub fn from_music_xml(text: &str) -> ScoreResult<Self> {
+        music_xml::parse_music_xml(text).map(Self::new)
+    }
+}
+
+/// MIDIのノート番号を、音声合成モデルの音高(基本周波数の自然対数)にする
+fn note_number_to_pitch(note_number: f32) -> f32 {
+    (440. * 2_f32.powf((note_number - 69.) / 12.)).ln()
+}
+
+/// 楽譜のモーラと、その母音が始まる時刻と終わる時刻
+struct SungMora {
+    mora: MoraModel,
+    start: f32,
+    end: f32,
+}
+
+/// 音符の歌詞をモーラに分ける。一つの音符に複数のモーラがあるときは、音符の長さを等分する
+fn sung_moras(score: &Score, key_shift: i32) -> ScoreResult<Vec<SungMora>> {
+    if score.notes.iter().all(|note| note.lyric.is_none()) {
+        return Err(ScoreError::NoLyrics);
+    }
+
+    let mut sung_moras: Vec<SungMora> = Vec::new();
+    for (index, note) in score.notes.iter().enumerate() {
+        let lyric = note.lyric.as_deref().map(str::trim).unwrap_or_default();
+        let moras = if lyric.is_empty() || MELISMA_LYRICS.contains(&lyric) {
+            let previous = sung_moras
+                .last()
+                .ok_or(ScoreError::MissingLyric { index })?;
+            let vowel = previous.mora.vowel().clone();
+            vec![MoraModel::new(
+                mora2text(&vowel).to_owned(),
+                None,
+                None,
+                vowel,
+                0.,
+                0.,
+            )]
+        } else {
+            parse_kana_lenient(lyric)
+                .map_err(|source| ScoreError::InvalidLyric { index, source })?
+                .0
+                .into_iter()
+                .flat_map(|accent_phrase| accent_phrase.moras().clone())
+                .collect()
+        };
+
+        let pitch = note_number_to_pitch(note.note_number as f32 + key_shift as f32);
+        let mora_length = (note.end - note.start) / moras.len() as f32;
+        for (i, mut mora) in moras.into_iter().enumerate() {
+            let is_voiced = !UNVOICED_MORA_PHONEME_LIST.contains(&mora.vowel().as_str());
+            mora.set_pitch(if is_voiced { pitch } else { 0. });
+            sung_moras.push(SungMora {
+                mora,
+                start: note.start + mora_length * i as f32,
+                end: note.start + mora_length * (i + 1) as f32,
+            });
+        }
+    }
+    Ok(sung_moras)
+}
+
+/// 楽譜から、音素の長さと音高を楽譜に合わせたアクセント句と前後の無音の長さを作る
+///
+/// 休符ごとにアクセント句を分け、休符を無音のモーラとする。
+/// 母音が音符の開始時刻に始まるように、`predict_phoneme_length`で推論した子音の長さだけ前の音素を縮める。
+/// 子音の長さは前の音素の長さの半分までとする。
+/// 音素の境界はフレーム単位に揃え、長い楽譜でも楽譜の時刻からずれないようにする
+pub(crate) fn score_accent_phrases(
+    score: &Score,
+    key_shift: i32,
+    pre_phoneme_length: f32,
+    post_phoneme_length: f32,
+    predict_phoneme_length: impl FnOnce(&[AccentPhraseModel]) -> Result<Vec<AccentPhraseModel>>,
+) -> Result<(Vec<AccentPhraseModel>, f32, f32)> {
+    let sung_moras = sung_moras(score, key_shift)?;
+
+    let mut accent_phrases = Vec::new();
+    let mut moras = Vec::new();
+    let mut boundaries = vec![-pre_phoneme_length];
+    let mut rest_starts = Vec::new();
+    for (i, sung_mora) in sung_moras.iter().enumerate() {
+        moras.push(sung_mora.mora.clone());
+        let next_start = sung_moras.get(i + 1).map(|next| next.start);
+        match next_start {
+            Some(next_start) if next_start - sung_mora.end < MIN_REST_LENGTH => {}
+            _ => {
+                let pause_mora = next_start.map(|_| {
+                    rest_starts.push(sung_mora.end);
+                    MoraModel::new("、".into(), None, None, "pau".into(), 0., 0.)
+                });
+                let accent = moras.len();
+                accent_phrases.push(AccentPhraseModel::new(
+                    std::mem::take(&mut moras),
+                    accent,
+                    pause_mora,
+                    false,
+                ));
+            }
+        }
+    }
+    let predicted = predict_phoneme_length(&accent_phrases)?;
+
+    // 各音素の開始時刻を求める
+    let mut previous_start = -pre_phoneme_length;
+    let mut rest_starts = rest_starts.into_iter();
+    let mut sung_moras = sung_moras.iter();
+    for accent_phrase in &predicted {
+        for mora in accent_phrase.moras() {
+            let start = sung_moras.next().unwrap().start;
+            if let Some(consonant_length) = mora.consonant_length() {
+                let consonant_length = consonant_length.min((start - previous_start) / 2.);
+                boundaries.push(start - consonant_length);
+            }
+            boundaries.push(start);
+            previous_start = start;
+        }
+        if accent_phrase.pause_mora().is_some() {
+            previous_start = rest_starts.next().unwrap();
+            boundaries.push(previous_start);
+        }
+    }
+    let end = score.notes.last().map_or(0., |note| note.end);
+    boundaries.push(end);
+    boundaries.push(end + post_phoneme_length);
+
+    let frames = boundaries
+        .iter()
+        .map(|time| ((time + pre_phoneme_length) * PITCH_FRAME_RATE).round_ties_even_())
+        .collect::<Vec<_>>();
+    let mut lengths = frames
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).max(0.) / PITCH_FRAME_RATE);
+
+    let pre_phoneme_length = lengths.next().unwrap();
+    for accent_phrase in &mut accent_phrases {
+        for mora in accent_phrase.moras_mut() {
+            if mora.consonant().is_some() {
+                mora.set_consonant_length(lengths.next());
+            }
+            mora.set_vowel_length(lengths.next().unwrap());
+        }
+        if let Some(pause_mora) = accent_phrase.pause_mora_mut() {
+            pause_mora.set_vowel_length(lengths.next().unwrap());
+        }
+    }
+    let post_phoneme_length = lengths.next().unwrap();
+    Ok((accent_phrases, pre_phoneme_length, post_phoneme_length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn note(lyric: Option<&str>, note_number: u8, start: f32, end: f32) -> ScoreNote {
+        ScoreNote {
+            lyric: lyric.map(Into::into),
+            note_number,
+            start,
+            end,
+        }
+    }
+
+    /// 音素の境界をフレーム単位に揃えるため、1フレームの誤差を許容する
+    fn assert_length(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() <= 1. / PITCH_FRAME_RATE,
+            "{actual} != {expected}"
+        );
+    }
+
+    #[rstest]
+    fn new_sorts_and_truncates_overlapping_notes() {
+        let score = Score::new(vec![
+            note(Some("ラ"), 69, 0.5, 1.),
+            note(Some("ド"), 60, 0., 0.8),
+            note(None, 62, 1., 1.),
+        ]);
+        assert_eq!(
+            score.notes(),
+            [note(Some("ド"), 60, 0., 0.5), note(Some("ラ"), 69, 0.5, 1.)]
+        );
+    }
+
+    #[rstest]
+    fn note_number_to_pitch_works() {
+        assert_eq!(note_number_to_pitch(69.), 440_f32.ln());
+        assert!((note_number_to_pitch(81.) - 880_f32.ln()).abs() < 1e-6);
+    }
+
+    #[rstest]
+    fn score_accent_phrases_works() {
+        let score = Score::new(vec![
+            note(Some("さくら"), 69, 0., 0.6),
+            note(Some("ー"), 71, 0.6, 1.),
+            note(Some("か"), 72, 1.5, 2.),
+        ]);
+        let (accent_phrases, pre_phoneme_length, post_phoneme_length) =
+            score_accent_phrases(&score, -12, 0.2, 0.1, |accent_phrases| {
+                let mut accent_phrases = accent_phrases.to_vec();
+                for mora in accent_phrases.iter_mut().flat_map(|a| a.moras_mut()) {
+                    if mora.consonant().is_some() {
+                        mora.set_consonant_length(Some(0.3));
+                    }
+                }
+                Ok(accent_phrases)
+            })
+            .unwrap();
+
+        let texts = accent_phrases
+            .iter()
+            .map(|accent_phrase| {
+                accent_phrase
+                    .moras()
+                    .iter()
+                    .map(|mora| mora.text().as_str())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["サクラア", "カ"]);
+        assert!(accent_phrases[0].pause_mora().is_some());
+        assert!(accent_phrases[1].pause_mora().is_none());
+
+        let moras = accent_phrases[0].moras();
+        // 先頭の子音は先頭の無音の半分まで
+        assert_length(pre_phoneme_length, 0.1);
+        assert_length(moras[0].consonant_length().unwrap(), 0.1);
+        // 子音は前の母音の半分まで
+        assert_length(moras[1].consonant_length().unwrap(), 0.1);
+        assert_length(*moras[0].vowel_length(), 0.1);
+        assert_eq!(*moras[2].pitch(), note_number_to_pitch(57.));
+        assert_eq!(*moras[3].vowel(), "a");
+        assert_eq!(*moras[3].pitch(), note_number_to_pitch(59.));
+        assert_length(*moras[3].vowel_length(), 0.4);
+        // 休符は次の子音の分だけ短くなる
+        let pause_length = *accent_phrases[0]
+            .pause_mora()
+            .as_ref()
+            .unwrap()
+            .vowel_length();
+        assert_length(pause_length, 0.25);
+        assert_length(
+            accent_phrases[1].moras()[0].consonant_length().unwrap(),
+            0.25,
+        );
+        assert_length(post_phoneme_length, 0.1);
+
+        // 音素の長さの合計は楽譜の長さと前後の無音の長さに一致する
+        let total = pre_phoneme_length
+            + post_phoneme_length
+            + pause_length
+            + accent_phrases
+                .iter()
+                .flat_map(|accent_phrase| accent_phrase.moras())
+                .map(|mora| mora.consonant_length().unwrap_or(0.) + mora.vowel_length())
+                .sum::<f32>();
+        assert_eq!(
+            (total * PITCH_FRAME_RATE).round(),
+            (2.3 * PITCH_FRAME_RATE).round()
+        );
+    }
+
+    #[rstest]
+    #[case(vec![note(None, 60, 0., 1.)], ScoreError::NoLyrics)]
+    #[case(
+        vec![note(None, 60, 0., 1.), note(Some("ア"), 60, 1., 2.)],
+        ScoreError::MissingLyric { index: 0 }
+    )]
+    fn score_accent_phrases_fails(#[case] notes: Vec<ScoreNote>, #[case] expected: ScoreError) {
+        let result = score_accent_phrases(&Score::new(notes), 0, 0.1, 0.1, |accent_phrases| {
+            Ok(accent_phrases.to_vec())
+        });
+        assert_eq!(result.unwrap_err(), Error::ParseScore(expected));
+    }
+}
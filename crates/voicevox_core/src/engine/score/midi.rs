@@ -0,0 +1,398 @@
+use super::{ScoreError, ScoreNote, ScoreResult};
+
+/// テンポの指定がないときの4分音符の長さ(マイクロ秒)
+const DEFAULT_TEMPO: u32 = 500_000;
+
+/// トラックから読み取ったイベント
+#[derive(Debug, Default)]
+struct Track {
+    /// (開始tick, 終了tick, ノート番号)
+    notes: Vec<(u64, u64, u8)>,
+    /// (tick, 歌詞)
+    lyrics: Vec<(u64, String)>,
+    /// (tick, 4分音符の長さ(マイクロ秒))
+    tempos: Vec<(u64, u32)>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn peek(&self) -> ScoreResult<u8> {
+        self.bytes
+            .get(self.pos)
+            .copied()
+            .ok_or(ScoreError::InvalidMidi("unexpected end of data"))
+    }
+
+    fn read(&mut self, len: usize) -> ScoreResult<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .ok_or(ScoreError::InvalidMidi("unexpected end of data"))?;
+        let bytes = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(ScoreError::InvalidMidi("unexpected end of data"))?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> ScoreResult<u8> {
+        Ok(self.read(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> ScoreResult<u16> {
+        Ok(u16::from_be_bytes(self.read(2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&mut self) -> ScoreResult<u32> {
+        Ok(u32::from_be_bytes(self.read(4)?.try_into().unwrap()))
+    }
+
+    /// 可変長数値を読む
+    fn read_variable_length(&mut self) -> ScoreResult<u32> {
+        let mut value = 0_u32;
+        for _ in 0..4 {
+            let byte = self.read_u8()?;
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ScoreError::InvalidMidi(
+            "variable-length quantity is too long",
+        ))
+    }
+}
+
+/// Standard MIDI Fileから、歌詞の付いた最初のトラックの音符を読み込む
+pub(super) fn parse_midi(bytes: &[u8]) -> ScoreResult<Vec<ScoreNote>> {
+    let mut reader = Reader::new(bytes);
+    if reader.read(4).ok() != Some(b"MThd") {
+        return Err(ScoreError::InvalidMidi("not a Standard MIDI File"));
+    }
+    let header_length = reader.read_u32()? as usize;
+    let header = reader.read(header_length)?;
+    let mut header = Reader::new(header);
+    let _format = header.read_u16()?;
+    let num_tracks = header.read_u16()?;
+    let division = header.read_u16()?;
+    if division & 0x8000 != 0 || division == 0 {
+        return Err(ScoreError::UnsupportedTimeDivision(division));
+    }
+
+    let mut tracks = Vec::new();
+    while !reader.is_empty() && tracks.len() < num_tracks as usize {
+        let chunk_id = reader.read(4)?;
+        let chunk_length = reader.read_u32()? as usize;
+        let chunk = reader.read(chunk_length)?;
+        if chunk_id == b"MTrk" {
+            tracks.push(parse_track(chunk)?);
+        }
+    }
+
+    // テンポの変更は、フォーマット1では最初のトラックに置かれるため、全トラックから集める
+    let mut tempos = tracks
+        .iter()
+        .flat_map(|track| track.tempos.iter().copied())
+        .collect::<Vec<_>>();
+    tempos.sort_by_key(|&(tick, _)| tick);
+
+    let track = tracks
+        .into_iter()
+        .find(|track| !track.notes.is_empty() && !track.lyrics.is_empty())
+        .ok_or(ScoreError::NoLyrics)?;
+    let lyrics = assign_lyrics(&track.notes, track.lyrics);
+
+    let to_seconds = |tick| tick_to_seconds(tick, &tempos, division);
+    Ok(std::iter::zip(track.notes, lyrics)
+        .map(|((on_tick, off_tick, note_number), lyric)| ScoreNote {
+            lyric,
+            note_number,
+            start: to_seconds(on_tick),
+            end: to_seconds(off_tick),
+        })
+        .collect())
+}
+
+fn parse_track(bytes: &[u8]) -> ScoreResult<Track> {
+    let mut reader = Reader::new(bytes);
+    let mut track = Track::default();
+    // ノート番号ごとの、鳴っている音符の開始tick
+    let mut note_ons: [Option<u64>; 128] = [None; 128];
+    let mut tick = 0_u64;
+    let mut running_status = None;
+
+    while !reader.is_empty() {
+        tick += reader.read_variable_length()? as u64;
+        let status = if reader.peek()? & 0x80 != 0 {
+            reader.read_u8()?
+        } else {
+            running_status.ok_or(ScoreError::InvalidMidi("missing running status"))?
+        };
+
+        match status {
+            0xff => {
+                let meta_type = reader.read_u8()?;
+                let length = reader.read_variable_length()? as usize;
+                let data = reader.read(length)?;
+                match meta_type {
+                    0x05 => {
+                        let lyric =
+                            std::str::from_utf8(data).map_err(|_| ScoreError::NonUtf8Lyric)?;
+                        track.lyrics.push((tick, lyric.to_owned()));
+                    }
+                    0x51 if length == 3 => {
+                        let tempo = u32::from_be_bytes([0, data[0], data[1], data[2]]);
+                        if tempo == 0 {
+                            return Err(ScoreError::InvalidMidi("tempo must be positive"));
+                        }
+                        track.tempos.push((tick, tempo));
+                    }
+                    0x2f => break,
+                    _ => {}
+                }
+                running_status = None;
+            }
+            0xf0 | 0xf7 => {
+                let length = reader.read_variable_length()? as usize;
+                reader.read(length)?;
+                running_status = None;
+            }
+            0x80..=0xef => {
+                running_status = Some(status);
+                let data_length = if matches!(status & 0xf0, 0xc0 | 0xd0) {
+                    1
+                } else {
+                    2
+                };
+                let data = reader.read(data_length)?;
+                let note_number = data[0] & 0x7f;
+                match (status & 0xf0, data.get(1)) {
+                    // ベロシティが0のノートオンはノートオフとして扱う
+                    (0x80, _) | (0x90, Some(0)) => {
+                        if let Some(on_tick) = note_ons[note_number as usize].take() {
+                            track.notes.push((on_tick, tick, note_number));
+                        }
+                    }
+                    (0x90, _) => {
+                        if let Some(on_tick) = note_ons[note_number as usize].replace(tick) {
+                            track.notes.push((on_tick, tick, note_number));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => return Err(ScoreError::InvalidMidi("unsupported status byte")),
+        }
+    }
+
+    track.notes.sort_by_key(|&(on_tick, _, _)| on_tick);
+    Ok(track)
+}
+
+/// 各歌詞を、その位置と同じか後に始まる最初の音符に割り当てる。同じ音符に割り当てた歌詞は繋げる
+fn assign_lyrics(notes: &[(u64, u64, u8)], mut lyrics: Vec<(u64, String)>) -> Vec<Option<String>> {
+    lyrics.sort_by_key(|&(tick, _)| tick);
+    let mut assigned = vec![None::<String>; notes.len()];
+    let mut index = 0;
+    for (tick, lyric) in lyrics {
+        while index < notes.len() && notes[index].0 < tick {
+            index += 1;
+        }
+        let Some(note_lyric) = assigned.get_mut(index) else {
+            break;
+        };
+        note_lyric.get_or_insert_with(String::new).push_str(&lyric);
+    }
+    assigned
+}
+
+/// テンポの変更を考慮して、tickを先頭からの秒数にする
+fn tick_to_seconds(tick: u64, tempos: &[(u64, u32)], division: u16) -> f32 {
+    let mut seconds = 0.;
+    let mut last_tick = 0;
+    let mut tempo = DEFAULT_TEMPO;
+    for &(tempo_tick, next_tempo) in tempos.iter().take_while(|&&(t, _)| t < tick) {
+        seconds += (tempo_tick - last_tick) as f64 * tempo as f64;
+        last_tick = tempo_tick;
+        tempo = next_tempo;
+    }
+    seconds += (tick - last_tick) as f64 * tempo as f64;
+    (seconds / division as f64 / 1_000_000.) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn smf(division: u16, tracks: &[&[u8]]) -> Vec<u8> {
+        let mut bytes = b"MThd".to_vec();
+        bytes.extend(6_u32.to_be_bytes());
+        bytes.extend(1_u16.to_be_bytes());
+        bytes.extend((tracks.len() as u16).to_be_bytes());
+        bytes.extend(division.to_be_bytes());
+        for track in tracks {
+            bytes.extend(b"MTrk");
+            bytes.extend((track.len() as u32).to_be_bytes());
+            bytes.extend(*track);
+        }
+        bytes
+    }
+
+    #[rstest]
+    fn parse_midi_works() {
+        // テンポを4分音符0.25秒に変える
+        let conductor: &[u8] = &[
+            0x00, 0xff, 0x51, 0x03, 0x03, 0xd0, 0x90, 0x00, 0xff, 0x2f, 0x00,
+        ];
+        let mut melody = Vec::new();
+        // 「ド」: 歌詞「さ」
+        melody.extend([0x00, 0xff, 0x05, 0x03]);
+        melody.extend("さ".as_bytes());
+        melody.extend([0x00, 0x90, 60, 100]);
+        // 4分音符(480tick)後にベロシティ0でノートオフ。ランニングステータスを用いる
+        melody.extend([0x83, 0x60, 60, 0]);
+        // 4分休符の後に「レ」: 歌詞なし
+        melody.extend([0x83, 0x60, 62, 100]);
+        melody.extend([0x83, 0x60, 0x80, 62, 0]);
+        melody.extend([0x00, 0xff, 0x2f, 0x00]);
+
+        let notes = parse_midi(&smf(480, &[conductor, &melody])).unwrap();
+        assert_eq!(
+            notes,
+            [
+                ScoreNote {
+                    lyric: Some("さ".into()),
+                    note_number: 60,
+                    start: 0.,
+                    end: 0.25,
+                },
+                ScoreNote {
+                    lyric: None,
+                    note_number: 62,
+                    start: 0.5,
+                    end: 0.75,
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(b"RIFF".to_vec(), ScoreError::InvalidMidi("not a Standard MIDI File"))]
+    #[case(smf(0xe728, &[]), ScoreError::UnsupportedTimeDivision(0xe728))]
+    #[case(
+        smf(480, &[&[0x00, 0x90, 60, 100, 0x60, 60, 0, 0x00, 0xff, 0x2f, 0x00]]),
+        ScoreError::NoLyrics
+    )]
+    #[case(
+        smf(480, &[&[0x00, 0xff, 0x05, 0x01, 0x82, 0x00, 0xff, 0x2f, 0x00]]),
+        ScoreError::NonUtf8Lyric
+    )]
+    #[case(Vec::new(), ScoreError::InvalidMidi("not a Standard MIDI File"))]
+    #[case(
+        b"MThd\0\0\0\x02\0\x01".to_vec(),
+        ScoreError::InvalidMidi("unexpected end of data")
+    )]
+    #[case(
+        b"MThd\0\0\0\x06\0\x01\0\x01".to_vec(),
+        ScoreError::InvalidMidi("unexpected end of data")
+    )]
+    #[case(smf(0, &[]), ScoreError::UnsupportedTimeDivision(0))]
+    #[case(
+        {
+            let mut bytes = smf(480, &[&[0x00, 0xff, 0x2f, 0x00]]);
+            bytes.truncate(bytes.len() - 1);
+            bytes
+        },
+        ScoreError::InvalidMidi("unexpected end of data")
+    )]
+    #[case(
+        smf(480, &[&[0x00, 60, 100]]),
+        ScoreError::InvalidMidi("missing running status")
+    )]
+    #[case(smf(480, &[&[0x00, 0xf1, 0x00]]), ScoreError::InvalidMidi("unsupported status byte"))]
+    #[case(
+        smf(480, &[&[0xff, 0xff, 0xff, 0xff, 0x00]]),
+        ScoreError::InvalidMidi("variable-length quantity is too long")
+    )]
+    #[case(
+        smf(480, &[&[0x00, 0xff, 0x05, 0x08, 0x82]]),
+        ScoreError::InvalidMidi("unexpected end of data")
+    )]
+    #[case(
+        smf(480, &[&[0x00, 0xf0, 0x7f]]),
+        ScoreError::InvalidMidi("unexpected end of data")
+    )]
+    #[case(smf(480, &[&[0x00, 0x90, 60]]), ScoreError::InvalidMidi("unexpected end of data"))]
+    #[case(
+        smf(480, &[&[0x00, 0xff, 0x51, 0x03, 0x00, 0x00, 0x00]]),
+        ScoreError::InvalidMidi("tempo must be positive")
+    )]
+    #[case(smf(480, &[]), ScoreError::NoLyrics)]
+    fn parse_midi_fails(#[case] bytes: Vec<u8>, #[case] expected: ScoreError) {
+        assert_eq!(parse_midi(&bytes).unwrap_err(), expected);
+    }
+
+    #[rstest]
+    fn parse_midi_ignores_unknown_chunks_and_unfinished_notes() {
+        let mut bytes = smf(480, &[]);
+        // トラック数を1とし、未知のチャンクの後にトラックを置く
+        bytes[10..12].copy_from_slice(&1_u16.to_be_bytes());
+        bytes.extend(b"XYZW\0\0\0\x02ab");
+        let mut track = Vec::new();
+        track.extend([0x00, 0xff, 0x05, 0x03]);
+        track.extend("ら".as_bytes());
+        track.extend([0x00, 0x90, 60, 100, 0x60, 0x80, 60, 0]);
+        // ノートオフのない音符は用いない
+        track.extend([0x00, 0x90, 62, 100, 0x00, 0xff, 0x2f, 0x00]);
+        bytes.extend(b"MTrk");
+        bytes.extend((track.len() as u32).to_be_bytes());
+        bytes.extend(track);
+
+        let notes = parse_midi(&bytes).unwrap();
+        assert_eq!(
+            notes,
+            [ScoreNote {
+                lyric: Some("ら".into()),
+                note_number: 60,
+                start: 0.,
+                end: 0.1,
+            }]
+        );
+    }
+
+    #[rstest]
+    fn parse_midi_does_not_panic_on_broken_input() {
+        // 途中で切れたもの、各バイトを書き換えたものは、エラーになるか一部の音符を読み込む
+        let mut melody = vec![0x00, 0xff, 0x05, 0x03];
+        melody.extend("さ".as_bytes());
+        melody.extend([0x00, 0x90, 60, 100, 0x83, 0x60, 60, 0]);
+        melody.extend([0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20]);
+        melody.extend([0x00, 0xf0, 0x01, 0xf7, 0x00, 0xff, 0x2f, 0x00]);
+        let bytes = smf(480, &[&melody]);
+        for end in 0..bytes.len() {
+            let _ = parse_midi(&bytes[..end]);
+        }
+        for i in 0..bytes.len() {
+            for value in [0x00, 0x7f, 0x80, 0xff] {
+                let mut broken = bytes.clone();
+                broken[i] = value;
+                let _ = parse_midi(&broken);
+            }
+        }
+    }
+}
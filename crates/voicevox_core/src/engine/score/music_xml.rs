@@ -0,0 +1,617 @@
+use super::{ScoreError, ScoreNote, ScoreResult};
+
+/// テンポの指定がないときのテンポ(4分音符/分)
+const DEFAULT_TEMPO: f32 = 120.;
+
+/// `<backup>`で先頭より前に戻ったとみなさない、時刻の誤差(秒)
+const TIME_TOLERANCE: f32 = 1e-4;
+
+/// `<voice>`のない音符の声部
+const DEFAULT_VOICE: &str = "1";
+
+/// MusicXMLを読むための最小限のXMLの要素
+#[derive(Debug, Default, PartialEq)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn child_text(&self, name: &str) -> Option<&str> {
+        self.child(name).map(|child| child.text.trim())
+    }
+
+    fn child_number<T: std::str::FromStr>(&self, name: &str) -> ScoreResult<Option<T>> {
+        self.child_text(name)
+            .map(|text| {
+                text.parse()
+                    .map_err(|_| invalid(format!("<{name}> must be a number: {text}")))
+            })
+            .transpose()
+    }
+
+    /// `<duration>`を読む。有限の0以上の値でなければならない
+    fn duration(&self) -> ScoreResult<Option<f32>> {
+        match self.child_number::<f32>("duration")? {
+            Some(duration) if !(duration.is_finite() && duration >= 0.) => Err(invalid(format!(
+                "<duration> must be a finite non-negative number: {duration}"
+            ))),
+            duration => Ok(duration),
+        }
+    }
+
+    /// 音符の声部
+    fn voice(&self) -> &str {
+        self.child_text("voice").unwrap_or(DEFAULT_VOICE)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> ScoreError {
+    ScoreError::InvalidMusicXml(message.into())
+}
+
+/// partwise形式のMusicXMLから、最初のパートの、最初の音符の声部の音符を読み込む
+pub(super) fn parse_music_xml(text: &str) -> ScoreResult<Vec<ScoreNote>> {
+    let root = parse_xml(text)?;
+    if root.name != "score-partwise" {
+        return Err(invalid(format!(
+            "unsupported root element: <{}>",
+            root.name
+        )));
+    }
+    let part = root
+        .child("part")
+        .ok_or_else(|| invalid("no <part> found"))?;
+
+    let measures = part.children.iter().filter(|e| e.name == "measure");
+    let voice = measures
+        .clone()
+        .flat_map(|measure| &measure.children)
+        .find(|e| e.name == "note" && e.child("grace").is_none())
+        .map_or(DEFAULT_VOICE, Element::voice);
+
+    let mut notes: Vec<ScoreNote> = Vec::new();
+    // 4分音符あたりのdurationの単位数
+    let mut divisions = 1.;
+    let mut tempo = DEFAULT_TEMPO;
+    // 現在位置(秒)
+    let mut time = 0_f32;
+    // タイで次の音符と繋がる音符の位置
+    let mut tied: Option<usize> = None;
+    let seconds = |duration: f32, divisions: f32, tempo: f32| duration / divisions * 60. / tempo;
+    for measure in measures {
+        for element in &measure.children {
+            match element.name.as_str() {
+                "attributes" => {
+                    if let Some(value) = element.child_number::<f32>("divisions")? {
+                        if !(value.is_finite() && value > 0.) {
+                            return Err(invalid("<divisions> must be positive"));
+                        }
+                        divisions = value;
+                    }
+                }
+                "sound" => {
+                    if let Some(value) = parse_tempo(element)? {
+                        tempo = value;
+                    }
+                }
+                "direction" => {
+                    for sound in element.children.iter().filter(|e| e.name == "sound") {
+                        if let Some(value) = parse_tempo(sound)? {
+                            tempo = value;
+                        }
+                    }
+                }
+                "backup" | "forward" => {
+                    let duration = element
+                        .duration()?
+                        .ok_or_else(|| invalid(format!("<{}> has no <duration>", element.name)))?;
+                    let length = seconds(duration, divisions, tempo);
+                    if element.name == "backup" {
+                        if length > time + TIME_TOLERANCE {
+                            return Err(invalid("<backup> moves before the start of the part"));
+                        }
+                        time = (time - length).max(0.);
+                    } else {
+                        time += length;
+                    }
+                }
+                "note" => {
+                    // 装飾音は長さを持たないため、位置を進めない
+                    if element.child("grace").is_some() {
+                        continue;
+                    }
+                    let duration = element.duration()?.unwrap_or(0.);
+                    let length = seconds(duration, divisions, tempo);
+                    // 和音の2音目以降は前の音符と同じ位置に置かれるため、位置を進めない
+                    if element.child("chord").is_some() {
+                        continue;
+                    }
+                    let start = time;
+                    time += length;
+                    // 最初の音符の声部以外は、位置を進めるのみで用いない
+                    if element.voice() != voice {
+                        continue;
+                    }
+                    if element.child("rest").is_some() {
+                        tied = None;
+                        continue;
+                    }
+
+                    let pitch = element
+                        .child("pitch")
+                        .ok_or_else(|| invalid("<note> has neither <pitch> nor <rest>"))?;
+                    let note_number = parse_pitch(pitch)?;
+                    let ties = element
+                        .children
+                        .iter()
+                        .filter(|e| e.name == "tie")
+                        .filter_map(|e| e.attribute("type"))
+                        .collect::<Vec<_>>();
+                    let lyric = element
+                        .children
+                        .iter()
+                        .filter(|e| e.name == "lyric")
+                        .find_map(|e| e.child_text("text"))
+                        .map(ToOwned::to_owned);
+
+                    match tied.and_then(|i| notes.get_mut(i)) {
+                        Some(previous)
+                            if ties.contains(&"stop")
+                                && previous.note_number == note_number
+                                && lyric.is_none() =>
+                        {
+                            previous.end = time;
+                        }
+                        _ => notes.push(ScoreNote {
+                            lyric,
+                            note_number,
+                            start,
+                            end: time,
+                        }),
+                    }
+                    tied = ties.contains(&"start").then_some(notes.len() - 1);
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(notes)
+}
+
+fn parse_tempo(sound: &Element) -> ScoreResult<Option<f32>> {
+    let Some(tempo) = sound.attribute("tempo") else {
+        return Ok(None);
+    };
+    match tempo.trim().parse::<f32>() {
+        Ok(tempo) if tempo.is_finite() && tempo > 0. => Ok(Some(tempo)),
+        _ => Err(invalid(format!("invalid tempo: {tempo}"))),
+    }
+}
+
+/// `<pitch>`をMIDIのノート番号にする
+fn parse_pitch(pitch: &Element) -> ScoreResult<u8> {
+    let step = match pitch.child_text("step") {
+        Some("C") => 0,
+        Some("D") => 2,
+        Some("E") => 4,
+        Some("F") => 5,
+        Some("G") => 7,
+        Some("A") => 9,
+        Some("B") => 11,
+        step => return Err(invalid(format!("invalid <step>: {step:?}"))),
+    };
+    // 極端な値でも桁あふれしないよう、i64で計算する
+    let alter = pitch.child_number::<f32>("alter")?.unwrap_or(0.).round() as i64;
+    let octave = pitch
+        .child_number::<i32>("octave")?
+        .ok_or_else(|| invalid("<pitch> has no <octave>"))? as i64;
+    let note_number = (octave + 1) * 12 + step + alter;
+    u8::try_from(note_number)
+        .ok()
+        .filter(|&n| n < 128)
+        .ok_or_else(|| invalid(format!("pitch is out of range: {note_number}")))
+}
+
+/// XMLを読み、ルート要素を返す
+///
+/// 宣言、処理命令、コメント、DOCTYPEは読み飛ばす。名前空間は扱わない
+fn parse_xml(text: &str) -> ScoreResult<Element> {
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let Some(tag_start) = rest.find('<') else {
+            if let Some(element) = stack.last_mut() {
+                element.text.push_str(&unescape(rest)?);
+            }
+            break;
+        };
+        if let Some(element) = stack.last_mut() {
+            element.text.push_str(&unescape(&rest[..tag_start])?);
+        }
+        rest = &rest[tag_start..];
+
+        let skip_until = |rest: &str, end: &str| {
+            rest.find(end)
+                .map(|i| i + end.len())
+                .ok_or_else(|| invalid(format!("missing `{end}`")))
+        };
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = skip_until(cdata, "]]>")?;
+            if let Some(element) = stack.last_mut() {
+                element.text.push_str(&cdata[..end - 3]);
+            }
+            rest = &cdata[end..];
+        } else if rest.starts_with("<!--") {
+            rest = &rest[skip_until(rest, "-->")?..];
+        } else if rest.starts_with("<?") {
+            rest = &rest[skip_until(rest, "?>")?..];
+        } else if rest.starts_with("<!") {
+            // DOCTYPE。内部サブセットの`[...]`を含むことがある
+            let end = match (rest.find('['), rest.find('>')) {
+                (Some(bracket), Some(gt)) if bracket < gt => skip_until(rest, "]>")?,
+                _ => skip_until(rest, ">")?,
+            };
+            rest = &rest[end..];
+        } else if let Some(close) = rest.strip_prefix("</") {
+            let end = skip_until(close, ">")?;
+            let name = close[..end - 1].trim();
+            let element = stack
+                .pop()
+                .filter(|element| element.name == name)
+                .ok_or_else(|| invalid(format!("unexpected closing tag: </{name}>")))?;
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => root = Some(element),
+            }
+            rest = &close[end..];
+        } else {
+            let end = find_tag_end(rest)?;
+            let tag = &rest[1..end];
+            let (tag, is_empty) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let element = parse_start_tag(tag)?;
+            if is_empty {
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => root = Some(element),
+                }
+            } else {
+                stack.push(element);
+            }
+            rest = &rest[end + 1..];
+        }
+        if root.is_some() {
+            break;
+        }
+    }
+    if let Some(element) = stack.last() {
+        return Err(invalid(format!("unclosed tag: <{}>", element.name)));
+    }
+    root.ok_or_else(|| invalid("no root element found"))
+}
+
+/// 開始タグの`>`の位置を、属性値の中の`>`を除いて探す
+fn find_tag_end(rest: &str) -> ScoreResult<usize> {
+    let mut quote = None;
+    for (i, c) in rest.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return Ok(i),
+            _ => {}
+        }
+    }
+    Err(invalid("unterminated tag"))
+}
+
+fn parse_start_tag(tag: &str) -> ScoreResult<Element> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = &tag[..name_end];
+    if name.is_empty() {
+        return Err(invalid("empty tag name"));
+    }
+
+    let mut attributes = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let (key, value) = rest
+            .split_once('=')
+            .ok_or_else(|| invalid(format!("invalid attribute in <{name}>")))?;
+        let value = value.trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '"' | '\''))
+            .ok_or_else(|| invalid(format!("unquoted attribute in <{name}>")))?;
+        let value_end = value[1..]
+            .find(quote)
+            .ok_or_else(|| invalid(format!("unterminated attribute in <{name}>")))?;
+        attributes.push((key.trim().to_owned(), unescape(&value[1..1 + value_end])?));
+        rest = value[value_end + 2..].trim_start();
+    }
+    Ok(Element {
+        name: name.to_owned(),
+        attributes,
+        ..Default::default()
+    })
+}
+
+/// 文字参照と定義済みの実体参照を展開する
+fn unescape(text: &str) -> ScoreResult<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        unescaped.push_str(&rest[..amp]);
+        let semicolon = rest[amp..]
+            .find(';')
+            .ok_or_else(|| invalid("unterminated entity reference"))?;
+        let entity = &rest[amp + 1..amp + semicolon];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        unescaped.push(c.ok_or_else(|| invalid(format!("unknown entity: &{entity};")))?);
+        rest = &rest[amp + semicolon + 1..];
+    }
+    unescaped.push_str(rest);
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn parse_xml_works() {
+        let root = parse_xml(
+            r#"<?xml version="1.0"?>
+<!DOCTYPE a [<!ENTITY x "y">]>
+<!-- comment -->
+<a k='v &amp; w'><b>x&lt;<![CDATA[<y>]]>&#x3042;</b><c/></a>"#,
+        )
+        .unwrap();
+        assert_eq!(root.name, "a");
+        assert_eq!(root.attribute("k"), Some("v & w"));
+        assert_eq!(root.child_text("b"), Some("x<<y>あ"));
+        assert!(root.child("c").is_some());
+    }
+
+    const MUSIC_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="3.1">
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>2</divisions></attributes>
+      <direction><sound tempo="60"/></direction>
+      <note>
+        <pitch><step>A</step><octave>4</octave></pitch>
+        <duration>2</duration>
+        <tie type="start"/>
+        <lyric><text>さ</text></lyric>
+      </note>
+      <note>
+        <pitch><step>C</step><octave>5</octave></pitch>
+        <duration>2</duration>
+        <chord/>
+      </note>
+    </measure>
+    <measure number="2">
+      <note>
+        <pitch><step>A</step><octave>4</octave></pitch>
+        <duration>1</duration>
+        <tie type="stop"/>
+      </note>
+      <note><rest/><duration>1</duration></note>
+      <note>
+        <grace/>
+        <pitch><step>B</step><octave>4</octave></pitch>
+      </note>
+      <note>
+        <pitch><step>B</step><alter>-1</alter><octave>4</octave></pitch>
+        <duration>2</duration>
+        <lyric><text>く</text></lyric>
+      </note>
+    </measure>
+  </part>
+</score-partwise>"#;
+
+    fn score_partwise(measure: &str) -> String {
+        format!("<score-partwise><part><measure>{measure}</measure></part></score-partwise>")
+    }
+
+    #[rstest]
+    fn parse_music_xml_works() {
+        let notes = parse_music_xml(MUSIC_XML).unwrap();
+        assert_eq!(
+            notes,
+            [
+                ScoreNote {
+                    lyric: Some("さ".into()),
+                    note_number: 69,
+                    start: 0.,
+                    end: 1.5,
+                },
+                ScoreNote {
+                    lyric: Some("く".into()),
+                    note_number: 70,
+                    start: 2.,
+                    end: 3.,
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("<score-timewise/>", "unsupported root element: <score-timewise>")]
+    #[case("<score-partwise><part>", "unclosed tag: <part>")]
+    #[case("<score-partwise></part>", "unexpected closing tag: </part>")]
+    #[case(
+        "<score-partwise><part><measure><note><pitch><step>H</step></pitch></note></measure></part></score-partwise>",
+        "invalid <step>: Some(\"H\")"
+    )]
+    #[case("", "no root element found")]
+    #[case("<score-partwise/>", "no <part> found")]
+    #[case("<!-- comment", "missing `-->`")]
+    #[case("<a><![CDATA[x</a>", "missing `]]>`")]
+    #[case("<a b='c/>", "unterminated tag")]
+    #[case("<a b=c/>", "unquoted attribute in <a>")]
+    #[case("<a b/>", "invalid attribute in <a>")]
+    #[case("< />", "empty tag name")]
+    #[case("<a>&bogus;</a>", "unknown entity: &bogus;")]
+    #[case("<a>&amp</a>", "unterminated entity reference")]
+    #[case("<a>&#xd800;</a>", "unknown entity: &#xd800;")]
+    fn parse_music_xml_fails(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(
+            parse_music_xml(text).unwrap_err(),
+            ScoreError::InvalidMusicXml(expected.into())
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "<attributes><divisions>0</divisions></attributes>",
+        "<divisions> must be positive"
+    )]
+    #[case(
+        "<attributes><divisions>NaN</divisions></attributes>",
+        "<divisions> must be positive"
+    )]
+    #[case(
+        "<attributes><divisions>x</divisions></attributes>",
+        "<divisions> must be a number: x"
+    )]
+    #[case("<sound tempo='0'/>", "invalid tempo: 0")]
+    #[case("<direction><sound tempo='inf'/></direction>", "invalid tempo: inf")]
+    #[case("<backup/>", "<backup> has no <duration>")]
+    #[case(
+        "<backup><duration>1</duration></backup>",
+        "<backup> moves before the start of the part"
+    )]
+    #[case(
+        "<forward><duration>-1</duration></forward>",
+        "<duration> must be a finite non-negative number: -1"
+    )]
+    #[case(
+        "<note><rest/><duration>inf</duration></note>",
+        "<duration> must be a finite non-negative number: inf"
+    )]
+    #[case(
+        "<note><duration>1</duration></note>",
+        "<note> has neither <pitch> nor <rest>"
+    )]
+    #[case(
+        "<note><pitch><step>C</step></pitch><duration>1</duration></note>",
+        "<pitch> has no <octave>"
+    )]
+    #[case(
+        "<note><pitch><step>C</step><octave>x</octave></pitch></note>",
+        "<octave> must be a number: x"
+    )]
+    #[case(
+        "<note><pitch><step>C</step><octave>2147483647</octave></pitch></note>",
+        "pitch is out of range: 25769803776"
+    )]
+    #[case(
+        "<note><pitch><step>C</step><alter>-1e30</alter><octave>4</octave></pitch></note>",
+        "pitch is out of range: -9223372036854775748"
+    )]
+    #[case(
+        "<note><pitch><step>G</step><alter>1</alter><octave>9</octave></pitch></note>",
+        "pitch is out of range: 128"
+    )]
+    fn parse_music_xml_fails_with_invalid_measure(#[case] measure: &str, #[case] expected: &str) {
+        assert_eq!(
+            parse_music_xml(&score_partwise(measure)).unwrap_err(),
+            ScoreError::InvalidMusicXml(expected.into())
+        );
+    }
+
+    #[rstest]
+    fn parse_music_xml_reads_first_voice() {
+        let notes = parse_music_xml(
+            "<score-partwise><part>
+               <measure>
+                 <note><grace/><voice>3</voice><pitch><step>D</step><octave>4</octave></pitch></note>
+                 <note>
+                   <voice>2</voice><pitch><step>C</step><octave>4</octave></pitch>
+                   <duration>1</duration><lyric><text>ら</text></lyric>
+                 </note>
+                 <backup><duration>1</duration></backup>
+                 <note>
+                   <voice>1</voice><pitch><step>E</step><octave>4</octave></pitch>
+                   <duration>1</duration><lyric><text>り</text></lyric>
+                 </note>
+               </measure>
+               <measure>
+                 <note>
+                   <voice>2</voice><pitch><step>D</step><octave>4</octave></pitch>
+                   <duration>1</duration><lyric><text>る</text></lyric>
+                 </note>
+               </measure>
+             </part></score-partwise>",
+        )
+        .unwrap();
+        assert_eq!(
+            notes,
+            [
+                ScoreNote {
+                    lyric: Some("ら".into()),
+                    note_number: 60,
+                    start: 0.,
+                    end: 0.5,
+                },
+                ScoreNote {
+                    lyric: Some("る".into()),
+                    note_number: 62,
+                    start: 0.5,
+                    end: 1.,
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    fn parse_music_xml_does_not_panic_on_broken_input() {
+        // 途中で切れたもの、1文字を取り除いたものは、エラーになるか一部の音符を読み込む
+        let boundaries = MUSIC_XML
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([MUSIC_XML.len()])
+            .collect::<Vec<_>>();
+        for &end in &boundaries {
+            let _ = parse_music_xml(&MUSIC_XML[..end]);
+        }
+        for window in boundaries.windows(2) {
+            let text = [&MUSIC_XML[..window[0]], &MUSIC_XML[window[1]..]].concat();
+            let _ = parse_music_xml(&text);
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use super::model::{AccentPhraseModel, TextSpan};
+use super::synthesis_engine::PAUSE_PUNCTUATIONS;
+
+/// OpenJTalkで解析したアクセント句とモーラに、元になった入力テキスト中の範囲を設定する
+///
+/// 入力テキスト中のかなをモーラの読みと対応付け、漢字などの読みは前後のかなとの対応から求める。
+/// 対応付けられなかった息継ぎの区切りのアクセント句とモーラには範囲を設定しない
+pub(super) fn assign_source_spans(text: &str, accent_phrases: &mut [AccentPhraseModel]) {
+    let text = text.chars().collect::<Vec<_>>();
+    let (breath_group_spans, pause_spans) = split_breath_groups(&text);
+    let breath_groups = accent_phrases
+        .split_inclusive_mut(|accent_phrase| accent_phrase.pause_mora().is_some())
+        .collect::<Vec<_>>();
+    if breath_groups.len() != breath_group_spans.len() {
+        return;
+    }
+
+    for (i, (breath_group, breath_group_span)) in
+        std::iter::zip(breath_groups, breath_group_spans).enumerate()
+    {
+        let readings = breath_group
+            .iter()
+            .flat_map(|accent_phrase| accent_phrase.moras())
+            .map(|mora| mora.text().as_str())
+            .collect::<Vec<_>>();
+        if let Some(mora_spans) = align_moras(&text, breath_group_span, &readings) {
+            let mut mora_spans = mora_spans.into_iter();
+            for accent_phrase in breath_group.iter_mut() {
+                let mut accent_phrase_span: Option<TextSpan> = None;
+                for mora in accent_phrase.moras_mut() {
+                    let mora_span = mora_spans.next().unwrap();
+                    mora.set_source_span(Some(mora_span));
+                    accent_phrase_span = Some(match accent_phrase_span {
+                        Some(span) => TextSpan {
+                            start: span.start,
+                            end: span.end.max(mora_span.end),
+                        },
+                        None => mora_span,
+                    });
+                }
+                accent_phrase.set_source_span(accent_phrase_span);
+            }
+        }
+        if let Some(pause_mora) = breath_group
+            .last_mut()
+            .and_then(|accent_phrase| accent_phrase.pause_mora_mut())
+        {
+            pause_mora.set_source_span(pause_spans.get(i).copied());
+        }
+    }
+}
+
+/// テキストを息継ぎの区切りとなる句読点で分け、区切りごとの範囲と句読点の範囲を返す
+///
+/// `extract_pause_punctuations`と同じく、連続する句読点は一つの区切りとし、テキストの先頭と末尾にあるものは含めない
+fn split_breath_groups(text: &[char]) -> (Vec<TextSpan>, Vec<TextSpan>) {
+    let mut breath_group_spans = Vec::new();
+    let mut pause_spans = Vec::new();
+    let mut breath_group_span: Option<TextSpan> = None;
+    let mut pause_span: Option<TextSpan> = None;
+    for (i, &c) in text.iter().enumerate() {
+        if PAUSE_PUNCTUATIONS.contains(&c) {
+            if breath_group_span.is_some() {
+                pause_span.get_or_insert(TextSpan { start: i, end: i }).end = i + 1;
+            }
+        } else if !c.is_whitespace() {
+            if let Some(pause_span) = pause_span.take() {
+                breath_group_spans.extend(breath_group_span.take());
+                pause_spans.push(pause_span);
+            }
+            breath_group_span
+                .get_or_insert(TextSpan { start: i, end: i })
+                .end = i + 1;
+        }
+    }
+    breath_group_spans.extend(breath_group_span);
+    (breath_group_spans, pause_spans)
+}
+
+/// 息継ぎの区切りの範囲のテキストをモーラの読みと対応付け、各モーラの範囲を返す
+///
+/// かな以外の文字が続く範囲は、その後に続くかなと対応するモーラの手前までの読みとする。
+/// 対応付けられないときは`None`を返す
+fn align_moras(text: &[char], breath_group: TextSpan, readings: &[&str]) -> Option<Vec<TextSpan>> {
+    let mut mora_spans = Vec::with_capacity(readings.len());
+    let mut position = breath_group.start;
+    while position < breath_group.end {
+        let is_kana = to_katakana(text[position]).is_some();
+        let run_end = (position..breath_group.end)
+            .find(|&i| to_katakana(text[i]).is_some() != is_kana)
+            .unwrap_or(breath_group.end);
+        if is_kana {
+            let spans = match_kana(text, position, run_end, readings, mora_spans.len())?;
+            mora_spans.extend(spans);
+        } else {
+            let next_kana_end = (run_end..breath_group.end)
+                .find(|&i| to_katakana(text[i]).is_none())
+                .unwrap_or(breath_group.end);
+            let is_last = next_kana_end == breath_group.end;
+            // 記号や空白だけのときは読みがないこともある
+            let is_read = text[position..run_end].iter().any(|c| c.is_alphanumeric());
+            let reading_end =
+                (mora_spans.len() + is_read as usize..=readings.len()).find(|&index| {
+                    match_kana(text, run_end, next_kana_end, readings, index)
+                        .map(|spans| !is_last || index + spans.len() == readings.len())
+                        == Some(true)
+                })?;
+            let span = trim_whitespace(text, position, run_end);
+            mora_spans.resize(reading_end, span);
+        }
+        position = run_end;
+    }
+    (mora_spans.len() == readings.len()).then_some(mora_spans)
+}
+
+/// `start`から`end`までのかなを`mora_index`からのモーラの読みと順に対応付け、各モーラの範囲を返す
+fn match_kana(
+    text: &[char],
+    start: usize,
+    end: usize,
+    readings: &[&str],
+    mora_index: usize,
+) -> Option<Vec<TextSpan>> {
+    let mut spans = Vec::new();
+    let mut position = start;
+    while position < end {
+        let reading = readings.get(mora_index + spans.len())?;
+        let length = reading.chars().count();
+        let is_matched = position + length <= end
+            && std::iter::zip(&text[position..position + length], reading.chars()).all(
+                |(&c, reading)| to_katakana(c).map(|kana| is_read_as(kana, reading)) == Some(true),
+            );
+        if !is_matched {
+            return None;
+        }
+        spans.push(TextSpan {
+            start: position,
+            end: position + length,
+        });
+        position += length;
+    }
+    Some(spans)
+}
+
+/// かなを全角カタカナにする。かなでなければ`None`を返す
+fn to_katakana(c: char) -> Option<char> {
+    match c {
+        'ぁ'..='ゖ' => char::from_u32(c as u32 + ('ァ' as u32 - 'ぁ' as u32)),
+        'ァ'..='ヺ' | 'ー' => Some(c),
+        _ => None,
+    }
+}
+
+/// 入力テキストのかながモーラの読みの文字として読まれうるか
+///
+/// 助詞の「は」「へ」「を」や、「おう」「えい」のような長音も考慮する
+fn is_read_as(kana: char, reading: char) -> bool {
+    kana == reading
+        || matches!(
+            (kana, reading),
+            ('ハ', 'ワ')
+                | ('ヘ', 'エ')
+                | ('ヲ', 'オ')
+                | ('ヅ', 'ズ')
+                | ('ヂ', 'ジ')
+                | ('ウ', 'オ')
+                | ('イ', 'エ')
+        )
+        || kana == 'ー' && "アイウエオ".contains(reading)
+}
+
+fn trim_whitespace(text: &[char], start: usize, end: usize) -> TextSpan {
+    match (start..end).find(|&i| !text[i].is_whitespace()) {
+        Some(trimmed_start) => TextSpan {
+            start: trimmed_start,
+            end: (trimmed_start..end)
+                .rfind(|&i| !text[i].is_whitespace())
+                .unwrap()
+                + 1,
+        },
+        None => TextSpan { start, end },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MoraModel;
+    use crate::*;
+    use pretty_assertions::assert_eq;
+
+    fn accent_phrase(readings: &[&str], pause_mora: Option<&str>) -> AccentPhraseModel {
+        let mora = |text: &str| MoraModel::new(text.into(), None, None, "a".into(), 0., 0.);
+        AccentPhraseModel::new(
+            readings.iter().map(|&reading| mora(reading)).collect(),
+            1,
+            pause_mora.map(mora),
+            false,
+        )
+    }
+
+    fn span((start, end): (usize, usize)) -> TextSpan {
+        TextSpan { start, end }
+    }
+
+    #[rstest]
+    #[case("同じ、文章、です。", &[(0, 2), (3, 5), (6, 8)], &[(2, 3), (5, 6)])]
+    #[case("、同じ、、 文章です。", &[(1, 3), (6, 10)], &[(3, 5)])]
+    #[case("", &[], &[])]
+    fn split_breath_groups_works(
+        #[case] text: &str,
+        #[case] expected_breath_groups: &[(usize, usize)],
+        #[case] expected_pauses: &[(usize, usize)],
+    ) {
+        let text = text.chars().collect::<Vec<_>>();
+        let (breath_groups, pauses) = split_breath_groups(&text);
+        assert_eq!(
+            breath_groups,
+            expected_breath_groups
+                .iter()
+                .copied()
+                .map(span)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            pauses,
+            expected_pauses
+                .iter()
+                .copied()
+                .map(span)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[rstest]
+    fn assign_source_spans_works() {
+        let mut accent_phrases = vec![
+            accent_phrase(&["ト", "オ", "キョ", "オ"], None),
+            accent_phrase(&["タ", "ワ", "ア", "ワ"], Some("、")),
+            accent_phrase(&["タ", "カ", "イ"], None),
+        ];
+        assign_source_spans("東京タワーは、高い。", &mut accent_phrases);
+
+        let mora_spans = |accent_phrase: &AccentPhraseModel| {
+            accent_phrase
+                .moras()
+                .iter()
+                .map(|mora| *mora.source_span())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(mora_spans(&accent_phrases[0]), [Some(span((0, 2))); 4]);
+        assert_eq!(*accent_phrases[0].source_span(), Some(span((0, 2))));
+        assert_eq!(
+            mora_spans(&accent_phrases[1]),
+            [(2, 3), (3, 4), (4, 5), (5, 6)].map(|s| Some(span(s)))
+        );
+        assert_eq!(*accent_phrases[1].source_span(), Some(span((2, 6))));
+        assert_eq!(
+            *accent_phrases[1]
+                .pause_mora()
+                .as_ref()
+                .unwrap()
+                .source_span(),
+            Some(span((6, 7)))
+        );
+        assert_eq!(
+            mora_spans(&accent_phrases[2]),
+            [(7, 8), (7, 8), (8, 9)].map(|s| Some(span(s)))
+        );
+        assert_eq!(*accent_phrases[2].source_span(), Some(span((7, 9))));
+    }
+
+    #[rstest]
+    fn assign_source_spans_skips_unaligned_breath_groups() {
+        let mut accent_phrases = vec![
+            accent_phrase(&["コ", "ン", "ニ", "チ", "ワ"], Some("、")),
+            accent_phrase(&["サ", "ヨ", "オ", "ナ", "ラ"], None),
+        ];
+        assign_source_spans("こんばんは、さようなら", &mut accent_phrases);
+
+        assert_eq!(*accent_phrases[0].source_span(), None);
+        assert_eq!(*accent_phrases[0].moras()[0].source_span(), None);
+        assert_eq!(
+            *accent_phrases[0]
+                .pause_mora()
+                .as_ref()
+                .unwrap()
+                .source_span(),
+            Some(span((5, 6)))
+        );
+        assert_eq!(*accent_phrases[1].source_span(), Some(span((6, 11))));
+        assert_eq!(
+            *accent_phrases[1].moras()[1].source_span(),
+            Some(span((7, 8)))
+        );
+    }
+}
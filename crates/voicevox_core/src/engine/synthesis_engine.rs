@@ -1,24 +1,104 @@
 use derive_new::new;
 use std::io::{Cursor, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use super::full_context_label::Utterance;
 use super::open_jtalk::OpenJtalk;
+use super::pitch_shift::{pitch_offset, shift_voiced_pitches};
+use super::source_span::assign_source_spans;
 use super::*;
 // use crate::numerics::F32Ext as _;
 use crate::InferenceCore;
 
-const UNVOICED_MORA_PHONEME_LIST: &[&str] = &["A", "I", "U", "E", "O", "cl", "pau"];
+/// ポーズとして扱われる句読点
+pub(super) const PAUSE_PUNCTUATIONS: &[char] = &[
+    '、', '。', '，', '．', ',', '.', '！', '？', '!', '?', '…', '‥',
+];
 
-#[allow(dead_code)]
-const MORA_PHONEME_LIST: &[&str] = &[
+/// 語尾を上げる文の終わりとして扱う句読点。感嘆符で終わる文も疑問文と同じく語尾を上げる
+const INTERROGATION_MARKS: &[char] = &['？', '?', '！', '!'];
+
+pub(super) const UNVOICED_MORA_PHONEME_LIST: &[&str] = &["A", "I", "U", "E", "O", "cl", "pau"];
+
+pub(super) const MORA_PHONEME_LIST: &[&str] = &[
     "a", "i", "u", "e", "o", "N", "A", "I", "U", "E", "O", "cl", "pau",
 ];
 
+/// アクセント句ごとの音量が変わる境界で、音量を滑らかに変える秒数
+const VOLUME_TRANSITION_LENGTH: f32 = 0.05;
+
+/// 音声合成モデルに入力する音素ごとの値
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct DecodeInput {
+    pub(super) phoneme_ids: Vec<i64>,
+    pub(super) pitches: Vec<f32>,
+    pub(super) durations: Vec<f32>,
+    /// 先頭の無音、各アクセント句、末尾の無音の順に、それぞれの音素の数
+    pub(super) segment_lengths: Vec<usize>,
+    /// 先頭と末尾の無音を含む、各モーラの母音の位置
+    pub(super) vowel_indices: Vec<usize>,
+    /// `vowel_indices`の各母音に加えるビブラート
+    pub(super) vibratos: Vec<Option<Vibrato>>,
+    /// 音素ごとの音量
+    pub(super) volumes: Vec<f32>,
+}
+
+impl DecodeInput {
+    /// 合成した波形に音素ごとの音量を掛ける
+    ///
+    /// 音量の変わる音素の境界では、境界を中心に[`VOLUME_TRANSITION_LENGTH`]秒かけて線形に音量を変える
+    pub(super) fn apply_volumes(&self, wave: &mut [f32]) {
+        if let [volume, rest @ ..] = self.volumes.as_slice() {
+            if rest.iter().all(|v| v == volume) {
+                wave.iter_mut().for_each(|sample| *sample *= volume);
+                return;
+            }
+        }
+        let frame_lengths = frame_lengths(&self.durations);
+        let num_frames = frame_lengths.iter().sum::<usize>();
+        if num_frames == 0 {
+            return;
+        }
+        let to_sample = |frame: usize| frame * wave.len() / num_frames;
+
+        let mut gains = Vec::with_capacity(wave.len());
+        let mut boundaries = Vec::new();
+        let mut frame = 0;
+        for (&length, &volume) in std::iter::zip(&frame_lengths, &self.volumes) {
+            if matches!(gains.last(), Some(&last) if last != volume) {
+                boundaries.push(to_sample(frame));
+            }
+            frame += length;
+            gains.resize(to_sample(frame), volume);
+        }
+
+        let half = VOLUME_TRANSITION_LENGTH / 2. * SynthesisEngine::DEFAULT_SAMPLING_RATE as f32;
+        for boundary in boundaries {
+            let (Some(&from), Some(&to)) = (gains.get(boundary.saturating_sub(1)), gains.get(boundary)) else {
+                continue;
+            };
+            let start = boundary.saturating_sub(half as usize);
+            let end = (boundary + half as usize).min(gains.len());
+            for (sample, gain) in gains[start..end].iter_mut().enumerate() {
+                let time = (start + sample) as f32 + 0.5 - (boundary as f32 - half);
+                let ratio = (time / (2. * half)).clamp(0., 1.);
+                *gain = from + (to - from) * ratio;
+            }
+        }
+
+        for (sample, gain) in std::iter::zip(wave, gains) {
+            *sample *= gain;
+        }
+    }
+}
+
 #[derive(new)]
 pub struct SynthesisEngine {
     inference_core: InferenceCore,
     open_jtalk: OpenJtalk,
+    /// テキストの解析で初めて必要になったときに読み込む辞書のディレクトリ
+    #[new(default)]
+    lazy_openjtalk_dict_dir: Option<PathBuf>,
 }
 
 #[allow(unsafe_code)]
@@ -40,76 +120,97 @@ impl SynthesisEngine {
         text: impl AsRef<str>,
         speaker_id: u32,
     ) -> Result<Vec<AccentPhraseModel>> {
+        let accent_phrases = self.analyze_text(text)?;
+        self.replace_mora_data(&accent_phrases, speaker_id)
+    }
+
+    /// 音声合成モデルを使わずに、OpenJTalkでテキストの読みとアクセントを解析する
+    ///
+    /// 音素の長さと音高は全て0とする
+    pub fn analyze_text(&mut self, text: impl AsRef<str>) -> Result<Vec<AccentPhraseModel>> {
+        self.ensure_openjtalk_dict_loaded()?;
         if text.as_ref().is_empty() {
             return Ok(Vec::new());
         }
 
         let utterance = Utterance::extract_full_context_label(&mut self.open_jtalk, text.as_ref())?;
 
-        let accent_phrases: Vec<AccentPhraseModel> = utterance
+        // 息継ぎの区切りの数と句読点の数が一致しないときは、元の句読点を特定できないので指定しない
+        let pause_punctuations = Some(extract_pause_punctuations(text.as_ref()))
+            .filter(|punctuations| punctuations.len() + 1 == utterance.breath_groups().len());
+        // OpenJTalkは"！"や"！？"、半角の"?"で終わる文を疑問文としないことがあるため、元のテキストからも判定する
+        let interrogative_breath_groups = Some(extract_interrogative_breath_groups(text.as_ref()))
+            .filter(|interrogatives| interrogatives.len() == utterance.breath_groups().len());
+
+        let mut accent_phrases = utterance_to_accent_phrases(
+            &utterance,
+            pause_punctuations.as_deref(),
+            interrogative_breath_groups.as_deref(),
+        );
+        assign_source_spans(text.as_ref(), &mut accent_phrases);
+
+        Ok(accent_phrases)
+    }
+
+    /// OpenJTalkでテキストを解析し、NJDのノードを返す
+    pub fn extract_njd_nodes(&mut self, text: impl AsRef<str>) -> Result<Vec<NjdNodeModel>> {
+        self.ensure_openjtalk_dict_loaded()?;
+        if text.as_ref().is_empty() {
+            return Ok(Vec::new());
+        }
+        self.open_jtalk
+            .extract_njd_nodes(text)
+            .map_err(|e| FullContextLabelError::from(e).into())
+    }
+
+    /// 指定したアクセント句のアクセント位置を、読みをOpenJTalkで解析して推定する
+    ///
+    /// 辞書が読み込めないときや、解析結果のモーラ数が一致しないときは、アクセント位置を変更しない
+    pub fn predict_missing_accents(
+        &mut self,
+        accent_phrases: &mut [AccentPhraseModel],
+        missing_accent_indices: &[usize],
+    ) {
+        if self.ensure_openjtalk_dict_loaded().is_err() {
+            return;
+        }
+        for &index in missing_accent_indices {
+            let accent_phrase = &mut accent_phrases[index];
+            if let Some(accent) = self.predict_accent(accent_phrase.moras()) {
+                accent_phrase.set_accent(accent);
+            }
+        }
+    }
+
+    fn predict_accent(&mut self, moras: &[MoraModel]) -> Option<usize> {
+        let reading = moras
+            .iter()
+            .map(|mora| mora.text().as_str())
+            .collect::<String>();
+        let utterance =
+            Utterance::extract_full_context_label(&mut self.open_jtalk, reading).ok()?;
+        let predicted_accent_phrases = utterance
             .breath_groups()
             .iter()
-            .enumerate()
-            .fold(Vec::new(), |mut accum_vec, (i, breath_group)| {
-                accum_vec.extend(breath_group.accent_phrases().iter().enumerate().map(
-                    |(j, accent_phrase)| {
-                        let moras = accent_phrase
-                            .moras()
-                            .iter()
-                            .map(|mora| {
-                                let mora_text = mora
-                                    .phonemes()
-                                    .iter()
-                                    .map(|phoneme| phoneme.phoneme().to_string())
-                                    .collect::<Vec<_>>()
-                                    .join("");
-
-                                let (consonant, consonant_length) =
-                                    if let Some(consonant) = mora.consonant() {
-                                        (Some(consonant.phoneme().to_string()), Some(0.))
-                                    } else {
-                                        (None, None)
-                                    };
-
-                                MoraModel::new(
-                                    mora_to_text(mora_text),
-                                    consonant,
-                                    consonant_length,
-                                    mora.vowel().phoneme().into(),
-                                    0.,
-                                    0.,
-                                )
-                            })
-                            .collect();
-
-                        let pause_mora = if i != utterance.breath_groups().len() - 1
-                            && j == breath_group.accent_phrases().len() - 1
-                        {
-                            Some(MoraModel::new(
-                                "、".into(),
-                                None,
-                                None,
-                                "pau".into(),
-                                0.,
-                                0.,
-                            ))
-                        } else {
-                            None
-                        };
-
-                        AccentPhraseModel::new(
-                            moras,
-                            *accent_phrase.accent(),
-                            pause_mora,
-                            *accent_phrase.is_interrogative(),
-                        )
-                    },
-                ));
-
-                accum_vec
-            });
-
-        self.replace_mora_data(&accent_phrases, speaker_id)
+            .flat_map(|breath_group| breath_group.accent_phrases())
+            .collect::<Vec<_>>();
+        let mora_count = predicted_accent_phrases
+            .iter()
+            .map(|accent_phrase| accent_phrase.moras().len())
+            .sum::<usize>();
+        if mora_count != moras.len() {
+            return None;
+        }
+        // 複数のアクセント句に分かれたときは、最初に音高が下がる位置をアクセント位置とする
+        let mut offset = 0;
+        for accent_phrase in predicted_accent_phrases {
+            let accent = *accent_phrase.accent();
+            if 0 < accent && accent < accent_phrase.moras().len() {
+                return Some(offset + accent);
+            }
+            offset += accent_phrase.moras().len();
+        }
+        Some(moras.len())
     }
 
     pub fn replace_mora_data(
@@ -121,6 +222,58 @@ impl SynthesisEngine {
         self.replace_mora_pitch(&accent_phrases, speaker_id, Some(&pitches))
     }
 
+    /// アクセント句の音素の長さと音高を別の話者で推論し直す
+    ///
+    /// `source_speaker_id`を指定したときは、固定したモーラの音高を話者ごとの平均の音高の差だけずらす。
+    /// 音高は対数F0なので、F0を平均の比で拡大することになる
+    pub fn retarget_accent_phrases(
+        &mut self,
+        accent_phrases: &[AccentPhraseModel],
+        speaker_id: u32,
+        source_speaker_id: Option<u32>,
+    ) -> Result<Vec<AccentPhraseModel>> {
+        let mut new_accent_phrases = self.replace_mora_data(accent_phrases, speaker_id)?;
+        let Some(source_speaker_id) = source_speaker_id else {
+            return Ok(new_accent_phrases);
+        };
+        let source_mean_pitch = self.mean_pitch(accent_phrases, source_speaker_id)?;
+        let target_mean_pitch = self.mean_pitch(accent_phrases, speaker_id)?;
+        if let (Some(source_mean_pitch), Some(target_mean_pitch)) =
+            (source_mean_pitch, target_mean_pitch)
+        {
+            let locked_moras = new_accent_phrases
+                .iter_mut()
+                .flat_map(|accent_phrase| accent_phrase.moras_mut())
+                .filter(|mora| *mora.is_locked() && *mora.pitch() > 0.);
+            for mora in locked_moras {
+                mora.set_pitch(mora.pitch() + target_mean_pitch - source_mean_pitch);
+            }
+        }
+        Ok(new_accent_phrases)
+    }
+
+    /// 固定したモーラも含めて音高を推論し、有声のモーラの音高の平均を求める
+    fn mean_pitch(
+        &mut self,
+        accent_phrases: &[AccentPhraseModel],
+        speaker_id: u32,
+    ) -> Result<Option<f32>> {
+        let mut accent_phrases = accent_phrases.to_vec();
+        for mora in accent_phrases
+            .iter_mut()
+            .flat_map(|accent_phrase| accent_phrase.moras_mut())
+        {
+            mora.set_is_locked(false);
+        }
+        let accent_phrases = self.replace_mora_pitch(&accent_phrases, speaker_id, None)?;
+        let pitches = to_flatten_moras(&accent_phrases)
+            .iter()
+            .map(|mora| *mora.pitch())
+            .filter(|&pitch| pitch > 0.)
+            .collect::<Vec<_>>();
+        Ok((!pitches.is_empty()).then(|| pitches.iter().sum::<f32>() / pitches.len() as f32))
+    }
+
     pub fn replace_phoneme_length(
         &mut self,
         accent_phrases: &[AccentPhraseModel],
@@ -135,47 +288,29 @@ impl SynthesisEngine {
         )?;
 
         let mut index = 1;
-        let new_accent_phrases = accent_phrases
-            .iter()
-            .map(|accent_phrase| {
-                AccentPhraseModel::new(
-                    accent_phrase
-                        .moras()
-                        .iter()
-                        .map(|mora| {
-                            let consonant_is_some = mora.consonant().is_some();
-                            let new_mora = MoraModel::new(
-                                mora.text().clone(),
-                                mora.consonant().clone(),
-                                mora.consonant().as_ref().map(|_| phoneme_length[index]),
-                                mora.vowel().clone(),
-                                phoneme_length[index + consonant_is_some as usize],
-                                *mora.pitch(),
-                            );
-                            index += 1;
-                            if consonant_is_some {
-                                index += 1;
-                            }
-                            new_mora
-                        })
-                        .collect(),
-                    *accent_phrase.accent(),
-                    accent_phrase.pause_mora().as_ref().map(|pause_mora| {
-                        let new_pause_mora = MoraModel::new(
-                            pause_mora.text().clone(),
-                            pause_mora.consonant().clone(),
-                            *pause_mora.consonant_length(),
-                            pause_mora.vowel().clone(),
-                            phoneme_length[index],
-                            *pause_mora.pitch(),
-                        );
-                        index += 1;
-                        new_pause_mora
-                    }),
-                    *accent_phrase.is_interrogative(),
-                )
-            })
-            .collect();
+        let mut new_accent_phrases = accent_phrases.to_vec();
+        for accent_phrase in &mut new_accent_phrases {
+            for mora in accent_phrase.moras_mut() {
+                let consonant_is_some = mora.consonant().is_some();
+                if !mora.is_locked() {
+                    if consonant_is_some {
+                        mora.set_consonant_length(Some(phoneme_length[index]));
+                    }
+                    mora.set_vowel_length(phoneme_length[index + consonant_is_some as usize]);
+                }
+                index += 1;
+                if consonant_is_some {
+                    index += 1;
+                }
+            }
+            let is_pause_length_explicit = *accent_phrase.is_pause_length_explicit();
+            if let Some(pause_mora) = accent_phrase.pause_mora_mut() {
+                if !(*pause_mora.is_locked() || is_pause_length_explicit) {
+                    pause_mora.set_vowel_length(phoneme_length[index]);
+                }
+                index += 1;
+            }
+        }
 
         Ok((new_accent_phrases, pitches))
     }
@@ -199,91 +334,211 @@ impl SynthesisEngine {
         }
 
         let mut index = 1;
-        let new_accent_phrases = accent_phrases
-            .iter()
-            .map(|accent_phrase| {
-                AccentPhraseModel::new(
-                    accent_phrase
-                        .moras()
-                        .iter()
-                        .map(|mora| {
-                            if mora.consonant().is_some() {
-                                index += 1;
-                            }
-                            if UNVOICED_MORA_PHONEME_LIST
-                                .iter()
-                                .any(|phoneme| *phoneme == mora.vowel())
-                            {
-                                pitches[index] = 0.;
-                            }
-                            let new_mora = MoraModel::new(
-                                mora.text().clone(),
-                                mora.consonant().clone(),
-                                *mora.consonant_length(),
-                                mora.vowel().clone(),
-                                *mora.vowel_length(),
-                                pitches[index],
-                            );
-                            index += 1;
-                            new_mora
-                        })
-                        .collect(),
-                    *accent_phrase.accent(),
-                    accent_phrase.pause_mora().as_ref().map(|pause_mora| {
-                        let new_pause_mora = MoraModel::new(
-                            pause_mora.text().clone(),
-                            pause_mora.consonant().clone(),
-                            *pause_mora.consonant_length(),
-                            pause_mora.vowel().clone(),
-                            *pause_mora.vowel_length(),
-                            0.,
-                        );
-                        index += 1;
-                        new_pause_mora
-                    }),
-                    *accent_phrase.is_interrogative(),
-                )
-            })
-            .collect();
+        let mut new_accent_phrases = accent_phrases.to_vec();
+        for accent_phrase in &mut new_accent_phrases {
+            for mora in accent_phrase.moras_mut() {
+                if mora.consonant().is_some() {
+                    index += 1;
+                }
+                if UNVOICED_MORA_PHONEME_LIST
+                    .iter()
+                    .any(|phoneme| *phoneme == mora.vowel())
+                {
+                    pitches[index] = 0.;
+                }
+                if !mora.is_locked() {
+                    mora.set_pitch(pitches[index]);
+                }
+                index += 1;
+            }
+            if let Some(pause_mora) = accent_phrase.pause_mora_mut() {
+                pause_mora.set_pitch(0.);
+                index += 1;
+            }
+        }
 
         Ok(new_accent_phrases)
     }
 
+    /// AudioQueryから音声合成する
+    ///
+    /// 音量を適用した、[`Self::DEFAULT_SAMPLING_RATE`]のモノラル波形を返す。
+    /// `pitch_contour`を指定したときは、モーラの音高を滑らかに繋いだフレームごとの音高で音声合成する。
+    /// AudioQueryかモーラにビブラートを指定したときも、フレームごとの音高に加えて音声合成する。
+    /// ただしフレームごとの音高に対応していないモデルでは、ビブラートは無視する
     pub fn synthesis(
         &mut self,
         query: &AudioQueryModel,
         speaker_id: u32,
-        enable_interrogative_upspeak: bool,
+        interrogative_upspeak: Option<&InterrogativeUpspeak>,
+        pitch_contour: Option<&PitchContour>,
     ) -> Result<Vec<f32>> {
+        let decode_input = Self::create_decode_input(query, interrogative_upspeak)?;
+        if let Some(frame_pitches) =
+            self.decode_frame_pitches(&decode_input, speaker_id, pitch_contour)?
+        {
+            let mut wave = self.inference_core_mut().decode_with_frame_pitch(
+                &decode_input.phoneme_ids,
+                &frame_pitches,
+                &decode_input.durations,
+                speaker_id,
+            )?;
+            decode_input.apply_volumes(&mut wave);
+            return Ok(wave);
+        }
+        let mut wave = self.inference_core_mut().decode(
+            &decode_input.phoneme_ids,
+            &decode_input.pitches,
+            &decode_input.durations,
+            speaker_id,
+        )?;
+        decode_input.apply_volumes(&mut wave);
+        Ok(wave)
+    }
+
+    /// `pitch_contour`を指定したとき、またはビブラートを指定したときに、音声合成に用いるフレームごとの音高を返す
+    ///
+    /// ビブラートはフレームごとの音高に対応したモデルでのみ加え、それ以外のモデルでは無視する。
+    /// フレームごとの音高を用いないときは`None`を返す
+    pub(super) fn decode_frame_pitches(
+        &self,
+        decode_input: &DecodeInput,
+        speaker_id: u32,
+        pitch_contour: Option<&PitchContour>,
+    ) -> Result<Option<Vec<f32>>> {
+        let has_vibrato = decode_input.vibratos.iter().any(Option::is_some)
+            && self.inference_core().supports_frame_pitch(speaker_id)?;
+        if pitch_contour.is_none() && !has_vibrato {
+            return Ok(None);
+        }
+        let mut frame_pitches = match pitch_contour {
+            Some(pitch_contour) => pitch_contour.frame_pitches(
+                &decode_input.pitches,
+                &decode_input.durations,
+                &decode_input.vowel_indices,
+            ),
+            None => phoneme_frame_pitches(&decode_input.pitches, &decode_input.durations),
+        };
+        apply_vibratos(
+            &mut frame_pitches,
+            &decode_input.durations,
+            &decode_input.vowel_indices,
+            &decode_input.vibratos,
+        );
+        Ok(Some(frame_pitches))
+    }
+
+    /// AudioQueryの音素の長さと、フレームごとの音高から音声合成する
+    ///
+    /// AudioQueryのモーラの音高と抑揚は用いず、`pitch_scale`と`pitch_shift`、`target_mean_f0`による音高の調整だけを`frame_pitch`に適用する
+    pub fn synthesis_with_frame_pitch(
+        &mut self,
+        query: &AudioQueryModel,
+        speaker_id: u32,
+        frame_pitch: &FramePitch,
+    ) -> Result<Vec<f32>> {
+        let decode_input = Self::create_decode_input(query, None)?;
+        let pitch_scale = 2.0_f32.powf(*query.pitch_scale());
+        let mut frame_pitches = frame_pitch
+            .to_frames(frame_count(&decode_input.durations))?
+            .into_iter()
+            .map(|pitch| pitch * pitch_scale)
+            .collect::<Vec<_>>();
+        let offset = pitch_offset(query, frame_pitches.iter().copied());
+        shift_voiced_pitches(&mut frame_pitches, offset);
+        let mut wave = self.inference_core_mut().decode_with_frame_pitch(
+            &decode_input.phoneme_ids,
+            &frame_pitches,
+            &decode_input.durations,
+            speaker_id,
+        )?;
+        decode_input.apply_volumes(&mut wave);
+        Ok(wave)
+    }
+
+    /// AudioQueryから音声合成モデルに入力する音素ごとの値を作る
+    pub(super) fn create_decode_input(
+        query: &AudioQueryModel,
+        interrogative_upspeak: Option<&InterrogativeUpspeak>,
+    ) -> Result<DecodeInput> {
         let speed_scale = *query.speed_scale();
         let pitch_scale = *query.pitch_scale();
         let intonation_scale = *query.intonation_scale();
+        let volume_scale = *query.volume_scale();
         let pre_phoneme_length = *query.pre_phoneme_length();
         let post_phoneme_length = *query.post_phoneme_length();
 
-        let accent_phrases = if enable_interrogative_upspeak {
-            adjust_interrogative_accent_phrases(query.accent_phrases().as_slice())
+        let mut accent_phrases = if let Some(interrogative_upspeak) = interrogative_upspeak {
+            adjust_interrogative_accent_phrases(
+                query.accent_phrases().as_slice(),
+                interrogative_upspeak,
+            )
         } else {
             query.accent_phrases().clone()
         };
+        for accent_phrase in &mut accent_phrases {
+            if let Some(pause_length) = adjust_pause_length(query, accent_phrase) {
+                if let Some(pause_mora) = accent_phrase.pause_mora_mut() {
+                    pause_mora.set_vowel_length(pause_length);
+                }
+            }
+        }
+        if let Some(prosody_variation) = query.prosody_variation() {
+            prosody_variation.apply(&mut accent_phrases);
+        }
+        // 揺らぎで音高を平坦でなくしないよう、話し方の変換は揺らぎを加えた後に行う
+        if let Some(prosody_preset) = query.prosody_preset() {
+            prosody_preset.apply(&mut accent_phrases);
+        }
+
+        let (_, phoneme_id_list, _) = SynthesisEngine::initial_process(&accent_phrases);
 
-        let (flatten_moras, phoneme_id_list, _) = SynthesisEngine::initial_process(&accent_phrases);
+        let mut segment_lengths = vec![1];
+        segment_lengths.extend(accent_phrases.iter().map(|accent_phrase| {
+            let mora_phonemes = accent_phrase
+                .moras()
+                .iter()
+                .map(|mora| 1 + mora.consonant().is_some() as usize)
+                .sum::<usize>();
+            mora_phonemes + accent_phrase.pause_mora().is_some() as usize
+        }));
+        segment_lengths.push(1);
 
         let mut durations = vec![pre_phoneme_length];
         let mut pitches = vec![0.];
         let mut voiced_list = vec![false];
+        let mut vowel_indices = vec![0];
+        let mut vibratos = vec![None];
+        let mut intonation_scales = vec![intonation_scale];
+        let mut volumes = vec![volume_scale];
 
         {
             let mut sum_of_f0_bigger_than_zero = 0.;
             let mut count_of_f0_bigger_than_zero = 0;
 
-            for mora in flatten_moras {
+            // アクセント句ごとの値を指定したときは、そのアクセント句のモーラにAudioQueryの値の代わりに用いる
+            let moras = accent_phrases.iter().flat_map(|accent_phrase| {
+                let prosody = accent_phrase.prosody();
+                accent_phrase
+                    .moras()
+                    .iter()
+                    .chain(accent_phrase.pause_mora())
+                    .map(move |mora| (mora, prosody))
+            });
+            for (mora, prosody) in moras {
+                let speed_scale = prosody.speed_scale.unwrap_or(speed_scale);
+                let pitch_scale = prosody.pitch_scale.unwrap_or(pitch_scale);
+                let intonation_scale = prosody.intonation_scale.unwrap_or(intonation_scale);
+                let volume_scale = prosody.volume_scale.unwrap_or(volume_scale);
+
                 let consonant_length = *mora.consonant_length();
                 let vowel_length = *mora.vowel_length();
                 let pitch = *mora.pitch() * 2.0_f32.powf(pitch_scale);
                 pitches.push(pitch);
                 let bigger_than_zero = pitch > 0.;
                 voiced_list.push(bigger_than_zero);
+                intonation_scales.push(intonation_scale);
+                volumes.push(volume_scale);
 
                 if bigger_than_zero {
                     sum_of_f0_bigger_than_zero += pitch;
@@ -294,36 +549,58 @@ impl SynthesisEngine {
                     durations.push(consonant_length / speed_scale);
                     pitches.push(pitch);
                     voiced_list.push(bigger_than_zero);
+                    intonation_scales.push(intonation_scale);
+                    volumes.push(volume_scale);
                 }
                 durations.push(vowel_length / speed_scale);
+                vowel_indices.push(durations.len() - 1);
+                vibratos.push((*mora.vibrato()).or(*query.vibrato()));
             }
             durations.push(post_phoneme_length);
+            vowel_indices.push(durations.len() - 1);
+            vibratos.push(None);
             pitches.push(0.);
             voiced_list.push(false);
+            intonation_scales.push(intonation_scale);
+            volumes.push(volume_scale);
             let mean_f0 = sum_of_f0_bigger_than_zero / (count_of_f0_bigger_than_zero as f32);
 
             if !mean_f0.is_nan() {
                 for i in 0..pitches.len() {
                     if voiced_list[i] {
-                        pitches[i] = (pitches[i] - mean_f0) * intonation_scale + mean_f0;
+                        pitches[i] = (pitches[i] - mean_f0) * intonation_scales[i] + mean_f0;
                     }
                 }
             }
         }
 
-        self.inference_core_mut()
-            .decode(&phoneme_id_list, &pitches, &durations, speaker_id)
+        let offset = pitch_offset(query, phoneme_frame_pitches(&pitches, &durations));
+        shift_voiced_pitches(&mut pitches, offset);
+
+        Ok(DecodeInput {
+            phoneme_ids: phoneme_id_list,
+            pitches,
+            durations,
+            segment_lengths,
+            vowel_indices,
+            vibratos,
+            volumes,
+        })
     }
 
     pub fn synthesis_wave_format(
         &mut self,
         query: &AudioQueryModel,
         speaker_id: u32,
-        enable_interrogative_upspeak: bool,
+        interrogative_upspeak: Option<&InterrogativeUpspeak>,
+        pitch_contour: Option<&PitchContour>,
     ) -> Result<Vec<u8>> {
-        let wave = self.synthesis(query, speaker_id, enable_interrogative_upspeak)?;
+        let wave = self.synthesis(query, speaker_id, interrogative_upspeak, pitch_contour)?;
+        Ok(Self::to_wav_bytes(&wave, query))
+    }
 
-        let volume_scale = *query.volume_scale();
+    /// 音量を適用した波形を、AudioQueryの出力形式のwavデータにする
+    pub(crate) fn to_wav_bytes(wave: &[f32], query: &AudioQueryModel) -> Vec<u8> {
         let output_stereo = *query.output_stereo();
         // TODO: 44.1kHzなどの対応
         let output_sampling_rate = *query.output_sampling_rate();
@@ -357,26 +634,56 @@ impl SynthesisEngine {
         cur.write_all(&bytes_size.to_le_bytes()).unwrap();
 
         for value in wave {
-            let v = (value * volume_scale).clamp(-1., 1.);
+            let v = value.clamp(-1., 1.);
             let data = (v * 0x7fff as f32) as i16;
             for _ in 0..repeat_count {
                 cur.write_all(&data.to_le_bytes()).unwrap();
             }
         }
 
-        Ok(cur.into_inner())
+        cur.into_inner()
+    }
+
+    /// 音量を適用した、[`Self::DEFAULT_SAMPLING_RATE`]のモノラル波形を合成する
+    pub fn synthesis_wave(
+        &mut self,
+        query: &AudioQueryModel,
+        speaker_id: u32,
+        interrogative_upspeak: Option<&InterrogativeUpspeak>,
+        pitch_contour: Option<&PitchContour>,
+    ) -> Result<Wave> {
+        let wave = self.synthesis(query, speaker_id, interrogative_upspeak, pitch_contour)?;
+        Ok(Wave::new(wave, Self::DEFAULT_SAMPLING_RATE, 1))
     }
 
     pub fn load_openjtalk_dict(&mut self, mecab_dict_dir: impl AsRef<Path>) -> Result<()> {
         self.open_jtalk
             .load(mecab_dict_dir)
-            .map_err(|_| Error::NotLoadedOpenjtalkDict)
+            .map_err(|_| Error::NotLoadedOpenjtalkDict)?;
+        self.lazy_openjtalk_dict_dir = None;
+        Ok(())
     }
 
     pub fn is_openjtalk_dict_loaded(&self) -> bool {
         self.open_jtalk.dict_loaded()
     }
 
+    /// 辞書をすぐには読み込まず、テキストの解析で初めて必要になったときに読み込むようにする
+    pub fn load_openjtalk_dict_lazily(&mut self, mecab_dict_dir: impl Into<PathBuf>) {
+        self.lazy_openjtalk_dict_dir = Some(mecab_dict_dir.into());
+    }
+
+    fn ensure_openjtalk_dict_loaded(&mut self) -> Result<()> {
+        if self.is_openjtalk_dict_loaded() {
+            return Ok(());
+        }
+        // 読み込みに失敗したときに後で再び読み込めるよう、成功するまで辞書のパスは残しておく
+        match self.lazy_openjtalk_dict_dir.clone() {
+            Some(mecab_dict_dir) => self.load_openjtalk_dict(mecab_dict_dir),
+            None => Err(Error::NotLoadedOpenjtalkDict),
+        }
+    }
+
     fn initial_process(
         accent_phrases: &[AccentPhraseModel],
     ) -> (Vec<MoraModel>, Vec<i64>, Vec<i64>) {
@@ -520,7 +827,81 @@ pub fn split_mora(phoneme_list: &[OjtPhoneme]) -> (Vec<OjtPhoneme>, Vec<OjtPhone
     (consonant_phoneme_list, vowel_phoneme_list, vowel_indexes)
 }
 
-fn mora_to_text(mora: impl AsRef<str>) -> String {
+/// OpenJTalkの解析結果からアクセント句を作る。音素の長さと音高は全て0とする
+///
+/// `pause_punctuations`と`interrogative_breath_groups`は、息継ぎの区切りごとの句読点と疑問文かどうかを表す
+pub(super) fn utterance_to_accent_phrases(
+    utterance: &Utterance,
+    pause_punctuations: Option<&[String]>,
+    interrogative_breath_groups: Option<&[bool]>,
+) -> Vec<AccentPhraseModel> {
+    utterance.breath_groups().iter().enumerate().fold(
+        Vec::new(),
+        |mut accum_vec, (i, breath_group)| {
+            accum_vec.extend(breath_group.accent_phrases().iter().enumerate().map(
+                |(j, accent_phrase)| {
+                    let moras = accent_phrase
+                        .moras()
+                        .iter()
+                        .map(|mora| {
+                            let mora_text = mora
+                                .phonemes()
+                                .iter()
+                                .map(|phoneme| phoneme.phoneme().to_string())
+                                .collect::<Vec<_>>()
+                                .join("");
+
+                            let (consonant, consonant_length) =
+                                if let Some(consonant) = mora.consonant() {
+                                    (Some(consonant.phoneme().to_string()), Some(0.))
+                                } else {
+                                    (None, None)
+                                };
+
+                            MoraModel::new(
+                                mora_to_text(mora_text),
+                                consonant,
+                                consonant_length,
+                                mora.vowel().phoneme().into(),
+                                0.,
+                                0.,
+                            )
+                        })
+                        .collect();
+
+                    let has_pause = i != utterance.breath_groups().len() - 1
+                        && j == breath_group.accent_phrases().len() - 1;
+                    let pause_mora = has_pause
+                        .then(|| MoraModel::new("、".into(), None, None, "pau".into(), 0., 0.));
+
+                    let is_interrogative = *accent_phrase.is_interrogative()
+                        || j == breath_group.accent_phrases().len() - 1
+                            && interrogative_breath_groups
+                                .as_ref()
+                                .map(|interrogatives| interrogatives[i])
+                                == Some(true);
+
+                    let mut new_accent_phrase = AccentPhraseModel::new(
+                        moras,
+                        *accent_phrase.accent(),
+                        pause_mora,
+                        is_interrogative,
+                    );
+                    if has_pause {
+                        new_accent_phrase.set_pause_punctuation(
+                            pause_punctuations.map(|punctuations| punctuations[i].clone()),
+                        );
+                    }
+                    new_accent_phrase
+                },
+            ));
+
+            accum_vec
+        },
+    )
+}
+
+pub(super) fn mora_to_text(mora: impl AsRef<str>) -> String {
     let last_char = mora.as_ref().chars().last().unwrap();
     let mora = if ['A', 'I', 'U', 'E', 'O'].contains(&last_char) {
         format!(
@@ -535,23 +916,97 @@ fn mora_to_text(mora: impl AsRef<str>) -> String {
     mora_list::mora2text(&mora).to_string()
 }
 
+/// テキスト中のポーズとなる句読点を、連続するものは一つにまとめて先頭の文字で返す。
+/// テキストの先頭にある句読点はポーズにならないので含めない。
+fn extract_pause_punctuations(text: &str) -> Vec<String> {
+    let mut punctuations = Vec::new();
+    let mut current: Option<char> = None;
+    let mut after_text = false;
+    for c in text.chars() {
+        if PAUSE_PUNCTUATIONS.contains(&c) {
+            if after_text && current.is_none() {
+                current = Some(c);
+            }
+        } else if !c.is_whitespace() {
+            if let Some(punctuation) = current.take() {
+                punctuations.push(punctuation.to_string());
+            }
+            after_text = true;
+        }
+    }
+    punctuations
+}
+
+/// 息継ぎの区切りごとに、疑問符か感嘆符を含む句読点で終わっているかを返す
+fn extract_interrogative_breath_groups(text: &str) -> Vec<bool> {
+    let mut interrogatives = Vec::new();
+    let mut current: Option<bool> = None;
+    let mut after_text = false;
+    for c in text.chars() {
+        if PAUSE_PUNCTUATIONS.contains(&c) {
+            if after_text {
+                let is_interrogative = INTERROGATION_MARKS.contains(&c);
+                current = Some(current.unwrap_or(false) || is_interrogative);
+            }
+        } else if !c.is_whitespace() {
+            if let Some(is_interrogative) = current.take() {
+                interrogatives.push(is_interrogative);
+            }
+            after_text = true;
+        }
+    }
+    if after_text {
+        interrogatives.push(current.unwrap_or(false));
+    }
+    interrogatives
+}
+
+/// アクセント句のポーズについて、音声合成に用いる長さを求める。ポーズがないときは`None`
+///
+/// kanaで明示したポーズの長さはポーズのモーラの母音の長さにしているため、
+/// 明示したポーズはAudioQueryで指定したポーズの長さより優先し、編集された母音の長さをそのまま用いる。
+/// 句読点ごとのポーズの長さは、ポーズの元になった句読点で引く
+pub(super) fn adjust_pause_length(
+    query: &AudioQueryModel,
+    accent_phrase: &AccentPhraseModel,
+) -> Option<f32> {
+    let pause_mora = accent_phrase.pause_mora().as_ref()?;
+    let pause_length = if *accent_phrase.is_pause_length_explicit() {
+        *pause_mora.vowel_length()
+    } else {
+        let punctuation = accent_phrase
+            .pause_punctuation()
+            .as_ref()
+            .unwrap_or_else(|| pause_mora.text());
+        query
+            .pause_length()
+            .or_else(|| query.punctuation_pause_lengths().get(punctuation).copied())
+            .unwrap_or(*pause_mora.vowel_length())
+    };
+    Some(pause_length * query.pause_length_scale())
+}
+
 fn adjust_interrogative_accent_phrases(
     accent_phrases: &[AccentPhraseModel],
+    interrogative_upspeak: &InterrogativeUpspeak,
 ) -> Vec<AccentPhraseModel> {
     accent_phrases
         .iter()
         .map(|accent_phrase| {
-            AccentPhraseModel::new(
-                adjust_interrogative_moras(accent_phrase),
-                *accent_phrase.accent(),
-                accent_phrase.pause_mora().clone(),
-                *accent_phrase.is_interrogative(),
-            )
+            let mut new_accent_phrase = accent_phrase.clone();
+            new_accent_phrase.set_moras(adjust_interrogative_moras(
+                accent_phrase,
+                interrogative_upspeak,
+            ));
+            new_accent_phrase
         })
         .collect()
 }
 
-fn adjust_interrogative_moras(accent_phrase: &AccentPhraseModel) -> Vec<MoraModel> {
+fn adjust_interrogative_moras(
+    accent_phrase: &AccentPhraseModel,
+    interrogative_upspeak: &InterrogativeUpspeak,
+) -> Vec<MoraModel> {
     let moras = accent_phrase.moras();
     if *accent_phrase.is_interrogative() && !moras.is_empty() {
         let last_mora = moras.last().unwrap();
@@ -559,31 +1014,57 @@ fn adjust_interrogative_moras(accent_phrase: &AccentPhraseModel) -> Vec<MoraMode
         if last_mora_pitch != 0.0 {
             let mut new_moras: Vec<MoraModel> = Vec::with_capacity(moras.len() + 1);
             new_moras.extend_from_slice(moras.as_slice());
-            let interrogative_mora = make_interrogative_mora(last_mora);
-            new_moras.push(interrogative_mora);
+            match interrogative_upspeak.contour {
+                UpspeakContour::AppendMora => {
+                    let interrogative_mora =
+                        make_interrogative_mora(last_mora, interrogative_upspeak);
+                    new_moras.push(interrogative_mora);
+                }
+                UpspeakContour::RaiseLastMora => {
+                    let raised_mora = raise_last_mora(last_mora, interrogative_upspeak);
+                    *new_moras.last_mut().unwrap() = raised_mora;
+                }
+            }
             return new_moras;
         }
     }
     moras.clone()
 }
 
-fn make_interrogative_mora(last_mora: &MoraModel) -> MoraModel {
-    const FIX_VOWEL_LENGTH: f32 = 0.15;
-    const ADJUST_PITCH: f32 = 0.3;
-    const MAX_PITCH: f32 = 6.5;
-
-    let pitch = (*last_mora.pitch() + ADJUST_PITCH).min(MAX_PITCH);
+fn make_interrogative_mora(
+    last_mora: &MoraModel,
+    interrogative_upspeak: &InterrogativeUpspeak,
+) -> MoraModel {
+    let pitch = (*last_mora.pitch() + interrogative_upspeak.pitch_delta)
+        .min(interrogative_upspeak.max_pitch);
 
     MoraModel::new(
         mora_to_text(last_mora.vowel()),
         None,
         None,
         last_mora.vowel().clone(),
-        FIX_VOWEL_LENGTH,
+        interrogative_upspeak.vowel_length,
         pitch,
     )
 }
 
+fn raise_last_mora(
+    last_mora: &MoraModel,
+    interrogative_upspeak: &InterrogativeUpspeak,
+) -> MoraModel {
+    let pitch = (*last_mora.pitch() + interrogative_upspeak.pitch_delta)
+        .min(interrogative_upspeak.max_pitch);
+
+    let mut raised_mora = last_mora.clone();
+    raised_mora.set_vowel_length(
+        last_mora
+            .vowel_length()
+            .max(interrogative_upspeak.vowel_length),
+    );
+    raised_mora.set_pitch(pitch);
+    raised_mora
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -619,6 +1100,411 @@ mod tests {
         assert_eq!(synthesis_engine.is_openjtalk_dict_loaded(), false);
     }
 
+    #[rstest]
+    #[async_std::test]
+    async fn load_openjtalk_dict_lazily_works() {
+        let core = InferenceCore::new(false, None);
+        let mut synthesis_engine = SynthesisEngine::new(core, OpenJtalk::initialize());
+        let result = synthesis_engine.create_accent_phrases("", 0);
+        assert_eq!(result.unwrap_err(), Error::NotLoadedOpenjtalkDict);
+
+        let open_jtalk_dic_dir = download_open_jtalk_dict_if_no_exists().await;
+        synthesis_engine.load_openjtalk_dict_lazily(open_jtalk_dic_dir);
+        assert_eq!(synthesis_engine.is_openjtalk_dict_loaded(), false);
+
+        let result = synthesis_engine.create_accent_phrases("", 0);
+        assert!(result.unwrap().is_empty());
+        assert_eq!(synthesis_engine.is_openjtalk_dict_loaded(), true);
+    }
+
+    #[rstest]
+    fn load_openjtalk_dict_lazily_keeps_dir_on_failure() {
+        let core = InferenceCore::new(false, None);
+        let mut synthesis_engine = SynthesisEngine::new(core, OpenJtalk::initialize());
+        synthesis_engine.load_openjtalk_dict_lazily("not_exist_dir");
+        for _ in 0..2 {
+            let result = synthesis_engine.ensure_openjtalk_dict_loaded();
+            assert_eq!(result.unwrap_err(), Error::NotLoadedOpenjtalkDict);
+            assert_eq!(
+                synthesis_engine.lazy_openjtalk_dict_dir,
+                Some(PathBuf::from("not_exist_dir"))
+            );
+        }
+    }
+
+    #[rstest]
+    #[async_std::test]
+    async fn predict_missing_accents_works() {
+        let core = InferenceCore::new(false, None);
+        let mut synthesis_engine = SynthesisEngine::new(core, OpenJtalk::initialize());
+
+        // 辞書が読み込まれていないときは平板型のままとする
+        let (mut accent_phrases, missing_accent_indices) =
+            parse_kana_lenient("ミナサン/コ'ンニチワ").unwrap();
+        synthesis_engine.predict_missing_accents(&mut accent_phrases, &missing_accent_indices);
+        assert_eq!(*accent_phrases[0].accent(), 4);
+        assert_eq!(*accent_phrases[1].accent(), 1);
+
+        let open_jtalk_dic_dir = download_open_jtalk_dict_if_no_exists().await;
+        synthesis_engine
+            .load_openjtalk_dict(&open_jtalk_dic_dir)
+            .unwrap();
+        synthesis_engine.predict_missing_accents(&mut accent_phrases, &missing_accent_indices);
+        assert!((1..=4).contains(accent_phrases[0].accent()));
+        // アクセントが指定されていたアクセント句は変更しない
+        assert_eq!(*accent_phrases[1].accent(), 1);
+    }
+
+    #[rstest]
+    #[case("同じ、文章、です。完全に、同一です。", &["、", "、", "。", "、"])]
+    #[case("「えっ！？」……本当？", &["！", "…"])]
+    #[case("、先頭の句読点", &[])]
+    fn extract_pause_punctuations_works(#[case] text: &str, #[case] expected: &[&str]) {
+        assert_eq!(extract_pause_punctuations(text), expected);
+    }
+
+    #[rstest]
+    #[case(None, false, &[], 1., 0.3)]
+    #[case(Some(0.5), false, &[("。", 0.8)], 1., 0.5)]
+    #[case(None, false, &[("。", 0.8)], 2., 1.6)]
+    #[case(None, false, &[("、", 0.8)], 0.5, 0.15)]
+    // 明示したポーズは、推論時に設定されて編集されたかもしれない母音の長さを用いる
+    #[case(Some(0.5), true, &[], 1., 0.3)]
+    #[case(None, true, &[("。", 0.8)], 2., 0.6)]
+    fn adjust_pause_length_works(
+        #[case] pause_length: Option<f32>,
+        #[case] is_pause_length_explicit: bool,
+        #[case] punctuation_pause_lengths: &[(&str, f32)],
+        #[case] pause_length_scale: f32,
+        #[case] expected: f32,
+    ) {
+        let query: AudioQueryModel = serde_json::from_value(serde_json::json!({
+            "accent_phrases": [],
+            "speed_scale": 1.,
+            "pitch_scale": 0.,
+            "intonation_scale": 1.,
+            "volume_scale": 1.,
+            "pre_phoneme_length": 0.1,
+            "post_phoneme_length": 0.1,
+            "output_sampling_rate": 48000,
+            "output_stereo": false,
+            "kana": "",
+            "pause_length": pause_length,
+            "pause_length_scale": pause_length_scale,
+            "punctuation_pause_lengths": punctuation_pause_lengths
+                .iter()
+                .copied()
+                .collect::<std::collections::BTreeMap<_, _>>(),
+        }))
+        .unwrap();
+        let mut accent_phrase = AccentPhraseModel::new(
+            vec![MoraModel::new("ア".into(), None, None, "a".into(), 0.1, 5.)],
+            1,
+            Some(MoraModel::new(
+                "、".into(),
+                None,
+                None,
+                "pau".into(),
+                0.3,
+                0.,
+            )),
+            false,
+        );
+        accent_phrase.set_pause_punctuation(Some("。".into()));
+        accent_phrase.set_is_pause_length_explicit(is_pause_length_explicit);
+        let pause_length = adjust_pause_length(&query, &accent_phrase).unwrap();
+        assert!((pause_length - expected).abs() < 1e-6);
+    }
+
+    #[rstest]
+    fn create_decode_input_applies_accent_phrase_prosody() {
+        let accent_phrase = |prosody| {
+            let mut accent_phrase = AccentPhraseModel::new(
+                vec![
+                    MoraModel::new("ア".into(), None, None, "a".into(), 0.2, 5.),
+                    MoraModel::new("イ".into(), None, None, "i".into(), 0.2, 6.),
+                ],
+                1,
+                None,
+                false,
+            );
+            accent_phrase.set_prosody(prosody);
+            accent_phrase
+        };
+        let query = AudioQueryModel::new(
+            vec![
+                accent_phrase(AccentPhraseProsody::default()),
+                accent_phrase(AccentPhraseProsody {
+                    speed_scale: Some(2.),
+                    pitch_scale: Some(1.),
+                    intonation_scale: Some(0.),
+                    volume_scale: Some(0.5),
+                }),
+            ],
+            1.,
+            0.,
+            1.,
+            1.,
+            0.1,
+            0.1,
+            SynthesisEngine::DEFAULT_SAMPLING_RATE,
+            false,
+            "".into(),
+        );
+
+        let decode_input = SynthesisEngine::create_decode_input(&query, None).unwrap();
+        assert_eq!(decode_input.durations, [0.1, 0.2, 0.2, 0.1, 0.1, 0.1]);
+        // 音高の平均は(5 + 6 + 10 + 12) / 4 = 8.25で、後のアクセント句は抑揚をなくす
+        assert_eq!(decode_input.pitches, [0., 5., 6., 8.25, 8.25, 0.]);
+        assert_eq!(decode_input.volumes, [1., 1., 1., 0.5, 0.5, 1.]);
+    }
+
+    #[rstest]
+    #[case(ProsodyPreset::Monotone)]
+    #[case(ProsodyPreset::Robot)]
+    fn create_decode_input_keeps_preset_pitches_flat_with_variation(
+        #[case] prosody_preset: ProsodyPreset,
+    ) {
+        let mut query = AudioQueryModel::new(
+            vec![AccentPhraseModel::new(
+                vec![
+                    MoraModel::new("ア".into(), None, None, "a".into(), 0.2, 5.),
+                    MoraModel::new("イ".into(), None, None, "i".into(), 0.2, 6.),
+                    MoraModel::new("ウ".into(), None, None, "u".into(), 0.2, 5.5),
+                ],
+                1,
+                None,
+                false,
+            )],
+            1.,
+            0.,
+            1.,
+            1.,
+            0.1,
+            0.1,
+            SynthesisEngine::DEFAULT_SAMPLING_RATE,
+            false,
+            "".into(),
+        );
+        query.set_prosody_preset(Some(prosody_preset));
+        query.set_prosody_variation(Some(ProsodyVariation {
+            seed: 1,
+            pitch: 2.,
+            ..Default::default()
+        }));
+
+        let decode_input = SynthesisEngine::create_decode_input(&query, None).unwrap();
+        let pitches = &decode_input.pitches[1..4];
+        assert!(
+            pitches.iter().all(|&pitch| pitch == pitches[0]),
+            "{pitches:?}"
+        );
+        // 長さの揺らぎは残す
+        assert_ne!(decode_input.durations[1..4], [0.2, 0.2, 0.2]);
+    }
+
+    #[rstest]
+    fn create_decode_input_shifts_mean_f0_to_target() {
+        let mut query = AudioQueryModel::new(
+            vec![AccentPhraseModel::new(
+                vec![
+                    MoraModel::new("ア".into(), None, None, "a".into(), 0.2, 5.),
+                    MoraModel::new("イ".into(), None, None, "i".into(), 0.2, 6.),
+                    MoraModel::new("ウ".into(), None, None, "U".into(), 0.2, 0.),
+                ],
+                1,
+                None,
+                false,
+            )],
+            1.,
+            0.,
+            1.,
+            1.,
+            0.1,
+            0.1,
+            SynthesisEngine::DEFAULT_SAMPLING_RATE,
+            false,
+            "".into(),
+        );
+        query.set_target_mean_f0(Some(6_f32.exp()));
+
+        // 有声の音高の平均5.5を6にずらし、無声のモーラは変えない
+        let decode_input = SynthesisEngine::create_decode_input(&query, None).unwrap();
+        assert_eq!(decode_input.pitches, [0., 5.5, 6.5, 0., 0.]);
+    }
+
+    #[rstest]
+    fn create_decode_input_weights_mean_f0_by_frames() {
+        let mut query = AudioQueryModel::new(
+            vec![AccentPhraseModel::new(
+                vec![
+                    MoraModel::new("ア".into(), None, None, "a".into(), 0.1, 5.),
+                    MoraModel::new("イ".into(), None, None, "i".into(), 0.4, 6.),
+                ],
+                1,
+                None,
+                false,
+            )],
+            1.,
+            0.,
+            1.,
+            1.,
+            0.1,
+            0.1,
+            SynthesisEngine::DEFAULT_SAMPLING_RATE,
+            false,
+            "".into(),
+        );
+        query.set_target_mean_f0(Some(6_f32.exp()));
+
+        // `synthesis_with_frame_pitch`と同じく、有声のフレームの音高の平均を合わせる
+        let decode_input = SynthesisEngine::create_decode_input(&query, None).unwrap();
+        let voiced = phoneme_frame_pitches(&decode_input.pitches, &decode_input.durations)
+            .into_iter()
+            .filter(|&pitch| pitch > 0.)
+            .collect::<Vec<_>>();
+        let mean = voiced.iter().sum::<f32>() / voiced.len() as f32;
+        assert!((mean - 6.).abs() < 1e-5, "{mean}");
+    }
+
+    #[rstest]
+    fn apply_volumes_works() {
+        let frame = 1. / PITCH_FRAME_RATE;
+        let decode_input = DecodeInput {
+            phoneme_ids: vec![0; 2],
+            pitches: vec![0.; 2],
+            durations: vec![10. * frame, 10. * frame],
+            segment_lengths: vec![1, 1],
+            vowel_indices: vec![0, 1],
+            vibratos: vec![None; 2],
+            volumes: vec![1., 0.],
+        };
+        let num_samples = (20. * frame * SynthesisEngine::DEFAULT_SAMPLING_RATE as f32) as usize;
+        let mut wave = vec![1.; num_samples];
+        decode_input.apply_volumes(&mut wave);
+
+        let half = (VOLUME_TRANSITION_LENGTH / 2. * SynthesisEngine::DEFAULT_SAMPLING_RATE as f32)
+            as usize;
+        let boundary = num_samples / 2;
+        assert!(wave[..boundary - half].iter().all(|&sample| sample == 1.));
+        assert!(wave[boundary + half..].iter().all(|&sample| sample == 0.));
+        // 境界の前後では音量が滑らかに下がる
+        assert!((wave[boundary] - 0.5).abs() < 1e-3);
+        assert!(wave[boundary - half..boundary + half]
+            .windows(2)
+            .all(|pair| pair[0] > pair[1]));
+
+        let decode_input = DecodeInput {
+            volumes: vec![0.5, 0.5],
+            ..decode_input
+        };
+        let mut wave = vec![1.; num_samples];
+        decode_input.apply_volumes(&mut wave);
+        assert!(wave.iter().all(|&sample| sample == 0.5));
+    }
+
+    #[rstest]
+    #[case("同じ、文章、です。", &[false, false, false])]
+    #[case("本当? うん。", &[true, false])]
+    #[case("「えっ！？」……本当？", &[true, false, true])]
+    #[case("本当！", &[true])]
+    #[case("本当!", &[true])]
+    #[case("えっ！うん。", &[true, false])]
+    #[case("", &[])]
+    fn extract_interrogative_breath_groups_works(#[case] text: &str, #[case] expected: &[bool]) {
+        assert_eq!(extract_interrogative_breath_groups(text), expected);
+    }
+
+    #[rstest]
+    #[case(UpspeakContour::AppendMora, &[("ホ", 0.1, 5.), ("ン", 0.1, 5.5), ("ト", 0.1, 5.8), ("オ", 0.2, 6.)])]
+    #[case(UpspeakContour::RaiseLastMora, &[("ホ", 0.1, 5.), ("ン", 0.1, 5.5), ("ト", 0.2, 6.)])]
+    fn adjust_interrogative_moras_works(
+        #[case] contour: UpspeakContour,
+        #[case] expected: &[(&str, f32, f32)],
+    ) {
+        let accent_phrase = AccentPhraseModel::new(
+            vec![
+                MoraModel::new(
+                    "ホ".into(),
+                    Some("h".into()),
+                    Some(0.1),
+                    "o".into(),
+                    0.1,
+                    5.,
+                ),
+                MoraModel::new("ン".into(), None, None, "N".into(), 0.1, 5.5),
+                MoraModel::new(
+                    "ト".into(),
+                    Some("t".into()),
+                    Some(0.1),
+                    "o".into(),
+                    0.1,
+                    5.8,
+                ),
+            ],
+            1,
+            None,
+            true,
+        );
+        let interrogative_upspeak = InterrogativeUpspeak {
+            contour,
+            vowel_length: 0.2,
+            pitch_delta: 0.5,
+            max_pitch: 6.,
+        };
+
+        let moras = adjust_interrogative_moras(&accent_phrase, &interrogative_upspeak);
+        assert_eq!(
+            moras
+                .iter()
+                .map(|mora| (mora.text().as_str(), *mora.vowel_length(), *mora.pitch()))
+                .collect::<Vec<_>>(),
+            expected,
+        );
+    }
+
+    #[rstest]
+    #[async_std::test]
+    async fn analyze_text_works() {
+        // 音声合成モデルを読み込まずに解析できる
+        let core = InferenceCore::new(false, None);
+        let mut synthesis_engine = SynthesisEngine::new(core, OpenJtalk::initialize());
+        let open_jtalk_dic_dir = download_open_jtalk_dict_if_no_exists().await;
+
+        let _ = synthesis_engine.load_openjtalk_dict(&open_jtalk_dic_dir);
+        let accent_phrases = synthesis_engine
+            .analyze_text("同じ、文章、です。完全に、同一です。")
+            .unwrap();
+        assert_eq!(accent_phrases.len(), 5);
+
+        for mora in to_flatten_moras(&accent_phrases) {
+            assert_eq!(mora.consonant_length().unwrap_or(0.), 0.);
+            assert_eq!(*mora.vowel_length(), 0.);
+            assert_eq!(*mora.pitch(), 0.);
+        }
+    }
+
+    #[rstest]
+    #[async_std::test]
+    async fn extract_njd_nodes_works() {
+        let core = InferenceCore::new(false, None);
+        let mut synthesis_engine = SynthesisEngine::new(core, OpenJtalk::initialize());
+        let open_jtalk_dic_dir = download_open_jtalk_dict_if_no_exists().await;
+
+        let _ = synthesis_engine.load_openjtalk_dict(&open_jtalk_dic_dir);
+        let nodes = synthesis_engine
+            .extract_njd_nodes("同じ文章です。")
+            .unwrap();
+        let surfaces = nodes
+            .iter()
+            .map(|node| node.surface().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(surfaces, ["同じ", "文章", "です", "。"]);
+        assert_eq!(nodes[1].pronunciation(), "ブンショー");
+        assert_eq!(*nodes[1].mora_count(), 4);
+        assert!(*nodes[2].chain_flag());
+    }
+
     #[rstest]
     #[async_std::test]
     async fn create_accent_phrases_works() {
@@ -676,4 +1562,33 @@ mod tests {
             );
         }
     }
+
+    #[rstest]
+    #[async_std::test]
+    async fn create_accent_phrases_keeps_pause_punctuations() {
+        let mut core = InferenceCore::new(true, None);
+        core.initialize(
+            Path::new(concat!(env!("CARGO_WORKSPACE_DIR"), "/model/")),
+            false,
+            0,
+            true,
+        )
+        .unwrap();
+        let mut synthesis_engine = SynthesisEngine::new(core, OpenJtalk::initialize());
+        let open_jtalk_dic_dir = download_open_jtalk_dict_if_no_exists().await;
+
+        let _ = synthesis_engine.load_openjtalk_dict(&open_jtalk_dic_dir);
+        let accent_phrases = synthesis_engine
+            .create_accent_phrases("同じ、文章、です。完全に、同一です。", 0)
+            .unwrap();
+
+        let pause_punctuations = accent_phrases
+            .iter()
+            .map(|accent_phrase| accent_phrase.pause_punctuation().as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pause_punctuations,
+            [Some("、"), Some("、"), Some("。"), Some("、"), None]
+        );
+    }
 }
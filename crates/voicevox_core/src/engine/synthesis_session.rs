@@ -0,0 +1,392 @@
+use std::ops::Range;
+
+use super::synthesis_engine::DecodeInput;
+use super::*;
+
+/// 同じ話者でAudioQueryを繰り返し音声合成する際に、変更のない区間の合成結果を再利用するセッション
+///
+/// 前回の音声合成から変更のあったアクセント句だけを、前後`context_margin`個のアクセント句とともに推論し直し、
+/// `crossfade_length`秒のクロスフェードで前回の合成結果と繋ぐ。
+/// `pitch_contour`かビブラートを指定したときは、音声全体のフレームごとの音高を求めてから区間ごとに分けて推論する
+pub struct SynthesisSession {
+    speaker_id: u32,
+    interrogative_upspeak: Option<InterrogativeUpspeak>,
+    pitch_contour: Option<PitchContour>,
+    context_margin: usize,
+    crossfade_length: f32,
+    /// 先頭の無音、各アクセント句、末尾の無音の順に、前回の音声合成の入力と合成結果
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Segment {
+    phoneme_ids: Vec<i64>,
+    pitches: Vec<f32>,
+    durations: Vec<f32>,
+    /// フレームごとの音高で推論するときの、区間のフレームごとの音高
+    frame_pitches: Option<Vec<f32>>,
+    samples: Vec<f32>,
+}
+
+impl Segment {
+    fn has_same_input(&self, other: &Self) -> bool {
+        (
+            &self.phoneme_ids,
+            &self.pitches,
+            &self.durations,
+            &self.frame_pitches,
+        ) == (
+            &other.phoneme_ids,
+            &other.pitches,
+            &other.durations,
+            &other.frame_pitches,
+        )
+    }
+}
+
+impl SynthesisSession {
+    pub fn new(
+        speaker_id: u32,
+        interrogative_upspeak: Option<InterrogativeUpspeak>,
+        pitch_contour: Option<PitchContour>,
+        context_margin: usize,
+        crossfade_length: f32,
+    ) -> Self {
+        Self {
+            speaker_id,
+            interrogative_upspeak,
+            pitch_contour,
+            context_margin,
+            crossfade_length,
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn speaker_id(&self) -> u32 {
+        self.speaker_id
+    }
+
+    /// AudioQueryから音声合成し、音量を適用した[`SynthesisEngine::DEFAULT_SAMPLING_RATE`]のモノラル波形を返す
+    pub fn synthesis(
+        &mut self,
+        synthesis_engine: &mut SynthesisEngine,
+        query: &AudioQueryModel,
+    ) -> Result<Vec<f32>> {
+        let decode_input =
+            SynthesisEngine::create_decode_input(query, self.interrogative_upspeak.as_ref())?;
+        let frame_pitches = synthesis_engine.decode_frame_pitches(
+            &decode_input,
+            self.speaker_id,
+            self.pitch_contour.as_ref(),
+        )?;
+        let mut segments = split_segments(&decode_input, frame_pitches.as_deref());
+
+        let changed = changed_range(&self.segments, &segments);
+        if let Some(changed) = changed {
+            let window = changed.start.saturating_sub(self.context_margin)
+                ..(changed.end + self.context_margin).min(segments.len());
+            let window_samples = self.decode(synthesis_engine, &segments[window.clone()])?;
+            let window_samples = split_samples(&segments[window.clone()], &window_samples);
+            for (index, samples) in window.clone().zip(window_samples) {
+                segments[index].samples = samples;
+            }
+
+            // 変更のないアクセント句は前回の合成結果を用い、変更のあった区間との境目をクロスフェードする
+            let suffix_len = segments.len() - changed.end;
+            let old_suffix_start = self.segments.len() - suffix_len;
+            let crossfade_frames = (self.crossfade_length.max(0.)
+                * SynthesisEngine::DEFAULT_SAMPLING_RATE as f32)
+                .round() as usize;
+            let prefix = std::iter::zip(&mut segments[..changed.start], &mut self.segments);
+            for (index, (segment, old_segment)) in prefix.enumerate() {
+                let new_samples = std::mem::replace(
+                    &mut segment.samples,
+                    std::mem::take(&mut old_segment.samples),
+                );
+                if index + 1 == changed.start && window.start < changed.start {
+                    crossfade_tail(&mut segment.samples, &new_samples, crossfade_frames);
+                }
+            }
+            let suffix = std::iter::zip(
+                &mut segments[changed.end..],
+                &mut self.segments[old_suffix_start..],
+            );
+            for (index, (segment, old_segment)) in suffix.enumerate() {
+                let new_samples = std::mem::replace(
+                    &mut segment.samples,
+                    std::mem::take(&mut old_segment.samples),
+                );
+                if index == 0 && changed.end < window.end {
+                    crossfade_head(&mut segment.samples, &new_samples, crossfade_frames);
+                }
+            }
+        } else {
+            for (segment, old_segment) in std::iter::zip(&mut segments, &mut self.segments) {
+                segment.samples = std::mem::take(&mut old_segment.samples);
+            }
+        }
+
+        self.segments = segments;
+        let mut wave = self
+            .segments
+            .iter()
+            .flat_map(|segment| segment.samples.iter().copied())
+            .collect::<Vec<_>>();
+        decode_input.apply_volumes(&mut wave);
+        Ok(wave)
+    }
+
+    fn decode(
+        &self,
+        synthesis_engine: &mut SynthesisEngine,
+        segments: &[Segment],
+    ) -> Result<Vec<f32>> {
+        let phoneme_ids = segments
+            .iter()
+            .flat_map(|segment| segment.phoneme_ids.iter().copied())
+            .collect::<Vec<_>>();
+        let pitches = segments
+            .iter()
+            .flat_map(|segment| segment.pitches.iter().copied())
+            .collect::<Vec<_>>();
+        let durations = segments
+            .iter()
+            .flat_map(|segment| segment.durations.iter().copied())
+            .collect::<Vec<_>>();
+        if segments
+            .iter()
+            .all(|segment| segment.frame_pitches.is_some())
+        {
+            let frame_pitches = segments
+                .iter()
+                .flat_map(|segment| segment.frame_pitches.iter().flatten().copied())
+                .collect::<Vec<_>>();
+            return synthesis_engine
+                .inference_core_mut()
+                .decode_with_frame_pitch(
+                    &phoneme_ids,
+                    &frame_pitches,
+                    &durations,
+                    self.speaker_id,
+                );
+        }
+        synthesis_engine.inference_core_mut().decode(
+            &phoneme_ids,
+            &pitches,
+            &durations,
+            self.speaker_id,
+        )
+    }
+}
+
+/// 音素ごとの値と、あればフレームごとの音高を区間ごとに分ける
+fn split_segments(decode_input: &DecodeInput, frame_pitches: Option<&[f32]>) -> Vec<Segment> {
+    let mut start = 0;
+    let mut frame_start = 0;
+    decode_input
+        .segment_lengths
+        .iter()
+        .map(|&length| {
+            let range = start..start + length;
+            start += length;
+            let durations = decode_input.durations[range.clone()].to_vec();
+            let frames = frame_start..frame_start + frame_count(&durations);
+            frame_start = frames.end;
+            Segment {
+                phoneme_ids: decode_input.phoneme_ids[range.clone()].to_vec(),
+                pitches: decode_input.pitches[range].to_vec(),
+                durations,
+                frame_pitches: frame_pitches.map(|frame_pitches| frame_pitches[frames].to_vec()),
+                samples: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// 前回と今回の区間を先頭と末尾から比べ、今回の区間のうち推論し直す範囲を返す。変更がなければ`None`を返す
+///
+/// 区間が削除されただけのときも、その前後の区間を推論し直す
+fn changed_range(old_segments: &[Segment], new_segments: &[Segment]) -> Option<Range<usize>> {
+    let prefix_len = std::iter::zip(old_segments, new_segments)
+        .take_while(|(old, new)| old.has_same_input(new))
+        .count();
+    let suffix_len = std::iter::zip(old_segments.iter().rev(), new_segments.iter().rev())
+        .take(old_segments.len().min(new_segments.len()) - prefix_len)
+        .take_while(|(old, new)| old.has_same_input(new))
+        .count();
+    if old_segments.len() == new_segments.len() && prefix_len == new_segments.len() {
+        return None;
+    }
+
+    let changed = prefix_len..new_segments.len() - suffix_len;
+    if changed.is_empty() {
+        Some(changed.start.saturating_sub(1)..(changed.end + 1).min(new_segments.len()))
+    } else {
+        Some(changed)
+    }
+}
+
+/// 推論した波形を、各区間のフレームの境界で区間ごとに分ける
+///
+/// 推論では音素ごとにフレーム数を丸めるため、各区間のフレーム数に1フレームあたりのサンプル数を掛けた位置で分ける
+fn split_samples(segments: &[Segment], samples: &[f32]) -> Vec<Vec<f32>> {
+    let frame_counts = segments
+        .iter()
+        .map(|segment| frame_count(&segment.durations))
+        .collect::<Vec<_>>();
+    let total_frames = frame_counts.iter().sum::<usize>();
+
+    let mut elapsed_frames = 0;
+    let mut start = 0;
+    frame_counts
+        .iter()
+        .enumerate()
+        .map(|(i, frames)| {
+            elapsed_frames += frames;
+            let end = if i + 1 < frame_counts.len() && total_frames > 0 {
+                (elapsed_frames * samples.len() / total_frames).clamp(start, samples.len())
+            } else {
+                samples.len()
+            };
+            let segment_samples = samples[start..end].to_vec();
+            start = end;
+            segment_samples
+        })
+        .collect()
+}
+
+/// `samples`の末尾を、同じ区間を推論し直した`new_samples`の末尾へクロスフェードする
+fn crossfade_tail(samples: &mut [f32], new_samples: &[f32], crossfade_frames: usize) {
+    let frames = crossfade_frames.min(samples.len()).min(new_samples.len());
+    let samples_start = samples.len() - frames;
+    let new_samples_start = new_samples.len() - frames;
+    for frame in 0..frames {
+        let fade_in = (frame as f32 + 0.5) / frames as f32;
+        let sample = &mut samples[samples_start + frame];
+        *sample = *sample * (1. - fade_in) + new_samples[new_samples_start + frame] * fade_in;
+    }
+}
+
+/// `samples`の先頭を、同じ区間を推論し直した`new_samples`の先頭からクロスフェードする
+fn crossfade_head(samples: &mut [f32], new_samples: &[f32], crossfade_frames: usize) {
+    let frames = crossfade_frames.min(samples.len()).min(new_samples.len());
+    for frame in 0..frames {
+        let fade_in = (frame as f32 + 0.5) / frames as f32;
+        samples[frame] = new_samples[frame] * (1. - fade_in) + samples[frame] * fade_in;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn segment(phoneme_ids: &[i64], duration: f32) -> Segment {
+        Segment {
+            phoneme_ids: phoneme_ids.to_vec(),
+            pitches: vec![0.; phoneme_ids.len()],
+            durations: vec![duration; phoneme_ids.len()],
+            frame_pitches: None,
+            samples: Vec::new(),
+        }
+    }
+
+    fn segments(phoneme_ids: &[i64]) -> Vec<Segment> {
+        phoneme_ids.iter().map(|&id| segment(&[id], 0.1)).collect()
+    }
+
+    #[rstest]
+    #[case(&[], &[0, 1, 0], Some(0..3))]
+    #[case(&[0, 1, 2, 0], &[0, 1, 2, 0], None)]
+    #[case(&[0, 1, 2, 3, 0], &[0, 1, 5, 3, 0], Some(2..3))]
+    #[case(&[0, 1, 2, 3, 0], &[0, 1, 5, 6, 3, 0], Some(2..4))]
+    #[case(&[0, 1, 2, 3, 0], &[0, 1, 3, 0], Some(1..3))]
+    #[case(&[0, 1, 1, 0], &[0, 1, 0], Some(1..3))]
+    fn changed_range_works(
+        #[case] old: &[i64],
+        #[case] new: &[i64],
+        #[case] expected: Option<Range<usize>>,
+    ) {
+        assert_eq!(changed_range(&segments(old), &segments(new)), expected);
+    }
+
+    #[rstest]
+    fn split_samples_works() {
+        let segments = [
+            segment(&[0], 2. / PITCH_FRAME_RATE),
+            segment(&[1, 2], 2. / PITCH_FRAME_RATE),
+            segment(&[0], 1. / PITCH_FRAME_RATE),
+        ];
+        let samples = (0..14).map(|i| i as f32).collect::<Vec<_>>();
+        assert_eq!(
+            split_samples(&segments, &samples),
+            [
+                vec![0., 1., 2., 3.],
+                vec![4., 5., 6., 7., 8., 9., 10., 11.],
+                vec![12., 13.],
+            ]
+        );
+    }
+
+    #[rstest]
+    fn split_samples_splits_on_frame_boundaries() {
+        // 0.1秒は9.375フレームだが、推論では音素ごとに9フレームに丸める
+        let segments = [
+            segment(&[0], 0.1),
+            segment(&[1, 2], 0.1),
+            segment(&[0], 0.2),
+        ];
+        let samples = vec![0.; (9 + 18 + 19) * 256];
+        let lengths = split_samples(&segments, &samples)
+            .iter()
+            .map(Vec::len)
+            .collect::<Vec<_>>();
+        assert_eq!(lengths, [9 * 256, 18 * 256, 19 * 256]);
+    }
+
+    #[rstest]
+    fn split_segments_splits_frame_pitches() {
+        let decode_input = DecodeInput {
+            phoneme_ids: vec![0, 1, 2, 0],
+            pitches: vec![0., 5., 5.5, 0.],
+            durations: vec![
+                1. / PITCH_FRAME_RATE,
+                2. / PITCH_FRAME_RATE,
+                3. / PITCH_FRAME_RATE,
+                1. / PITCH_FRAME_RATE,
+            ],
+            segment_lengths: vec![1, 2, 1],
+            vowel_indices: vec![0, 1, 2, 3],
+            vibratos: vec![None; 4],
+            volumes: vec![1.; 4],
+        };
+        let frame_pitches = (0..7).map(|i| i as f32).collect::<Vec<_>>();
+        let segments = split_segments(&decode_input, Some(&frame_pitches));
+        assert_eq!(
+            segments
+                .iter()
+                .map(|segment| segment.frame_pitches.clone())
+                .collect::<Vec<_>>(),
+            [
+                Some(vec![0.]),
+                Some(vec![1., 2., 3., 4., 5.]),
+                Some(vec![6.]),
+            ]
+        );
+        assert!(split_segments(&decode_input, None)
+            .iter()
+            .all(|segment| segment.frame_pitches.is_none()));
+    }
+
+    #[rstest]
+    fn crossfade_works() {
+        let mut tail = vec![1.; 4];
+        crossfade_tail(&mut tail, &[0.; 6], 2);
+        assert_eq!(tail, [1., 1., 0.75, 0.25]);
+
+        let mut head = vec![1.; 4];
+        crossfade_head(&mut head, &[0.; 6], 2);
+        assert_eq!(head, [0.25, 0.75, 1., 1.]);
+    }
+}
@@ -0,0 +1,153 @@
+use super::frame_pitch::{frame_lengths, PITCH_FRAME_RATE};
+use super::pitch_shift::semitones_to_pitch;
+use crate::numerics::SplitMix64;
+use serde::{Deserialize, Serialize};
+
+/// 揺らぎの値を決めるフレームの間隔。その間のフレームは線形補間する
+const JITTER_INTERVAL: usize = 4;
+
+/// 長く伸ばした母音の音高に加えるビブラートと細かな揺らぎ
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Vibrato {
+    /// 1秒あたりの揺れの回数
+    pub rate: f32,
+    /// 揺れの深さ(半音)
+    pub depth: f32,
+    /// 母音が始まってから揺れ始めるまでの秒数
+    pub delay: f32,
+    /// ビブラートと揺らぎを加える母音の長さの最小値(秒)
+    pub min_length: f32,
+    /// フレームごとに加える不規則な揺らぎの大きさ(半音)
+    pub jitter: f32,
+}
+
+impl Default for Vibrato {
+    fn default() -> Self {
+        Self {
+            rate: 5.5,
+            depth: 0.5,
+            delay: 0.15,
+            min_length: 0.3,
+            jitter: 0.05,
+        }
+    }
+}
+
+/// フレームごとの音高のうち、`min_length`以上の有声の母音のフレームにビブラートと揺らぎを加える
+///
+/// `vibratos`は`vowel_indices`の各母音に加えるビブラート。
+/// 揺れの深さは揺れ始めてから1周期かけて大きくする。揺らぎは母音の位置をシードとするため、同じ入力からは同じ音高になる
+pub(crate) fn apply_vibratos(
+    frame_pitches: &mut [f32],
+    durations: &[f32],
+    vowel_indices: &[usize],
+    vibratos: &[Option<Vibrato>],
+) {
+    let frame_lengths = frame_lengths(durations);
+    let mut frame_start = 0;
+    let mut vowels = std::iter::zip(vowel_indices, vibratos).peekable();
+    for (phoneme, &frame_length) in frame_lengths.iter().enumerate() {
+        let frames = frame_start..(frame_start + frame_length).min(frame_pitches.len());
+        frame_start += frame_length;
+        let Some((_, vibrato)) = vowels.next_if(|&(&vowel, _)| vowel == phoneme) else {
+            continue;
+        };
+        let Some(vibrato) = vibrato else {
+            continue;
+        };
+        if durations[phoneme] < vibrato.min_length {
+            continue;
+        }
+
+        let depth = semitones_to_pitch(vibrato.depth);
+        let jitter = semitones_to_pitch(vibrato.jitter);
+        let mut rng = SplitMix64::new(phoneme as u64);
+        let mut knots = (rng.next_signed_unit(), rng.next_signed_unit());
+        for (i, frame) in frames.enumerate() {
+            let pitch = &mut frame_pitches[frame];
+            if *pitch <= 0. {
+                continue;
+            }
+            if i > 0 && i % JITTER_INTERVAL == 0 {
+                knots = (knots.1, rng.next_signed_unit());
+            }
+            let ratio = (i % JITTER_INTERVAL) as f32 / JITTER_INTERVAL as f32;
+            *pitch += jitter * (knots.0 + (knots.1 - knots.0) * ratio);
+
+            let time = (i as f32 + 0.5) / PITCH_FRAME_RATE - vibrato.delay;
+            if time > 0. {
+                let fade_in = (time * vibrato.rate).min(1.);
+                let phase = 2. * std::f32::consts::PI * vibrato.rate * time;
+                *pitch += depth * fade_in * phase.sin();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::frame_pitch::phoneme_frame_pitches;
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    fn apply_vibratos_works() {
+        // 先頭の無音、短い「ア」、長い「ア」、無声化した長い「ア」、末尾の無音
+        let durations = [
+            2. / PITCH_FRAME_RATE,
+            4. / PITCH_FRAME_RATE,
+            60. / PITCH_FRAME_RATE,
+            40. / PITCH_FRAME_RATE,
+            2. / PITCH_FRAME_RATE,
+        ];
+        let pitches = [0., 5., 5.5, 0., 0.];
+        let mut frame_pitches = phoneme_frame_pitches(&pitches, &durations);
+        let vibrato = Vibrato {
+            jitter: 0.,
+            ..Default::default()
+        };
+        apply_vibratos(
+            &mut frame_pitches,
+            &durations,
+            &[0, 1, 2, 3, 4],
+            &[None, Some(vibrato), Some(vibrato), Some(vibrato), None],
+        );
+
+        // 短い母音と無声の母音は変えない
+        assert_eq!(frame_pitches[..6], [0., 0., 5., 5., 5., 5.]);
+        assert!(frame_pitches[66..].iter().all(|&pitch| pitch == 0.));
+
+        let long_vowel = &frame_pitches[6..66];
+        let delay_frames = (vibrato.delay * PITCH_FRAME_RATE) as usize;
+        assert!(long_vowel[..delay_frames].iter().all(|&pitch| pitch == 5.5));
+        let max_deviation = long_vowel
+            .iter()
+            .map(|pitch| (pitch - 5.5).abs())
+            .fold(0., f32::max);
+        assert!(max_deviation > 0.);
+        assert!(max_deviation <= semitones_to_pitch(vibrato.depth) + 1e-6);
+    }
+
+    #[rstest]
+    fn jitter_is_reproducible_and_bounded() {
+        let durations = [1.];
+        let vibrato = Vibrato {
+            depth: 0.,
+            jitter: 0.1,
+            ..Default::default()
+        };
+        let apply = || {
+            let mut frame_pitches = vec![5.; frame_lengths(&durations)[0]];
+            apply_vibratos(&mut frame_pitches, &durations, &[0], &[Some(vibrato)]);
+            frame_pitches
+        };
+        let frame_pitches = apply();
+        assert_eq!(frame_pitches, apply());
+        assert!(frame_pitches.iter().any(|&pitch| pitch != 5.));
+        assert!(frame_pitches
+            .iter()
+            .all(|pitch| (pitch - 5.).abs() <= semitones_to_pitch(0.1) + 1e-6));
+    }
+}
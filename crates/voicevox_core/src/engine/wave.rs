@@ -0,0 +1,440 @@
+use std::f64::consts::PI;
+use std::io::{Cursor, Write};
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum WaveError {
+    #[error("not a RIFF/WAVE data")]
+    NotRiffWave,
+
+    #[error("chunk not found chunk_id:{chunk_id}")]
+    ChunkNotFound { chunk_id: &'static str },
+
+    #[error("unsupported format audio_format:{audio_format} bits_per_sample:{bits_per_sample}")]
+    UnsupportedFormat {
+        audio_format: u16,
+        bits_per_sample: u16,
+    },
+
+    #[error("unsupported channel conversion from:{from} to:{to}")]
+    UnsupportedChannelConversion { from: u16, to: u16 },
+
+    #[error("wave data too large to write num_samples:{num_samples}")]
+    TooLarge { num_samples: usize },
+
+    #[error("invalid output format sampling_rate:{sampling_rate} num_channels:{num_channels}")]
+    InvalidOutputFormat {
+        sampling_rate: u32,
+        num_channels: u16,
+    },
+}
+
+type Result<T> = std::result::Result<T, WaveError>;
+
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+
+/// ダウンサンプリング時の低域通過フィルタの、中心から片側の零点の数
+const LOW_PASS_ZERO_CROSSINGS: usize = 8;
+
+/// チャンネルごとのサンプルを交互に並べて保持する波形データ
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wave {
+    samples: Vec<f32>,
+    sampling_rate: u32,
+    num_channels: u16,
+}
+
+impl Wave {
+    pub fn new(samples: Vec<f32>, sampling_rate: u32, num_channels: u16) -> Self {
+        Self {
+            samples,
+            sampling_rate,
+            num_channels,
+        }
+    }
+
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    pub fn sampling_rate(&self) -> u32 {
+        self.sampling_rate
+    }
+
+    pub fn num_channels(&self) -> u16 {
+        self.num_channels
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.samples.len() / self.num_channels as usize
+    }
+
+    /// wavフォーマットのバイト列を読み込む。
+    ///
+    /// 16bitのリニアPCMと32bitのIEEE浮動小数点数に対応している。
+    pub fn from_wav_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(WaveError::NotRiffWave);
+        }
+
+        let mut format = None;
+        let mut data = None;
+        let mut pos = 12;
+        while pos + 8 <= bytes.len() {
+            let chunk_id = &bytes[pos..pos + 4];
+            let chunk_size = u32::from_le_bytes(bytes[pos + 4..pos + 8].try_into().unwrap());
+            let start = pos + 8;
+            let end = (start + chunk_size as usize).min(bytes.len());
+            match chunk_id {
+                b"fmt " if end - start >= 16 => {
+                    let chunk = &bytes[start..end];
+                    let u16_at = |i: usize| u16::from_le_bytes([chunk[i], chunk[i + 1]]);
+                    let sampling_rate = u32::from_le_bytes(chunk[4..8].try_into().unwrap());
+                    format = Some((u16_at(0), u16_at(2), sampling_rate, u16_at(14)));
+                }
+                b"data" => data = Some(&bytes[start..end]),
+                _ => {}
+            }
+            // チャンクは2バイト境界に揃えられている
+            pos = start + chunk_size as usize + (chunk_size as usize & 1);
+        }
+
+        let (audio_format, num_channels, sampling_rate, bits_per_sample) =
+            format.ok_or(WaveError::ChunkNotFound { chunk_id: "fmt " })?;
+        let data = data.ok_or(WaveError::ChunkNotFound { chunk_id: "data" })?;
+        if num_channels == 0 || sampling_rate == 0 {
+            return Err(WaveError::UnsupportedFormat {
+                audio_format,
+                bits_per_sample,
+            });
+        }
+
+        let samples = match (audio_format, bits_per_sample) {
+            (WAVE_FORMAT_PCM, 16) => data
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 0x8000 as f32)
+                .collect(),
+            (WAVE_FORMAT_IEEE_FLOAT, 32) => data
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+            _ => {
+                return Err(WaveError::UnsupportedFormat {
+                    audio_format,
+                    bits_per_sample,
+                })
+            }
+        };
+
+        Ok(Self::new(samples, sampling_rate, num_channels))
+    }
+
+    /// 16bitのリニアPCMとしてwavフォーマットのバイト列に変換する
+    ///
+    /// wavフォーマットの32bitのサイズで表せないほど長いときはエラーとする
+    pub fn to_wav_bytes(&self) -> Result<Vec<u8>> {
+        let bit_depth: u16 = 16;
+        let too_large = || WaveError::TooLarge {
+            num_samples: self.samples.len(),
+        };
+        let block_size = (bit_depth / 8)
+            .checked_mul(self.num_channels)
+            .ok_or_else(too_large)?;
+        let bytes_size = u32::try_from(self.samples.len())
+            .ok()
+            .and_then(|num_samples| num_samples.checked_mul(2))
+            .ok_or_else(too_large)?;
+        let wave_size = bytes_size.checked_add(44).ok_or_else(too_large)?;
+        let block_rate = self.sampling_rate.checked_mul(block_size as u32).ok_or(
+            WaveError::InvalidOutputFormat {
+                sampling_rate: self.sampling_rate,
+                num_channels: self.num_channels,
+            },
+        )?;
+
+        let buf: Vec<u8> = Vec::with_capacity(wave_size as usize);
+        let mut cur = Cursor::new(buf);
+
+        cur.write_all("RIFF".as_bytes()).unwrap();
+        cur.write_all(&(wave_size - 8).to_le_bytes()).unwrap();
+        cur.write_all("WAVEfmt ".as_bytes()).unwrap();
+        cur.write_all(&16_u32.to_le_bytes()).unwrap(); // fmt header length
+        cur.write_all(&WAVE_FORMAT_PCM.to_le_bytes()).unwrap(); //linear PCM
+        cur.write_all(&self.num_channels.to_le_bytes()).unwrap();
+        cur.write_all(&self.sampling_rate.to_le_bytes()).unwrap();
+        cur.write_all(&block_rate.to_le_bytes()).unwrap();
+        cur.write_all(&block_size.to_le_bytes()).unwrap();
+        cur.write_all(&bit_depth.to_le_bytes()).unwrap();
+        cur.write_all("data".as_bytes()).unwrap();
+        cur.write_all(&bytes_size.to_le_bytes()).unwrap();
+
+        for value in &self.samples {
+            let data = (value.clamp(-1., 1.) * 0x7fff as f32) as i16;
+            cur.write_all(&data.to_le_bytes()).unwrap();
+        }
+
+        Ok(cur.into_inner())
+    }
+
+    /// 線形補間でサンプリングレートを変換する
+    ///
+    /// ダウンサンプリングでは折り返し雑音を防ぐため、補間の前に変換後のナイキスト周波数より高い成分を低域通過フィルタで除く
+    pub fn resample(&self, sampling_rate: u32) -> Self {
+        if sampling_rate == self.sampling_rate || self.samples.is_empty() {
+            return Self::new(self.samples.clone(), sampling_rate, self.num_channels);
+        }
+
+        let filtered;
+        let source = if sampling_rate < self.sampling_rate {
+            filtered = self.low_pass(sampling_rate as f64 / self.sampling_rate as f64);
+            &filtered
+        } else {
+            &self.samples
+        };
+
+        let channels = self.num_channels as usize;
+        let num_frames = self.num_frames();
+        let new_num_frames =
+            (num_frames as f64 * sampling_rate as f64 / self.sampling_rate as f64).round() as usize;
+        let step = self.sampling_rate as f64 / sampling_rate as f64;
+
+        let mut samples = Vec::with_capacity(new_num_frames * channels);
+        for i in 0..new_num_frames {
+            let position = i as f64 * step;
+            let left = (position.floor() as usize).min(num_frames - 1);
+            let right = (left + 1).min(num_frames - 1);
+            let ratio = (position - left as f64) as f32;
+            for channel in 0..channels {
+                let left_value = source[left * channels + channel];
+                let right_value = source[right * channels + channel];
+                samples.push(left_value + (right_value - left_value) * ratio);
+            }
+        }
+        Self::new(samples, sampling_rate, self.num_channels)
+    }
+
+    /// ブラックマン窓をかけたsinc関数で、ナイキスト周波数の`cutoff`倍より高い成分を除く
+    ///
+    /// 範囲外のサンプルは端のサンプルと同じ値とする
+    fn low_pass(&self, cutoff: f64) -> Vec<f32> {
+        let half_width = (LOW_PASS_ZERO_CROSSINGS as f64 / cutoff).ceil() as usize;
+        let kernel = (0..=2 * half_width)
+            .map(|i| {
+                let x = i as f64 - half_width as f64;
+                let sinc = if x == 0. {
+                    1.
+                } else {
+                    (PI * cutoff * x).sin() / (PI * cutoff * x)
+                };
+                let phase = PI * x / (half_width + 1) as f64;
+                let window = 0.42 + 0.5 * phase.cos() + 0.08 * (2. * phase).cos();
+                sinc * window
+            })
+            .collect::<Vec<_>>();
+        let kernel_sum = kernel.iter().sum::<f64>();
+
+        let channels = self.num_channels as usize;
+        let last_frame = self.num_frames() as isize - 1;
+        let mut samples = Vec::with_capacity(self.samples.len());
+        for frame in 0..self.num_frames() {
+            for channel in 0..channels {
+                let value = kernel
+                    .iter()
+                    .enumerate()
+                    .map(|(i, weight)| {
+                        let source = (frame as isize + i as isize - half_width as isize)
+                            .clamp(0, last_frame) as usize;
+                        weight * self.samples[source * channels + channel] as f64
+                    })
+                    .sum::<f64>();
+                samples.push((value / kernel_sum) as f32);
+            }
+        }
+        samples
+    }
+
+    /// チャンネル数を変換する。
+    ///
+    /// モノラルからの複製と、モノラルへのダウンミックスのみに対応している。
+    pub fn convert_channels(&self, num_channels: u16) -> Result<Self> {
+        let samples = if num_channels == self.num_channels {
+            self.samples.clone()
+        } else if self.num_channels == 1 {
+            self.samples
+                .iter()
+                .flat_map(|&sample| (0..num_channels).map(move |_| sample))
+                .collect()
+        } else if num_channels == 1 {
+            self.samples
+                .chunks_exact(self.num_channels as usize)
+                .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+                .collect()
+        } else {
+            return Err(WaveError::UnsupportedChannelConversion {
+                from: self.num_channels,
+                to: num_channels,
+            });
+        };
+        Ok(Self::new(samples, self.sampling_rate, num_channels))
+    }
+}
+
+/// 複数の波形を一つに繋げる。
+///
+/// 各波形は`sampling_rate`と`num_channels`に揃えられる。`crossfade_length`が正のときは隣り合う波形を
+/// その秒数だけ重ねてクロスフェードし、そうでないときは`gap_length`秒の無音を挟む。
+pub fn connect_waves(
+    waves: &[Wave],
+    sampling_rate: u32,
+    num_channels: u16,
+    gap_length: f32,
+    crossfade_length: f32,
+) -> Result<Wave> {
+    if sampling_rate == 0 || num_channels == 0 {
+        return Err(WaveError::InvalidOutputFormat {
+            sampling_rate,
+            num_channels,
+        });
+    }
+
+    let channels = num_channels as usize;
+    let gap_frames = (gap_length.max(0.) * sampling_rate as f32).round() as usize;
+    let crossfade_frames = (crossfade_length.max(0.) * sampling_rate as f32).round() as usize;
+
+    let mut samples: Vec<f32> = Vec::new();
+    for (i, wave) in waves.iter().enumerate() {
+        let wave = wave.convert_channels(num_channels)?.resample(sampling_rate);
+
+        if i == 0 {
+            samples.extend_from_slice(wave.samples());
+            continue;
+        }
+
+        if crossfade_frames == 0 {
+            samples.resize(samples.len() + gap_frames * channels, 0.);
+            samples.extend_from_slice(wave.samples());
+            continue;
+        }
+
+        let overlap_frames = crossfade_frames
+            .min(samples.len() / channels)
+            .min(wave.num_frames());
+        let overlap_start = samples.len() - overlap_frames * channels;
+        for frame in 0..overlap_frames {
+            let fade_in = (frame as f32 + 0.5) / overlap_frames as f32;
+            for channel in 0..channels {
+                let index = frame * channels + channel;
+                let current = &mut samples[overlap_start + index];
+                *current = *current * (1. - fade_in) + wave.samples()[index] * fade_in;
+            }
+        }
+        samples.extend_from_slice(&wave.samples()[overlap_frames * channels..]);
+    }
+
+    Ok(Wave::new(samples, sampling_rate, num_channels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    fn wav_bytes_round_trip_works(#[case] num_channels: u16) {
+        let wave = Wave::new(vec![0., 0.5, -0.5, 0.25], 24000, num_channels);
+        let bytes = wave.to_wav_bytes().unwrap();
+        assert_eq!(bytes.len(), 44 + 4 * 2);
+
+        let parsed = Wave::from_wav_bytes(&bytes).unwrap();
+        assert_eq!(parsed.sampling_rate(), 24000);
+        assert_eq!(parsed.num_channels(), num_channels);
+        for (actual, expected) in std::iter::zip(parsed.samples(), wave.samples()) {
+            assert!((actual - expected).abs() < 1e-3, "{actual} != {expected}");
+        }
+    }
+
+    #[rstest]
+    fn to_wav_bytes_fails_when_block_rate_overflows() {
+        let wave = Wave::new(vec![0.], u32::MAX, 1);
+        assert_eq!(
+            wave.to_wav_bytes(),
+            Err(WaveError::InvalidOutputFormat {
+                sampling_rate: u32::MAX,
+                num_channels: 1,
+            })
+        );
+    }
+
+    #[rstest]
+    #[case(b"".to_vec(), WaveError::NotRiffWave)]
+    #[case(b"RIFF\0\0\0\0WAVE".to_vec(), WaveError::ChunkNotFound { chunk_id: "fmt " })]
+    fn from_wav_bytes_fails(#[case] bytes: Vec<u8>, #[case] expected: WaveError) {
+        assert_eq!(Wave::from_wav_bytes(&bytes), Err(expected));
+    }
+
+    #[rstest]
+    #[case(48000, 24000, 50)]
+    #[case(24000, 48000, 200)]
+    #[case(48000, 44100, 92)]
+    fn resample_works(#[case] from: u32, #[case] to: u32, #[case] expected_frames: usize) {
+        let wave = Wave::new(vec![0.5; 100], from, 1);
+        let resampled = wave.resample(to);
+        assert_eq!(resampled.sampling_rate(), to);
+        assert_eq!(resampled.num_frames(), expected_frames);
+        assert!(resampled.samples().iter().all(|&s| (s - 0.5).abs() < 1e-6));
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    fn resample_removes_frequencies_above_nyquist(#[case] num_channels: u16) {
+        // 変換後のナイキスト周波数12kHzより高い、18kHzの正弦波
+        let samples = (0..4800)
+            .flat_map(|i| {
+                let value = (2. * PI * 18000. * i as f64 / 48000.).sin() as f32 * 0.5;
+                (0..num_channels).map(move |_| value)
+            })
+            .collect();
+        let resampled = Wave::new(samples, 48000, num_channels).resample(24000);
+        assert_eq!(resampled.num_frames(), 2400);
+        // 範囲外を端のサンプルで埋めた影響のある、両端のフィルタの幅を除く
+        let channels = num_channels as usize;
+        let samples = resampled.samples();
+        let interior = &samples[16 * channels..samples.len() - 16 * channels];
+        let max = interior.iter().fold(0_f32, |max, s| max.max(s.abs()));
+        assert!(max < 1e-3, "{max}");
+    }
+
+    #[rstest]
+    fn convert_channels_works() {
+        let mono = Wave::new(vec![0.1, 0.2], 48000, 1);
+        let stereo = mono.convert_channels(2).unwrap();
+        assert_eq!(stereo.samples(), &[0.1, 0.1, 0.2, 0.2]);
+        assert_eq!(stereo.convert_channels(1).unwrap(), mono);
+        assert_eq!(
+            Wave::new(vec![0.; 6], 48000, 3).convert_channels(2),
+            Err(WaveError::UnsupportedChannelConversion { from: 3, to: 2 })
+        );
+    }
+
+    #[rstest]
+    #[case(0.01, 0., 100 + 10 + 100)]
+    #[case(0., 0.01, 100 + 100 - 10)]
+    #[case(0., 0., 200)]
+    fn connect_waves_works(
+        #[case] gap_length: f32,
+        #[case] crossfade_length: f32,
+        #[case] expected_frames: usize,
+    ) {
+        let waves = [
+            Wave::new(vec![1.; 100], 1000, 1),
+            Wave::new(vec![1.; 400], 2000, 2),
+        ];
+        let wave = connect_waves(&waves, 1000, 2, gap_length, crossfade_length).unwrap();
+        assert_eq!(wave.num_channels(), 2);
+        assert_eq!(wave.num_frames(), expected_frames);
+    }
+}
@@ -1,4 +1,6 @@
-use self::engine::{FullContextLabelError, KanaParseError};
+use self::engine::{
+    AccentPhraseEditError, FullContextLabelError, KanaParseError, ModelValidationError, WaveError,
+};
 use self::result_code::SharevoxResultCode::{self, *};
 use super::*;
 //use engine::
@@ -67,6 +69,45 @@ pub enum Error {
     #[error("{},{0}", base_error_message(SHAREVOX_RESULT_PARSE_KANA_ERROR))]
     ParseKana(#[from] KanaParseError),
 
+    #[error("{},{0}", base_error_message(SHAREVOX_RESULT_INVALID_WAVE_ERROR))]
+    InvalidWave(#[from] WaveError),
+
+    #[error(
+        "{},{0}",
+        base_error_message(SHAREVOX_RESULT_INVALID_AUDIO_QUERY_ERROR)
+    )]
+    InvalidModel(#[from] ModelValidationError),
+
+    #[error(
+        "{},{0}",
+        base_error_message(SHAREVOX_RESULT_INVALID_ACCENT_PHRASE_ERROR)
+    )]
+    InvalidAccentPhrase(#[source] ModelValidationError),
+
+    #[error(
+        "{},{0}",
+        base_error_message(SHAREVOX_RESULT_INVALID_ACCENT_PHRASE_EDIT_ERROR)
+    )]
+    EditAccentPhrase(#[from] AccentPhraseEditError),
+
+    #[error("{},{0}", base_error_message(SHAREVOX_RESULT_PARSE_HTS_LABEL_ERROR))]
+    ParseHtsLabel(#[from] HtsLabelError),
+
+    #[error(
+        "{},{0}",
+        base_error_message(SHAREVOX_RESULT_INVALID_FRAME_PITCH_ERROR)
+    )]
+    InvalidFramePitch(#[from] FramePitchError),
+
+    #[error("{},{0}", base_error_message(SHAREVOX_RESULT_PARSE_SCORE_ERROR))]
+    ParseScore(#[from] ScoreError),
+
+    #[error(
+        "{},{0}",
+        base_error_message(SHAREVOX_RESULT_INVALID_TARGET_DURATION_ERROR)
+    )]
+    InvalidTargetDuration(#[from] TargetDurationError),
+
     #[error("{},{0}", base_error_message(SHAREVOX_RESULT_LOAD_LIBRARIES_ERROR))]
     LoadLibraries(#[source] anyhow::Error),
 
@@ -128,6 +169,14 @@ impl PartialEq for Error {
             ) => model_index1 == model_index2,
             (Self::ExtractFullContextLabel(e1), Self::ExtractFullContextLabel(e2)) => e1 == e2,
             (Self::ParseKana(e1), Self::ParseKana(e2)) => e1 == e2,
+            (Self::InvalidWave(e1), Self::InvalidWave(e2)) => e1 == e2,
+            (Self::InvalidModel(e1), Self::InvalidModel(e2))
+            | (Self::InvalidAccentPhrase(e1), Self::InvalidAccentPhrase(e2)) => e1 == e2,
+            (Self::EditAccentPhrase(e1), Self::EditAccentPhrase(e2)) => e1 == e2,
+            (Self::ParseHtsLabel(e1), Self::ParseHtsLabel(e2)) => e1 == e2,
+            (Self::InvalidFramePitch(e1), Self::InvalidFramePitch(e2)) => e1 == e2,
+            (Self::ParseScore(e1), Self::ParseScore(e2)) => e1 == e2,
+            (Self::InvalidTargetDuration(e1), Self::InvalidTargetDuration(e2)) => e1 == e2,
             (
                 Self::InvalidLibraryUuid {
                     library_uuid: library_uuid1,
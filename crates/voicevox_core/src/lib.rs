@@ -17,7 +17,15 @@ mod test_util;
 #[cfg(test)]
 use self::test_util::*;
 
-pub use self::engine::AudioQueryModel;
+pub use self::engine::{
+    create_kana, parse_kana, parse_kana_lenient, AccentPhraseEdit, AccentPhraseEditError,
+    AccentPhraseModel, AccentPhraseProsody, AudioQueryModel, DurationFit, FramePitch,
+    FramePitchError, HtsLabelError, InterrogativeUpspeak, KanaParseError, KanaParseErrorKind,
+    ModelValidationError, MoraModel, NjdNodeModel, PitchBreakpoint, PitchContour,
+    PitchInterpolation, ProsodyPreset, ProsodyVariation, Score, ScoreError, ScoreNote,
+    SynthesisSession, TargetDuration, TargetDurationError, TextAnalysisModel, TextSpan,
+    UpspeakContour, Vibrato, PITCH_FRAME_RATE,
+};
 pub use self::error::*;
 pub use self::result::*;
 
@@ -15,3 +15,26 @@ pub(crate) impl f32 {
         rounded
     }
 }
+
+/// シードから再現可能な擬似乱数列を生成する(SplitMix64)
+#[derive(Clone, Debug)]
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// -1以上1未満の一様乱数を返す
+    pub(crate) fn next_signed_unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1_u64 << 23) as f32 - 1.
+    }
+}
@@ -67,12 +67,26 @@ impl VoicevoxCore {
             options.load_all_models,
         )?;
         if let Some(open_jtalk_dict_dir) = options.open_jtalk_dict_dir {
-            self.synthesis_engine
-                .load_openjtalk_dict(open_jtalk_dict_dir)?;
+            if options.load_open_jtalk_dict_lazily {
+                self.synthesis_engine
+                    .load_openjtalk_dict_lazily(open_jtalk_dict_dir);
+            } else {
+                self.synthesis_engine
+                    .load_openjtalk_dict(open_jtalk_dict_dir)?;
+            }
         }
         Ok(())
     }
 
+    pub fn load_openjtalk_dict(&mut self, open_jtalk_dict_dir: &Path) -> Result<()> {
+        self.synthesis_engine
+            .load_openjtalk_dict(open_jtalk_dict_dir)
+    }
+
+    pub fn is_openjtalk_dict_loaded(&self) -> bool {
+        self.synthesis_engine.is_openjtalk_dict_loaded()
+    }
+
     pub fn is_gpu_mode(&self) -> bool {
         self.use_gpu
     }
@@ -131,18 +145,44 @@ impl VoicevoxCore {
         )
     }
 
+    /// 音素ごとではなく、[`PITCH_FRAME_RATE`]のフレームごとの音高でdecodeする
+    ///
+    /// `frame_pitch_vector`の長さは、`duration_vector`から求まるフレーム数と同じでなければならない
+    pub fn decode_with_frame_pitch(
+        &mut self,
+        phoneme_vector: &[i64],
+        frame_pitch_vector: &[f32],
+        duration_vector: &[f32],
+        speaker_id: u32,
+    ) -> Result<Vec<f32>> {
+        self.synthesis_engine
+            .inference_core_mut()
+            .decode_with_frame_pitch(
+                phoneme_vector,
+                frame_pitch_vector,
+                duration_vector,
+                speaker_id,
+            )
+    }
+
     pub fn audio_query(
         &mut self,
         text: &str,
         speaker_id: u32,
         options: AudioQueryOptions,
     ) -> Result<AudioQueryModel> {
-        if !self.synthesis_engine.is_openjtalk_dict_loaded() {
-            return Err(Error::NotLoadedOpenjtalkDict);
-        }
+        // kanaの解析にはOpenJTalkの辞書を必要としない
         let accent_phrases = if options.kana {
+            let accent_phrases = if options.lenient_kana {
+                let (mut accent_phrases, missing_accent_indices) = parse_kana_lenient(text)?;
+                self.synthesis_engine
+                    .predict_missing_accents(&mut accent_phrases, &missing_accent_indices);
+                accent_phrases
+            } else {
+                parse_kana(text)?
+            };
             self.synthesis_engine
-                .replace_mora_data(&parse_kana(text)?, speaker_id)?
+                .replace_mora_data(&accent_phrases, speaker_id)?
         } else {
             self.synthesis_engine
                 .create_accent_phrases(text, speaker_id)?
@@ -150,7 +190,7 @@ impl VoicevoxCore {
 
         let kana = create_kana(&accent_phrases);
 
-        Ok(AudioQueryModel::new(
+        let mut audio_query = AudioQueryModel::new(
             accent_phrases,
             1.,
             0.,
@@ -161,36 +201,460 @@ impl VoicevoxCore {
             SynthesisEngine::DEFAULT_SAMPLING_RATE,
             false,
             kana,
+        );
+        audio_query.set_prosody_preset(options.prosody_preset);
+        Ok(audio_query)
+    }
+
+    /// モノラベル、またはフルコンテキストラベルのHTSラベルから、ラベルの時刻を音素の長さとしたAudioQueryを作る
+    ///
+    /// `pitches`にラベルごとの音高を指定しないときは、音高を指定した話者で推論する
+    pub fn audio_query_from_labels(
+        &mut self,
+        labels: &str,
+        speaker_id: u32,
+        pitches: Option<&[f32]>,
+    ) -> Result<AudioQueryModel> {
+        let labels = parse_hts_labels(labels)?;
+        let LabelAccentPhrases {
+            mut accent_phrases,
+            missing_accent_indices,
+            pre_phoneme_length,
+            post_phoneme_length,
+        } = label_accent_phrases(&labels, pitches)?;
+        if pitches.is_none() {
+            self.synthesis_engine
+                .predict_missing_accents(&mut accent_phrases, &missing_accent_indices);
+            accent_phrases =
+                self.synthesis_engine
+                    .replace_mora_pitch(&accent_phrases, speaker_id, None)?;
+        }
+
+        let kana = create_kana(&accent_phrases);
+
+        Ok(AudioQueryModel::new(
+            accent_phrases,
+            1.,
+            0.,
+            1.,
+            1.,
+            pre_phoneme_length,
+            post_phoneme_length,
+            SynthesisEngine::DEFAULT_SAMPLING_RATE,
+            false,
+            kana,
         ))
     }
 
+    /// HTSラベルの時刻を音素の長さとして音声合成する
+    ///
+    /// ラベルの音素の長さを保つため、疑問文の語尾の音高の調整は行わない
+    pub fn synthesis_from_labels(
+        &mut self,
+        labels: &str,
+        speaker_id: u32,
+        pitches: Option<&[f32]>,
+    ) -> Result<Vec<u8>> {
+        let audio_query = self.audio_query_from_labels(labels, speaker_id, pitches)?;
+        self.synthesis_engine
+            .synthesis_wave_format(&audio_query, speaker_id, None, None)
+    }
+
+    /// 楽譜の音符の開始時刻に母音が始まるように、音素の長さと音高を楽譜に合わせたAudioQueryを作る
+    ///
+    /// 子音の長さは指定した話者で推論し、音符の開始時刻より前に置く
+    pub fn audio_query_from_score(
+        &mut self,
+        score: &Score,
+        speaker_id: u32,
+        options: SingOptions,
+    ) -> Result<AudioQueryModel> {
+        let (accent_phrases, pre_phoneme_length, post_phoneme_length) = score_accent_phrases(
+            score,
+            options.key_shift,
+            options.pre_phoneme_length,
+            options.post_phoneme_length,
+            |accent_phrases| {
+                self.synthesis_engine
+                    .replace_phoneme_length(accent_phrases, speaker_id)
+                    .map(|(accent_phrases, _)| accent_phrases)
+            },
+        )?;
+
+        let kana = create_kana(&accent_phrases);
+
+        Ok(AudioQueryModel::new(
+            accent_phrases,
+            1.,
+            0.,
+            1.,
+            1.,
+            pre_phoneme_length,
+            post_phoneme_length,
+            SynthesisEngine::DEFAULT_SAMPLING_RATE,
+            false,
+            kana,
+        ))
+    }
+
+    /// 楽譜を歌う音声を合成する
+    ///
+    /// 楽譜の時刻を保つため、疑問文の語尾の音高の調整は行わない
+    pub fn sing(
+        &mut self,
+        score: &Score,
+        speaker_id: u32,
+        options: SingOptions,
+    ) -> Result<Vec<u8>> {
+        let audio_query = self.audio_query_from_score(score, speaker_id, options)?;
+        self.synthesis_engine
+            .synthesis_wave_format(&audio_query, speaker_id, None, None)
+    }
+
+    /// AudioQueryの音素の長さと音高を別の話者で推論し直す
+    ///
+    /// 固定したモーラの値と、話速などのAudioQuery全体の値は変更しない
+    pub fn retarget_audio_query(
+        &mut self,
+        audio_query: &AudioQueryModel,
+        speaker_id: u32,
+        options: RetargetOptions,
+    ) -> Result<AudioQueryModel> {
+        validate_accent_phrases(audio_query.accent_phrases())
+            .map_err(Error::InvalidAccentPhrase)?;
+        let accent_phrases = self.synthesis_engine.retarget_accent_phrases(
+            audio_query.accent_phrases(),
+            speaker_id,
+            options.source_speaker_id,
+        )?;
+        let mut audio_query = audio_query.clone();
+        audio_query.set_accent_phrases(accent_phrases);
+        Ok(audio_query)
+    }
+
+    /// 音声合成モデルを使わずに、テキストの読みとアクセントを解析する
+    pub fn analyze_text(&mut self, text: &str) -> Result<TextAnalysisModel> {
+        let accent_phrases = self.synthesis_engine.analyze_text(text)?;
+        let kana = create_kana(&accent_phrases);
+        Ok(TextAnalysisModel::new(accent_phrases, kana))
+    }
+
+    /// テキストをOpenJTalkで解析し、NJDのノードを返す
+    pub fn extract_njd_nodes(&mut self, text: &str) -> Result<Vec<NjdNodeModel>> {
+        self.synthesis_engine.extract_njd_nodes(text)
+    }
+
+    /// アクセント句の音素の長さを、指定した話者で推論し直す
+    pub fn mora_length(
+        &mut self,
+        accent_phrases: &[AccentPhraseModel],
+        speaker_id: u32,
+    ) -> Result<Vec<AccentPhraseModel>> {
+        validate_accent_phrases(accent_phrases).map_err(Error::InvalidAccentPhrase)?;
+        let (accent_phrases, _) = self
+            .synthesis_engine
+            .replace_phoneme_length(accent_phrases, speaker_id)?;
+        Ok(accent_phrases)
+    }
+
+    /// アクセント句の音高を、指定した話者で推論し直す
+    pub fn mora_pitch(
+        &mut self,
+        accent_phrases: &[AccentPhraseModel],
+        speaker_id: u32,
+    ) -> Result<Vec<AccentPhraseModel>> {
+        validate_accent_phrases(accent_phrases).map_err(Error::InvalidAccentPhrase)?;
+        self.synthesis_engine
+            .replace_mora_pitch(accent_phrases, speaker_id, None)
+    }
+
+    /// アクセント句の音素の長さと音高を、指定した話者で推論し直す
+    pub fn mora_data(
+        &mut self,
+        accent_phrases: &[AccentPhraseModel],
+        speaker_id: u32,
+    ) -> Result<Vec<AccentPhraseModel>> {
+        validate_accent_phrases(accent_phrases).map_err(Error::InvalidAccentPhrase)?;
+        self.synthesis_engine
+            .replace_mora_data(accent_phrases, speaker_id)
+    }
+
+    /// アクセント句の列を編集し、編集したアクセント句の音素の長さと音高を指定した話者で推論し直す
+    ///
+    /// 推論は編集の影響を受けるアクセント句と前後の数個のアクセント句だけで行い、
+    /// 編集の影響を受けないアクセント句の値は変更しない
+    pub fn edit_accent_phrases(
+        &mut self,
+        accent_phrases: &[AccentPhraseModel],
+        edit: &AccentPhraseEdit,
+        speaker_id: u32,
+    ) -> Result<Vec<AccentPhraseModel>> {
+        validate_accent_phrases(accent_phrases).map_err(Error::InvalidAccentPhrase)?;
+        let mut accent_phrases = accent_phrases.to_vec();
+        let applied_edit = edit.apply(&mut accent_phrases)?;
+        self.synthesis_engine
+            .predict_missing_accents(&mut accent_phrases, &applied_edit.missing_accent_indices);
+
+        // 推論は前後の文脈のアクセント句を含む範囲だけで行い、影響を受けるアクセント句の結果だけを用いる
+        let context = applied_edit.context_range(accent_phrases.len());
+        let mut predicted = self
+            .synthesis_engine
+            .replace_mora_data(&accent_phrases[context.clone()], speaker_id)?;
+        for index in applied_edit.affected {
+            std::mem::swap(
+                &mut accent_phrases[index],
+                &mut predicted[index - context.start],
+            );
+        }
+        Ok(accent_phrases)
+    }
+
     pub fn synthesis(
         &mut self,
         audio_query: &AudioQueryModel,
         speaker_id: u32,
         options: SynthesisOptions,
     ) -> Result<Vec<u8>> {
+        let interrogative_upspeak = self.interrogative_upspeak(speaker_id, &options);
+        let fitted_query;
+        let audio_query = if let Some(target_duration) = &options.target_duration {
+            fitted_query =
+                engine::fit_duration(audio_query, interrogative_upspeak.as_ref(), target_duration)?
+                    .audio_query;
+            &fitted_query
+        } else {
+            audio_query
+        };
         self.synthesis_engine.synthesis_wave_format(
             audio_query,
             speaker_id,
-            options.enable_interrogative_upspeak,
+            interrogative_upspeak.as_ref(),
+            options.pitch_contour.as_ref(),
+        )
+    }
+
+    /// AudioQueryの母音とポーズの長さを伸縮させ、音声合成したときの長さを目標の長さに合わせる
+    ///
+    /// 子音の長さと先頭と末尾の無音の長さ、固定したモーラの長さは変えない。
+    /// 返り値の`audio_query`を`options`と同じ疑問文の調整で音声合成すると、`duration`の長さの音声になる
+    pub fn fit_duration(
+        &self,
+        audio_query: &AudioQueryModel,
+        speaker_id: u32,
+        target_duration: &TargetDuration,
+        options: &SynthesisOptions,
+    ) -> Result<DurationFit> {
+        let interrogative_upspeak = self.interrogative_upspeak(speaker_id, options);
+        engine::fit_duration(audio_query, interrogative_upspeak.as_ref(), target_duration)
+    }
+
+    /// AudioQueryの音素の長さと、フレームごとの音高、または音高曲線の制御点から音声合成する
+    pub fn synthesis_with_frame_pitch(
+        &mut self,
+        audio_query: &AudioQueryModel,
+        speaker_id: u32,
+        frame_pitch: &FramePitch,
+    ) -> Result<Vec<u8>> {
+        let wave = self.synthesis_engine.synthesis_with_frame_pitch(
+            audio_query,
+            speaker_id,
+            frame_pitch,
+        )?;
+        Ok(SynthesisEngine::to_wav_bytes(&wave, audio_query))
+    }
+
+    /// 変更のない区間の合成結果を再利用して音声合成するセッションを作る
+    ///
+    /// `synthesis_options.pitch_contour`とAudioQueryのビブラートは、[`Self::synthesis`]と同じくフレームごとの音高で音声合成する。
+    /// `synthesis_options.target_duration`は用いない
+    pub fn new_synthesis_session(
+        &self,
+        speaker_id: u32,
+        synthesis_options: SynthesisOptions,
+        session_options: SynthesisSessionOptions,
+    ) -> SynthesisSession {
+        SynthesisSession::new(
+            speaker_id,
+            self.interrogative_upspeak(speaker_id, &synthesis_options),
+            synthesis_options.pitch_contour,
+            session_options.context_margin,
+            session_options.crossfade_length,
         )
     }
 
+    /// セッションの前回の音声合成から変更のあったアクセント句だけを推論し直して音声合成する
+    pub fn synthesis_with_session(
+        &mut self,
+        session: &mut SynthesisSession,
+        audio_query: &AudioQueryModel,
+    ) -> Result<Vec<u8>> {
+        let wave = session.synthesis(&mut self.synthesis_engine, audio_query)?;
+        Ok(SynthesisEngine::to_wav_bytes(&wave, audio_query))
+    }
+
     pub fn tts(&mut self, text: &str, speaker_id: u32, options: TtsOptions) -> Result<Vec<u8>> {
         let audio_query = &self.audio_query(text, speaker_id, AudioQueryOptions::from(&options))?;
         self.synthesis(audio_query, speaker_id, SynthesisOptions::from(&options))
     }
+
+    pub fn connect_waves(
+        &self,
+        waves: &[impl AsRef<[u8]>],
+        options: ConnectWavesOptions,
+    ) -> Result<Vec<u8>> {
+        let waves = waves
+            .iter()
+            .map(|wave| Wave::from_wav_bytes(wave.as_ref()))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let (sampling_rate, num_channels) = waves
+            .first()
+            .map(|wave| (wave.sampling_rate(), wave.num_channels()))
+            .unwrap_or((SynthesisEngine::DEFAULT_SAMPLING_RATE, 1));
+        Self::connect_waves_with_options(&waves, sampling_rate, num_channels, options)
+    }
+
+    pub fn synthesis_connected(
+        &mut self,
+        audio_queries: &[(AudioQueryModel, u32)],
+        synthesis_options: SynthesisOptions,
+        connect_options: ConnectWavesOptions,
+    ) -> Result<Vec<u8>> {
+        let waves = audio_queries
+            .iter()
+            .map(|(audio_query, speaker_id)| {
+                let interrogative_upspeak =
+                    self.interrogative_upspeak(*speaker_id, &synthesis_options);
+                self.synthesis_engine.synthesis_wave(
+                    audio_query,
+                    *speaker_id,
+                    interrogative_upspeak.as_ref(),
+                    synthesis_options.pitch_contour.as_ref(),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (sampling_rate, num_channels) = audio_queries
+            .first()
+            .map(|(audio_query, _)| {
+                (
+                    *audio_query.output_sampling_rate(),
+                    if *audio_query.output_stereo() { 2 } else { 1 },
+                )
+            })
+            .unwrap_or((SynthesisEngine::DEFAULT_SAMPLING_RATE, 1));
+        Self::connect_waves_with_options(&waves, sampling_rate, num_channels, connect_options)
+    }
+
+    /// 疑問文の調整が有効なとき、オプション、話者のメタ情報、デフォルト値の順に調整のパラメータを決める
+    fn interrogative_upspeak(
+        &self,
+        speaker_id: u32,
+        options: &SynthesisOptions,
+    ) -> Option<InterrogativeUpspeak> {
+        options.enable_interrogative_upspeak.then(|| {
+            options
+                .interrogative_upspeak
+                .or_else(|| {
+                    self.synthesis_engine
+                        .inference_core()
+                        .interrogative_upspeak(speaker_id)
+                })
+                .unwrap_or_default()
+        })
+    }
+
+    fn connect_waves_with_options(
+        waves: &[Wave],
+        default_sampling_rate: u32,
+        default_num_channels: u16,
+        options: ConnectWavesOptions,
+    ) -> Result<Vec<u8>> {
+        let wave = engine::connect_waves(
+            waves,
+            options
+                .output_sampling_rate
+                .unwrap_or(default_sampling_rate),
+            options.output_num_channels.unwrap_or(default_num_channels),
+            options.gap_length,
+            options.crossfade_length,
+        )?;
+        Ok(wave.to_wav_bytes()?)
+    }
+}
+
+/// 複数の音声を繋げる際のオプション
+#[derive(Default)]
+pub struct ConnectWavesOptions {
+    /// 出力のサンプリングレート。`None`のときは最初の音声に合わせる
+    pub output_sampling_rate: Option<u32>,
+    /// 出力のチャンネル数。`None`のときは最初の音声に合わせる
+    pub output_num_channels: Option<u16>,
+    /// 音声の間に挟む無音の秒数
+    pub gap_length: f32,
+    /// 隣り合う音声を重ねてクロスフェードする秒数。正のときは`gap_length`より優先される
+    pub crossfade_length: f32,
+}
+
+/// 音声合成のセッションのオプション
+pub struct SynthesisSessionOptions {
+    /// 変更のあったアクセント句の前後で、合わせて推論し直すアクセント句の数
+    pub context_margin: usize,
+    /// 推論し直した区間と前回の合成結果を繋ぐクロスフェードの秒数
+    pub crossfade_length: f32,
+}
+
+impl Default for SynthesisSessionOptions {
+    fn default() -> Self {
+        Self {
+            context_margin: 1,
+            crossfade_length: 0.01,
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct AudioQueryOptions {
     pub kana: bool,
+    /// kanaでアクセントの指定がないアクセント句を許容し、アクセント位置を推定する
+    pub lenient_kana: bool,
+    /// AudioQueryに設定する、音声合成の前に適用する話し方の変換
+    pub prosody_preset: Option<ProsodyPreset>,
 }
 
 impl From<&TtsOptions> for AudioQueryOptions {
     fn from(options: &TtsOptions) -> Self {
-        Self { kana: options.kana }
+        Self {
+            kana: options.kana,
+            lenient_kana: options.lenient_kana,
+            prosody_preset: options.prosody_preset,
+        }
+    }
+}
+
+/// AudioQueryを別の話者で推論し直す際のオプション
+#[derive(Default)]
+pub struct RetargetOptions {
+    /// 元の話者ID。指定したときは、固定したモーラの音高を話者ごとの平均の音高の差だけずらす
+    pub source_speaker_id: Option<u32>,
+}
+
+/// 楽譜から歌声を合成する際のオプション
+#[derive(Clone, Copy)]
+pub struct SingOptions {
+    /// 楽譜の音高をずらす半音の数
+    pub key_shift: i32,
+    /// 最初の音符より前の無音の長さ(秒)
+    pub pre_phoneme_length: f32,
+    /// 最後の音符より後の無音の長さ(秒)
+    pub post_phoneme_length: f32,
+}
+
+impl Default for SingOptions {
+    fn default() -> Self {
+        Self {
+            key_shift: 0,
+            pre_phoneme_length: 0.1,
+            post_phoneme_length: 0.1,
+        }
     }
 }
 
@@ -208,23 +672,44 @@ pub struct InitializeOptions {
     pub cpu_num_threads: u16,
     pub load_all_models: bool,
     pub open_jtalk_dict_dir: Option<PathBuf>,
+    /// `open_jtalk_dict_dir`の辞書を初期化時には読み込まず、テキストの解析で初めて必要になったときに読み込む
+    pub load_open_jtalk_dict_lazily: bool,
 }
 
 pub struct SynthesisOptions {
     pub enable_interrogative_upspeak: bool,
+    /// 疑問文の調整のパラメータ。`None`のときは話者のメタ情報、またはデフォルト値を用いる
+    pub interrogative_upspeak: Option<InterrogativeUpspeak>,
+    /// モーラの音高を滑らかに繋いだフレームごとの音高で音声合成する。`SynthesisSystem::V2`のモデルでのみ使える
+    pub pitch_contour: Option<PitchContour>,
+    /// 母音とポーズの長さを伸縮させ、音声をこの長さに合わせる。`synthesis`と`tts`でのみ用いる
+    pub target_duration: Option<TargetDuration>,
 }
 
 impl From<&TtsOptions> for SynthesisOptions {
     fn from(options: &TtsOptions) -> Self {
         Self {
             enable_interrogative_upspeak: options.enable_interrogative_upspeak,
+            interrogative_upspeak: options.interrogative_upspeak,
+            pitch_contour: options.pitch_contour,
+            target_duration: options.target_duration.clone(),
         }
     }
 }
 
 pub struct TtsOptions {
     pub kana: bool,
+    /// kanaでアクセントの指定がないアクセント句を許容し、アクセント位置を推定する
+    pub lenient_kana: bool,
     pub enable_interrogative_upspeak: bool,
+    /// 疑問文の調整のパラメータ。`None`のときは話者のメタ情報、またはデフォルト値を用いる
+    pub interrogative_upspeak: Option<InterrogativeUpspeak>,
+    /// モーラの音高を滑らかに繋いだフレームごとの音高で音声合成する。`SynthesisSystem::V2`のモデルでのみ使える
+    pub pitch_contour: Option<PitchContour>,
+    /// 母音とポーズの長さを伸縮させ、音声をこの長さに合わせる
+    pub target_duration: Option<TargetDuration>,
+    /// 音声合成の前に適用する話し方の変換
+    pub prosody_preset: Option<ProsodyPreset>,
 }
 
 impl Default for TtsOptions {
@@ -232,6 +717,11 @@ impl Default for TtsOptions {
         Self {
             enable_interrogative_upspeak: true,
             kana: Default::default(),
+            lenient_kana: Default::default(),
+            interrogative_upspeak: None,
+            pitch_contour: None,
+            target_duration: None,
+            prosody_preset: None,
         }
     }
 }
@@ -319,6 +809,38 @@ impl InferenceCore {
         }
     }
 
+    pub fn interrogative_upspeak(&self, speaker_id: u32) -> Option<InterrogativeUpspeak> {
+        self.status_option
+            .as_ref()
+            .and_then(|status| status.get_interrogative_upspeak(speaker_id))
+    }
+
+    /// 話者のモデルが、[`PITCH_FRAME_RATE`]のフレームごとの音高でのdecodeに対応しているか
+    pub fn supports_frame_pitch(&self, speaker_id: u32) -> Result<bool> {
+        if !self.initialized {
+            return Err(Error::UninitializedStatus);
+        }
+
+        let status = self
+            .status_option
+            .as_ref()
+            .ok_or(Error::UninitializedStatus)?;
+
+        let library_uuid = status
+            .get_library_uuid_from_speaker_id(speaker_id)
+            .ok_or(Error::InvalidSpeakerId { speaker_id })?;
+
+        let synthesis_system = status
+            .usable_model_map
+            .get(&library_uuid)
+            .ok_or_else(|| Error::InvalidLibraryUuid {
+                library_uuid: library_uuid.clone(),
+            })?
+            .model_config
+            .synthesis_system;
+        Ok(matches!(synthesis_system, SynthesisSystem::V2))
+    }
+
     pub fn predict_pitch_and_duration(
         &mut self,
         phoneme_vector: &[i64],
@@ -375,6 +897,39 @@ impl InferenceCore {
         pitch_vector: &[f32],
         duration_vector: &[f32],
         speaker_id: u32,
+    ) -> Result<Vec<f32>> {
+        self.decode_with_pitch(
+            phoneme_vector,
+            DecoderPitch::Phoneme(pitch_vector),
+            duration_vector,
+            speaker_id,
+        )
+    }
+
+    /// 音素ごとではなく、[`PITCH_FRAME_RATE`]のフレームごとの音高でdecodeする
+    ///
+    /// `SynthesisSystem::V2`のモデルでのみ使える
+    pub fn decode_with_frame_pitch(
+        &mut self,
+        phoneme_vector: &[i64],
+        frame_pitch_vector: &[f32],
+        duration_vector: &[f32],
+        speaker_id: u32,
+    ) -> Result<Vec<f32>> {
+        self.decode_with_pitch(
+            phoneme_vector,
+            DecoderPitch::Frame(frame_pitch_vector),
+            duration_vector,
+            speaker_id,
+        )
+    }
+
+    fn decode_with_pitch(
+        &mut self,
+        phoneme_vector: &[i64],
+        pitch: DecoderPitch<'_>,
+        duration_vector: &[f32],
+        speaker_id: u32,
     ) -> Result<Vec<f32>> {
         if !self.initialized {
             return Err(Error::UninitializedStatus);
@@ -414,6 +969,9 @@ impl InferenceCore {
 
         let embedder_input_tensors: Vec<&mut dyn AnyArray> = match synthesis_system {
             SynthesisSystem::V1 => {
+                let DecoderPitch::Phoneme(pitch_vector) = pitch else {
+                    return Err(FramePitchError::UnsupportedModel { speaker_id }.into());
+                };
                 pitch_vector_array = NdArray::new(
                     ndarray::arr1(pitch_vector)
                         .into_shape([1, pitch_vector.len()])
@@ -467,14 +1025,26 @@ impl InferenceCore {
                 vec![&mut length_regulated_vector_array]
             }
             SynthesisSystem::V2 => {
-                let length_regulated_pitch_vector = status.length_regulator(
-                    phoneme_vector.len(),
-                    pitch_vector,
-                    duration_vector,
-                    93.75, // 48000 / 512 = 93.75
-                    1,
-                    1,
-                );
+                let length_regulated_pitch_vector = match pitch {
+                    DecoderPitch::Phoneme(pitch_vector) => status.length_regulator(
+                        phoneme_vector.len(),
+                        pitch_vector,
+                        duration_vector,
+                        93.75, // 48000 / 512 = 93.75
+                        1,
+                        1,
+                    ),
+                    DecoderPitch::Frame(frame_pitch_vector) => {
+                        if frame_pitch_vector.len() != new_length {
+                            return Err(FramePitchError::FrameCountMismatch {
+                                expected: new_length,
+                                actual: frame_pitch_vector.len(),
+                            }
+                            .into());
+                        }
+                        frame_pitch_vector.to_vec()
+                    }
+                };
 
                 length_regulated_pitch_vector_array = NdArray::new(
                     ndarray::arr1(length_regulated_pitch_vector.as_slice())
@@ -494,6 +1064,15 @@ impl InferenceCore {
     }
 }
 
+/// decoderに入力する音高
+#[derive(Clone, Copy)]
+enum DecoderPitch<'a> {
+    /// 音素ごとの音高
+    Phoneme(&'a [f32]),
+    /// フレームごとの音高
+    Frame(&'a [f32]),
+}
+
 pub static SUPPORTED_DEVICES: Lazy<SupportedDevices> =
     Lazy::new(|| SupportedDevices::get_supported_devices().unwrap());
 
@@ -532,6 +1111,13 @@ pub const fn error_result_to_message(result_code: SharevoxResultCode) -> &'stati
             "入力テキストをAquesTalkライクな読み仮名としてパースすることに失敗しました\0"
         }
         SHAREVOX_RESULT_INVALID_AUDIO_QUERY_ERROR => "無効なaudio_queryです\0",
+        SHAREVOX_RESULT_INVALID_WAVE_ERROR => "無効なwavデータです\0",
+        SHAREVOX_RESULT_INVALID_ACCENT_PHRASE_ERROR => "無効なaccent_phraseです\0",
+        SHAREVOX_RESULT_INVALID_ACCENT_PHRASE_EDIT_ERROR => "アクセント句の編集に失敗しました\0",
+        SHAREVOX_RESULT_PARSE_HTS_LABEL_ERROR => "HTSラベルの解析に失敗しました\0",
+        SHAREVOX_RESULT_INVALID_FRAME_PITCH_ERROR => "無効なフレームごとの音高です\0",
+        SHAREVOX_RESULT_PARSE_SCORE_ERROR => "楽譜の解析に失敗しました\0",
+        SHAREVOX_RESULT_INVALID_TARGET_DURATION_ERROR => "無効な目標の長さです\0",
         SHAREVOX_RESULT_LOAD_LIBRARIES_ERROR => "libraries.jsonの読み込みに失敗しました\0",
         SHAREVOX_RESULT_LOAD_MODEL_CONFIG_ERROR => "model_config.jsonの読み込みに失敗しました\0",
         SHAREVOX_RESULT_INVALID_LIBRARY_UUID_ERROR => "無効なlibrary_uuidです\0",
@@ -911,6 +1497,7 @@ mod tests {
                 0,
                 AudioQueryOptions {
                     kana: input_kana_option,
+                    ..Default::default()
                 },
             )
             .unwrap();
@@ -953,6 +1540,295 @@ mod tests {
         assert_eq!(query.kana(), expected_kana_text);
     }
 
+    #[rstest]
+    fn audio_query_with_kana_works_without_open_jtalk_dict() {
+        let core = VoicevoxCore::new_with_mutex();
+        core.lock()
+            .unwrap()
+            .initialize(
+                Path::new(concat!(env!("CARGO_WORKSPACE_DIR"), "/model/")),
+                InitializeOptions {
+                    acceleration_mode: AccelerationMode::Cpu,
+                    load_all_models: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let result =
+            core.lock()
+                .unwrap()
+                .audio_query("これはテストです", 0, AudioQueryOptions::default());
+        assert!(matches!(result, Err(Error::NotLoadedOpenjtalkDict)));
+
+        let query = core
+            .lock()
+            .unwrap()
+            .audio_query(
+                "コ'レワ/テ_スト'デ_ス",
+                0,
+                AudioQueryOptions {
+                    kana: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(query.kana(), "コ'レワ/テ_スト'デ_ス");
+    }
+
+    #[rstest]
+    fn mora_data_works() {
+        let core = VoicevoxCore::new_with_mutex();
+        core.lock()
+            .unwrap()
+            .initialize(
+                Path::new(concat!(env!("CARGO_WORKSPACE_DIR"), "/model/")),
+                InitializeOptions {
+                    acceleration_mode: AccelerationMode::Cpu,
+                    load_all_models: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let accent_phrases = parse_kana("コ'レワ/テ_スト'デ_ス").unwrap();
+
+        let accent_phrases = core
+            .lock()
+            .unwrap()
+            .mora_length(&accent_phrases, 0)
+            .unwrap();
+        let moras = to_flatten_moras(&accent_phrases);
+        assert!(moras.iter().all(|mora| *mora.vowel_length() > 0.));
+        assert!(moras.iter().all(|mora| *mora.pitch() == 0.));
+
+        let accent_phrases = core.lock().unwrap().mora_pitch(&accent_phrases, 0).unwrap();
+        let moras = to_flatten_moras(&accent_phrases);
+        assert!(moras.iter().any(|mora| *mora.pitch() > 0.));
+
+        let expected = core
+            .lock()
+            .unwrap()
+            .mora_data(&parse_kana("コ'レワ/テ_スト'デ_ス").unwrap(), 0)
+            .unwrap();
+        assert_eq!(accent_phrases, expected);
+    }
+
+    #[rstest]
+    fn retarget_audio_query_keeps_locked_moras() {
+        let core = VoicevoxCore::new_with_mutex();
+        core.lock()
+            .unwrap()
+            .initialize(
+                Path::new(concat!(env!("CARGO_WORKSPACE_DIR"), "/model/")),
+                InitializeOptions {
+                    acceleration_mode: AccelerationMode::Cpu,
+                    load_all_models: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let mut audio_query = core
+            .lock()
+            .unwrap()
+            .audio_query(
+                "コ'レワ/テ_スト'デ_ス",
+                0,
+                AudioQueryOptions {
+                    kana: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        audio_query.set_speed_scale(1.5);
+        let locked_mora = &mut audio_query.accent_phrases_mut()[0].moras_mut()[0];
+        locked_mora.set_vowel_length(0.5);
+        locked_mora.set_pitch(6.);
+        locked_mora.set_is_locked(true);
+
+        let retargeted = core
+            .lock()
+            .unwrap()
+            .retarget_audio_query(&audio_query, 1, RetargetOptions::default())
+            .unwrap();
+        assert_eq!(*retargeted.speed_scale(), 1.5);
+        let mora = &retargeted.accent_phrases()[0].moras()[0];
+        assert_eq!((*mora.vowel_length(), *mora.pitch()), (0.5, 6.));
+        assert!(*mora.is_locked());
+    }
+
+    #[rstest]
+    fn synthesis_with_session_works() {
+        let core = VoicevoxCore::new_with_mutex();
+        core.lock()
+            .unwrap()
+            .initialize(
+                Path::new(concat!(env!("CARGO_WORKSPACE_DIR"), "/model/")),
+                InitializeOptions {
+                    acceleration_mode: AccelerationMode::Cpu,
+                    load_all_models: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let mut audio_query = core
+            .lock()
+            .unwrap()
+            .audio_query(
+                "コ'レワ/テ_スト'デ_ス",
+                0,
+                AudioQueryOptions {
+                    kana: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let mut session = core.lock().unwrap().new_synthesis_session(
+            0,
+            SynthesisOptions::from(&TtsOptions::default()),
+            SynthesisSessionOptions::default(),
+        );
+
+        let wav = core
+            .lock()
+            .unwrap()
+            .synthesis_with_session(&mut session, &audio_query)
+            .unwrap();
+        assert_eq!(
+            core.lock()
+                .unwrap()
+                .synthesis_with_session(&mut session, &audio_query)
+                .unwrap(),
+            wav
+        );
+
+        let mora = &mut audio_query.accent_phrases_mut()[1].moras_mut()[0];
+        mora.set_vowel_length(mora.vowel_length() * 2.);
+        let edited_wav = core
+            .lock()
+            .unwrap()
+            .synthesis_with_session(&mut session, &audio_query)
+            .unwrap();
+        assert!(edited_wav.len() > wav.len());
+    }
+
+    #[rstest]
+    fn mora_data_rejects_invalid_accent_phrases() {
+        let mut accent_phrases = parse_kana("コ'レワ").unwrap();
+        accent_phrases[0].moras_mut()[0].set_vowel_length(-1.);
+
+        let result = VoicevoxCore::new_with_mutex()
+            .lock()
+            .unwrap()
+            .mora_data(&accent_phrases, 0);
+        assert!(matches!(result, Err(Error::InvalidAccentPhrase(_))));
+    }
+
+    #[rstest]
+    fn audio_query_from_labels_works_with_pitches() {
+        let labels = "0 1000000 sil\n1000000 1500000 k\n1500000 2500000 o\n2500000 3500000 N\n3500000 5500000 sil\n";
+        let pitches = [0., 0., 5.5, 5.8, 0.];
+
+        let query = VoicevoxCore::new_with_mutex()
+            .lock()
+            .unwrap()
+            .audio_query_from_labels(labels, 0, Some(&pitches))
+            .unwrap();
+        assert_eq!(*query.pre_phoneme_length(), 0.1);
+        assert_eq!(*query.post_phoneme_length(), 0.2);
+        let moras = query.accent_phrases()[0].moras();
+        assert_eq!(*moras[0].consonant_length(), Some(0.05));
+        assert_eq!(*moras[0].vowel_length(), 0.1);
+        assert_eq!(*moras[0].pitch(), 5.5);
+        assert_eq!(*moras[1].pitch(), 5.8);
+    }
+
+    #[rstest]
+    fn audio_query_from_labels_rejects_invalid_labels() {
+        let result = VoicevoxCore::new_with_mutex()
+            .lock()
+            .unwrap()
+            .audio_query_from_labels("0 1000000 xyz\n", 0, Some(&[0.]));
+        assert!(matches!(result, Err(Error::ParseHtsLabel(_))));
+    }
+
+    #[rstest]
+    fn sing_rejects_score_without_lyrics() {
+        let score = Score::new(vec![ScoreNote {
+            lyric: None,
+            note_number: 60,
+            start: 0.,
+            end: 1.,
+        }]);
+        let result =
+            VoicevoxCore::new_with_mutex()
+                .lock()
+                .unwrap()
+                .sing(&score, 0, SingOptions::default());
+        assert_eq!(result.unwrap_err(), Error::ParseScore(ScoreError::NoLyrics));
+    }
+
+    #[rstest]
+    fn synthesis_with_frame_pitch_rejects_frame_count_mismatch() {
+        let mut query = VoicevoxCore::new_with_mutex()
+            .lock()
+            .unwrap()
+            .audio_query_from_labels(
+                "0 1000000 sil\n1000000 2000000 a\n2000000 3000000 sil\n",
+                0,
+                Some(&[0., 5.5, 0.]),
+            )
+            .unwrap();
+        query.set_speed_scale(2.);
+
+        // 話速を適用した音素の長さ(0.1, 0.05, 0.1)からフレーム数を求める
+        let result = VoicevoxCore::new_with_mutex()
+            .lock()
+            .unwrap()
+            .synthesis_with_frame_pitch(&query, 0, &FramePitch::Frames(vec![5.5; 28]));
+        assert_eq!(
+            result.unwrap_err(),
+            Error::InvalidFramePitch(FramePitchError::FrameCountMismatch {
+                expected: 23,
+                actual: 28,
+            })
+        );
+    }
+
+    #[rstest]
+    #[case(ConnectWavesOptions::default(), 24000, 1, 24000 * 3 / 2)]
+    #[case(
+        ConnectWavesOptions {
+            output_sampling_rate: Some(48000),
+            output_num_channels: Some(2),
+            gap_length: 0.5,
+            ..Default::default()
+        },
+        48000,
+        2,
+        48000 * 2
+    )]
+    fn connect_waves_works(
+        #[case] options: ConnectWavesOptions,
+        #[case] expected_sampling_rate: u32,
+        #[case] expected_num_channels: u16,
+        #[case] expected_num_frames: usize,
+    ) {
+        let waves = [
+            Wave::new(vec![0.; 24000], 24000, 1).to_wav_bytes().unwrap(),
+            Wave::new(vec![0.; 48000], 48000, 2).to_wav_bytes().unwrap(),
+        ];
+        let wav = VoicevoxCore::new_with_mutex()
+            .lock()
+            .unwrap()
+            .connect_waves(&waves, options)
+            .unwrap();
+
+        let wave = Wave::from_wav_bytes(&wav).unwrap();
+        assert_eq!(wave.sampling_rate(), expected_sampling_rate);
+        assert_eq!(wave.num_channels(), expected_num_channels);
+        assert_eq!(wave.num_frames(), expected_num_frames);
+    }
+
     #[rstest]
     fn get_version_works() {
         assert_eq!("0.0.0", VoicevoxCore::get_version());
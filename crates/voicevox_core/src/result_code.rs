@@ -33,6 +33,20 @@ pub enum SharevoxResultCode {
     SHAREVOX_RESULT_PARSE_KANA_ERROR = 12,
     /// 無効なAudioQuery
     SHAREVOX_RESULT_INVALID_AUDIO_QUERY_ERROR = 13,
+    /// 無効なwavデータ
+    SHAREVOX_RESULT_INVALID_WAVE_ERROR = 14,
+    /// 無効なアクセント句
+    SHAREVOX_RESULT_INVALID_ACCENT_PHRASE_ERROR = 15,
+    /// アクセント句の編集に失敗した
+    SHAREVOX_RESULT_INVALID_ACCENT_PHRASE_EDIT_ERROR = 16,
+    /// HTSラベルの解析に失敗した
+    SHAREVOX_RESULT_PARSE_HTS_LABEL_ERROR = 17,
+    /// 無効なフレームごとの音高
+    SHAREVOX_RESULT_INVALID_FRAME_PITCH_ERROR = 18,
+    /// 楽譜の解析に失敗した
+    SHAREVOX_RESULT_PARSE_SCORE_ERROR = 19,
+    /// 無効な目標の長さ
+    SHAREVOX_RESULT_INVALID_TARGET_DURATION_ERROR = 20,
     /// libraries.jsonの読み込みに失敗した
     SHAREVOX_RESULT_LOAD_LIBRARIES_ERROR = 100,
     /// model_config.jsonの読み込みに失敗した
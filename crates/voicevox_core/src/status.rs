@@ -34,6 +34,7 @@ pub struct Status {
     usable_model_data_map: BTreeMap<String, ModelData>,
     pub usable_model_map: BTreeMap<String, Models>,
     pub speaker_id_map: BTreeMap<u64, String>,
+    pub interrogative_upspeak_map: BTreeMap<u64, InterrogativeUpspeak>,
     pub metas_str: CString,
     gaussian_session: Option<Session<'static>>,
 }
@@ -90,6 +91,9 @@ struct Meta {
 struct Style {
     name: String,
     id: u64,
+    /// このスタイルで用いる疑問文の調整のパラメータ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    interrogative_upspeak: Option<InterrogativeUpspeak>,
 }
 
 static ENVIRONMENT: Lazy<Environment> = Lazy::new(|| {
@@ -234,6 +238,7 @@ impl Status {
             usable_model_data_map: BTreeMap::new(),
             usable_model_map: BTreeMap::new(),
             speaker_id_map: BTreeMap::new(),
+            interrogative_upspeak_map: BTreeMap::new(),
             metas_str: CString::default(),
             gaussian_session: None,
         }
@@ -282,6 +287,10 @@ impl Status {
                     style.id = metas_style_id;
                     self.speaker_id_map
                         .insert(metas_style_id, library_uuid.clone());
+                    if let Some(interrogative_upspeak) = style.interrogative_upspeak {
+                        self.interrogative_upspeak_map
+                            .insert(metas_style_id, interrogative_upspeak);
+                    }
                     if let Some(speaker_index) = speaker_index {
                         all_metas[speaker_index].styles.push(style.clone());
                     }
@@ -469,6 +478,12 @@ impl Status {
         self.speaker_id_map.get(&(speaker_id as u64)).cloned()
     }
 
+    pub fn get_interrogative_upspeak(&self, speaker_id: u32) -> Option<InterrogativeUpspeak> {
+        self.interrogative_upspeak_map
+            .get(&(speaker_id as u64))
+            .copied()
+    }
+
     pub fn length_regulator(
         &mut self,
         length: usize,
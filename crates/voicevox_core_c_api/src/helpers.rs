@@ -1,15 +1,35 @@
+use std::cell::Cell;
 use std::fmt::Debug;
 
 use thiserror::Error;
 
 use super::*;
 
+thread_local! {
+    // 別のスレッドの呼び出しで上書きされないよう、スレッドごとに保持する
+    static LAST_KANA_PARSE_ERROR_DETAILS: Cell<Option<SharevoxKanaParseErrorDetails>> =
+        const { Cell::new(None) };
+}
+
+pub(crate) fn last_kana_parse_error_details() -> Option<SharevoxKanaParseErrorDetails> {
+    LAST_KANA_PARSE_ERROR_DETAILS.with(Cell::get)
+}
+
 pub(crate) fn into_result_code_with_error(result: CApiResult<()>) -> SharevoxResultCode {
     if let Err(err) = &result {
         display_error(err);
     }
+    record_kana_parse_error(&result);
     return into_result_code(result);
 
+    fn record_kana_parse_error(result: &CApiResult<()>) {
+        let details = match result {
+            Err(CApiError::RustApi(voicevox_core::Error::ParseKana(err))) => Some(err.into()),
+            _ => None,
+        };
+        LAST_KANA_PARSE_ERROR_DETAILS.with(|last| last.set(details));
+    }
+
     fn display_error(err: &CApiError) {
         eprintln!("Error(Display): {err}");
         eprintln!("Error(Debug): {err:#?}");
@@ -34,16 +54,27 @@ pub(crate) fn into_result_code_with_error(result: CApiResult<()>) -> SharevoxRes
                 SHAREVOX_RESULT_EXTRACT_FULL_CONTEXT_LABEL_ERROR
             }
             Err(RustApi(ParseKana(_))) => SHAREVOX_RESULT_PARSE_KANA_ERROR,
+            Err(RustApi(InvalidWave(_))) => SHAREVOX_RESULT_INVALID_WAVE_ERROR,
+            Err(RustApi(InvalidModel(_))) => SHAREVOX_RESULT_INVALID_AUDIO_QUERY_ERROR,
+            Err(RustApi(InvalidAccentPhrase(_))) => SHAREVOX_RESULT_INVALID_ACCENT_PHRASE_ERROR,
+            Err(RustApi(EditAccentPhrase(_))) => SHAREVOX_RESULT_INVALID_ACCENT_PHRASE_EDIT_ERROR,
+            Err(RustApi(ParseHtsLabel(_))) => SHAREVOX_RESULT_PARSE_HTS_LABEL_ERROR,
+            Err(RustApi(InvalidFramePitch(_))) => SHAREVOX_RESULT_INVALID_FRAME_PITCH_ERROR,
+            Err(RustApi(ParseScore(_))) => SHAREVOX_RESULT_PARSE_SCORE_ERROR,
+            Err(RustApi(InvalidTargetDuration(_))) => SHAREVOX_RESULT_INVALID_TARGET_DURATION_ERROR,
             Err(RustApi(LoadLibraries(_))) => SHAREVOX_RESULT_LOAD_LIBRARIES_ERROR,
             Err(RustApi(LoadModelConfig { .. })) => SHAREVOX_RESULT_LOAD_MODEL_CONFIG_ERROR,
             Err(RustApi(InvalidLibraryUuid { .. })) => SHAREVOX_RESULT_INVALID_LIBRARY_UUID_ERROR,
             Err(InvalidUtf8Input) => SHAREVOX_RESULT_INVALID_UTF8_INPUT_ERROR,
             Err(InvalidAudioQuery(_)) => SHAREVOX_RESULT_INVALID_AUDIO_QUERY_ERROR,
+            Err(InvalidAccentPhrasesJson(_)) => SHAREVOX_RESULT_INVALID_ACCENT_PHRASE_ERROR,
+            Err(InvalidAccentPhraseEditJson(_)) => SHAREVOX_RESULT_INVALID_ACCENT_PHRASE_EDIT_ERROR,
+            Err(NullTargetDurations) => SHAREVOX_RESULT_INVALID_TARGET_DURATION_ERROR,
         }
     }
 }
 
-type CApiResult<T> = std::result::Result<T, CApiError>;
+pub(crate) type CApiResult<T> = std::result::Result<T, CApiError>;
 
 #[derive(Error, Debug)]
 pub(crate) enum CApiError {
@@ -54,6 +85,12 @@ pub(crate) enum CApiError {
     #[allow(dead_code)]
     #[error("無効なAudioQueryです: {0}")]
     InvalidAudioQuery(serde_json::Error),
+    #[error("無効なアクセント句です: {0}")]
+    InvalidAccentPhrasesJson(serde_json::Error),
+    #[error("無効なアクセント句の編集操作です: {0}")]
+    InvalidAccentPhraseEditJson(serde_json::Error),
+    #[error("目標の長さの配列がNULLです")]
+    NullTargetDurations,
 }
 
 #[allow(dead_code)]
@@ -79,11 +116,34 @@ pub(crate) fn create_audio_query(
     Ok(CString::new(audio_query_model_to_json(&audio_query)).expect("should not contain '\\0'"))
 }
 
-#[allow(dead_code)]
-fn audio_query_model_to_json(audio_query_model: &AudioQueryModel) -> String {
+pub(crate) fn replace_accent_phrases(
+    accent_phrases_json: &CStr,
+    speaker_id: u32,
+    method: fn(&mut Internal, &[AccentPhraseModel], u32) -> Result<Vec<AccentPhraseModel>>,
+) -> CApiResult<CString> {
+    let accent_phrases_json = ensure_utf8(accent_phrases_json)?;
+    let accent_phrases: Vec<AccentPhraseModel> =
+        serde_json::from_str(accent_phrases_json).map_err(CApiError::InvalidAccentPhrasesJson)?;
+
+    let accent_phrases = method(&mut lock_internal(), &accent_phrases, speaker_id)?;
+    let json = serde_json::to_string(&accent_phrases).expect("should be always valid");
+    Ok(CString::new(json).expect("should not contain '\\0'"))
+}
+
+pub(crate) fn audio_query_model_to_json(audio_query_model: &AudioQueryModel) -> String {
     serde_json::to_string(audio_query_model).expect("should be always valid")
 }
 
+pub(crate) fn text_analysis_model_to_json(text_analysis_model: &TextAnalysisModel) -> CString {
+    let json = serde_json::to_string(text_analysis_model).expect("should be always valid");
+    CString::new(json).expect("should not contain '\\0'")
+}
+
+pub(crate) fn njd_nodes_to_json(njd_nodes: &[NjdNodeModel]) -> CString {
+    let json = serde_json::to_string(njd_nodes).expect("should be always valid");
+    CString::new(json).expect("should not contain '\\0'")
+}
+
 #[allow(dead_code)]
 pub(crate) unsafe fn write_json_to_ptr(output_ptr: *mut *mut c_char, json: &CStr) {
     let n = json.to_bytes_with_nul().len();
@@ -161,19 +221,277 @@ pub(crate) fn ensure_utf8(s: &CStr) -> CApiResult<&str> {
 
 impl From<voicevox_core::AudioQueryOptions> for SharevoxAudioQueryOptions {
     fn from(options: voicevox_core::AudioQueryOptions) -> Self {
-        Self { kana: options.kana }
+        Self {
+            kana: options.kana,
+            lenient_kana: options.lenient_kana,
+            prosody_preset: options.prosody_preset.into(),
+        }
     }
 }
 impl From<SharevoxAudioQueryOptions> for voicevox_core::AudioQueryOptions {
     fn from(options: SharevoxAudioQueryOptions) -> Self {
-        Self { kana: options.kana }
+        Self {
+            kana: options.kana,
+            lenient_kana: options.lenient_kana,
+            prosody_preset: options.prosody_preset.into(),
+        }
+    }
+}
+
+impl From<Option<voicevox_core::ProsodyPreset>> for SharevoxProsodyPreset {
+    fn from(preset: Option<voicevox_core::ProsodyPreset>) -> Self {
+        use voicevox_core::ProsodyPreset::*;
+        match preset {
+            None => Self::SHAREVOX_PROSODY_PRESET_NONE,
+            Some(Monotone) => Self::SHAREVOX_PROSODY_PRESET_MONOTONE,
+            Some(Whisper) => Self::SHAREVOX_PROSODY_PRESET_WHISPER,
+            Some(Robot) => Self::SHAREVOX_PROSODY_PRESET_ROBOT,
+        }
+    }
+}
+
+impl From<SharevoxProsodyPreset> for Option<voicevox_core::ProsodyPreset> {
+    fn from(preset: SharevoxProsodyPreset) -> Self {
+        use voicevox_core::ProsodyPreset::*;
+        use SharevoxProsodyPreset::*;
+        match preset {
+            SHAREVOX_PROSODY_PRESET_NONE => None,
+            SHAREVOX_PROSODY_PRESET_MONOTONE => Some(Monotone),
+            SHAREVOX_PROSODY_PRESET_WHISPER => Some(Whisper),
+            SHAREVOX_PROSODY_PRESET_ROBOT => Some(Robot),
+        }
+    }
+}
+
+impl From<voicevox_core::UpspeakContour> for SharevoxUpspeakContour {
+    fn from(contour: voicevox_core::UpspeakContour) -> Self {
+        use voicevox_core::UpspeakContour::*;
+        match contour {
+            AppendMora => Self::SHAREVOX_UPSPEAK_CONTOUR_APPEND_MORA,
+            RaiseLastMora => Self::SHAREVOX_UPSPEAK_CONTOUR_RAISE_LAST_MORA,
+        }
+    }
+}
+
+impl From<SharevoxUpspeakContour> for voicevox_core::UpspeakContour {
+    fn from(contour: SharevoxUpspeakContour) -> Self {
+        use SharevoxUpspeakContour::*;
+        match contour {
+            SHAREVOX_UPSPEAK_CONTOUR_APPEND_MORA => Self::AppendMora,
+            SHAREVOX_UPSPEAK_CONTOUR_RAISE_LAST_MORA => Self::RaiseLastMora,
+        }
     }
 }
 
-impl From<SharevoxSynthesisOptions> for voicevox_core::SynthesisOptions {
-    fn from(options: SharevoxSynthesisOptions) -> Self {
+impl From<voicevox_core::InterrogativeUpspeak> for SharevoxInterrogativeUpspeak {
+    fn from(upspeak: voicevox_core::InterrogativeUpspeak) -> Self {
         Self {
-            enable_interrogative_upspeak: options.enable_interrogative_upspeak,
+            contour: upspeak.contour.into(),
+            vowel_length: upspeak.vowel_length,
+            pitch_delta: upspeak.pitch_delta,
+            max_pitch: upspeak.max_pitch,
+        }
+    }
+}
+
+impl From<SharevoxInterrogativeUpspeak> for voicevox_core::InterrogativeUpspeak {
+    fn from(upspeak: SharevoxInterrogativeUpspeak) -> Self {
+        Self {
+            contour: upspeak.contour.into(),
+            vowel_length: upspeak.vowel_length,
+            pitch_delta: upspeak.pitch_delta,
+            max_pitch: upspeak.max_pitch,
+        }
+    }
+}
+
+impl From<voicevox_core::PitchInterpolation> for SharevoxPitchInterpolation {
+    fn from(interpolation: voicevox_core::PitchInterpolation) -> Self {
+        use voicevox_core::PitchInterpolation::*;
+        match interpolation {
+            Linear => Self::SHAREVOX_PITCH_INTERPOLATION_LINEAR,
+            Cosine => Self::SHAREVOX_PITCH_INTERPOLATION_COSINE,
+            Spline => Self::SHAREVOX_PITCH_INTERPOLATION_SPLINE,
+        }
+    }
+}
+
+impl From<SharevoxPitchInterpolation> for voicevox_core::PitchInterpolation {
+    fn from(interpolation: SharevoxPitchInterpolation) -> Self {
+        use SharevoxPitchInterpolation::*;
+        match interpolation {
+            SHAREVOX_PITCH_INTERPOLATION_LINEAR => Self::Linear,
+            SHAREVOX_PITCH_INTERPOLATION_COSINE => Self::Cosine,
+            SHAREVOX_PITCH_INTERPOLATION_SPLINE => Self::Spline,
+        }
+    }
+}
+
+impl From<voicevox_core::PitchContour> for SharevoxPitchContour {
+    fn from(contour: voicevox_core::PitchContour) -> Self {
+        Self {
+            interpolation: contour.interpolation.into(),
+            declination: contour.declination,
+        }
+    }
+}
+
+impl From<SharevoxPitchContour> for voicevox_core::PitchContour {
+    fn from(contour: SharevoxPitchContour) -> Self {
+        Self {
+            interpolation: contour.interpolation.into(),
+            declination: contour.declination,
+        }
+    }
+}
+
+unsafe fn pitch_contour_from_ptr(
+    pitch_contour: *const SharevoxPitchContour,
+) -> Option<voicevox_core::PitchContour> {
+    (!pitch_contour.is_null()).then(|| pitch_contour.read().into())
+}
+
+unsafe fn interrogative_upspeak_from_ptr(
+    interrogative_upspeak: *const SharevoxInterrogativeUpspeak,
+) -> Option<voicevox_core::InterrogativeUpspeak> {
+    (!interrogative_upspeak.is_null()).then(|| interrogative_upspeak.read().into())
+}
+
+impl SharevoxSynthesisOptions {
+    pub(crate) unsafe fn into_options(self) -> voicevox_core::SynthesisOptions {
+        voicevox_core::SynthesisOptions {
+            enable_interrogative_upspeak: self.enable_interrogative_upspeak,
+            interrogative_upspeak: interrogative_upspeak_from_ptr(self.interrogative_upspeak),
+            pitch_contour: pitch_contour_from_ptr(self.pitch_contour),
+            target_duration: target_duration_from_options(
+                self.target_duration,
+                self.target_sentence_durations_length,
+                self.target_sentence_durations,
+            ),
+        }
+    }
+}
+
+unsafe fn target_duration_from_options(
+    target_duration: f32,
+    target_sentence_durations_length: usize,
+    target_sentence_durations: *const f32,
+) -> Option<voicevox_core::TargetDuration> {
+    if !target_sentence_durations.is_null() {
+        let durations =
+            std::slice::from_raw_parts(target_sentence_durations, target_sentence_durations_length);
+        return Some(voicevox_core::TargetDuration::Sentences(durations.to_vec()));
+    }
+    (target_duration > 0.).then_some(voicevox_core::TargetDuration::Total(target_duration))
+}
+
+impl From<voicevox_core::RetargetOptions> for SharevoxRetargetOptions {
+    fn from(options: voicevox_core::RetargetOptions) -> Self {
+        Self {
+            shift_locked_pitch: options.source_speaker_id.is_some(),
+            source_speaker_id: options.source_speaker_id.unwrap_or(0),
+        }
+    }
+}
+
+impl From<SharevoxRetargetOptions> for voicevox_core::RetargetOptions {
+    fn from(options: SharevoxRetargetOptions) -> Self {
+        Self {
+            source_speaker_id: options
+                .shift_locked_pitch
+                .then_some(options.source_speaker_id),
+        }
+    }
+}
+
+impl From<voicevox_core::SingOptions> for SharevoxSingOptions {
+    fn from(options: voicevox_core::SingOptions) -> Self {
+        Self {
+            key_shift: options.key_shift,
+            pre_phoneme_length: options.pre_phoneme_length,
+            post_phoneme_length: options.post_phoneme_length,
+        }
+    }
+}
+
+impl From<SharevoxSingOptions> for voicevox_core::SingOptions {
+    fn from(options: SharevoxSingOptions) -> Self {
+        Self {
+            key_shift: options.key_shift,
+            pre_phoneme_length: options.pre_phoneme_length,
+            post_phoneme_length: options.post_phoneme_length,
+        }
+    }
+}
+
+impl From<voicevox_core::ConnectWavesOptions> for SharevoxConnectWavesOptions {
+    fn from(options: voicevox_core::ConnectWavesOptions) -> Self {
+        Self {
+            output_sampling_rate: options.output_sampling_rate.unwrap_or(0),
+            output_num_channels: options.output_num_channels.unwrap_or(0),
+            gap_length: options.gap_length,
+            crossfade_length: options.crossfade_length,
+        }
+    }
+}
+
+impl From<SharevoxConnectWavesOptions> for voicevox_core::ConnectWavesOptions {
+    fn from(options: SharevoxConnectWavesOptions) -> Self {
+        Self {
+            output_sampling_rate: (options.output_sampling_rate != 0)
+                .then_some(options.output_sampling_rate),
+            output_num_channels: (options.output_num_channels != 0)
+                .then_some(options.output_num_channels),
+            gap_length: options.gap_length,
+            crossfade_length: options.crossfade_length,
+        }
+    }
+}
+
+impl From<voicevox_core::SynthesisSessionOptions> for SharevoxSynthesisSessionOptions {
+    fn from(options: voicevox_core::SynthesisSessionOptions) -> Self {
+        Self {
+            context_margin: options.context_margin,
+            crossfade_length: options.crossfade_length,
+        }
+    }
+}
+
+impl From<SharevoxSynthesisSessionOptions> for voicevox_core::SynthesisSessionOptions {
+    fn from(options: SharevoxSynthesisSessionOptions) -> Self {
+        Self {
+            context_margin: options.context_margin,
+            crossfade_length: options.crossfade_length,
+        }
+    }
+}
+
+impl From<voicevox_core::KanaParseErrorKind> for SharevoxKanaParseErrorKind {
+    fn from(kind: voicevox_core::KanaParseErrorKind) -> Self {
+        use voicevox_core::KanaParseErrorKind::*;
+        match kind {
+            AccentAtBeginning => Self::SHAREVOX_KANA_PARSE_ERROR_ACCENT_AT_BEGINNING,
+            MultipleAccents => Self::SHAREVOX_KANA_PARSE_ERROR_MULTIPLE_ACCENTS,
+            AccentNotFound => Self::SHAREVOX_KANA_PARSE_ERROR_ACCENT_NOT_FOUND,
+            UnknownText => Self::SHAREVOX_KANA_PARSE_ERROR_UNKNOWN_TEXT,
+            EmptyAccentPhrase => Self::SHAREVOX_KANA_PARSE_ERROR_EMPTY_ACCENT_PHRASE,
+            MisplacedInterrogativeMark => {
+                Self::SHAREVOX_KANA_PARSE_ERROR_MISPLACED_INTERROGATIVE_MARK
+            }
+            InvalidPauseLength => Self::SHAREVOX_KANA_PARSE_ERROR_INVALID_PAUSE_LENGTH,
+            UnclosedPauseLength => Self::SHAREVOX_KANA_PARSE_ERROR_UNCLOSED_PAUSE_LENGTH,
+            TooLongAccentPhrase => Self::SHAREVOX_KANA_PARSE_ERROR_TOO_LONG_ACCENT_PHRASE,
+        }
+    }
+}
+
+impl From<&voicevox_core::KanaParseError> for SharevoxKanaParseErrorDetails {
+    fn from(err: &voicevox_core::KanaParseError) -> Self {
+        Self {
+            kind: (*err.kind()).into(),
+            offset: *err.offset(),
+            length: *err.length(),
+            accent_phrase_index: *err.accent_phrase_index(),
         }
     }
 }
@@ -208,6 +526,7 @@ impl Default for SharevoxInitializeOptions {
             cpu_num_threads: options.cpu_num_threads,
             load_all_models: options.load_all_models,
             open_jtalk_dict_dir: null(),
+            load_open_jtalk_dict_lazily: options.load_open_jtalk_dict_lazily,
         }
     }
 }
@@ -222,24 +541,42 @@ impl SharevoxInitializeOptions {
             cpu_num_threads: self.cpu_num_threads,
             load_all_models: self.load_all_models,
             open_jtalk_dict_dir,
+            load_open_jtalk_dict_lazily: self.load_open_jtalk_dict_lazily,
         })
     }
 }
 
-impl From<voicevox_core::TtsOptions> for SharevoxTtsOptions {
-    fn from(options: voicevox_core::TtsOptions) -> Self {
+impl Default for SharevoxTtsOptions {
+    fn default() -> Self {
+        let options = voicevox_core::TtsOptions::default();
         Self {
             kana: options.kana,
+            lenient_kana: options.lenient_kana,
             enable_interrogative_upspeak: options.enable_interrogative_upspeak,
+            interrogative_upspeak: null(),
+            pitch_contour: null(),
+            target_duration: 0.,
+            target_sentence_durations_length: 0,
+            target_sentence_durations: null(),
+            prosody_preset: options.prosody_preset.into(),
         }
     }
 }
 
-impl From<SharevoxTtsOptions> for voicevox_core::TtsOptions {
-    fn from(options: SharevoxTtsOptions) -> Self {
-        Self {
-            kana: options.kana,
-            enable_interrogative_upspeak: options.enable_interrogative_upspeak,
+impl SharevoxTtsOptions {
+    pub(crate) unsafe fn into_options(self) -> voicevox_core::TtsOptions {
+        voicevox_core::TtsOptions {
+            kana: self.kana,
+            lenient_kana: self.lenient_kana,
+            enable_interrogative_upspeak: self.enable_interrogative_upspeak,
+            interrogative_upspeak: interrogative_upspeak_from_ptr(self.interrogative_upspeak),
+            pitch_contour: pitch_contour_from_ptr(self.pitch_contour),
+            target_duration: target_duration_from_options(
+                self.target_duration,
+                self.target_sentence_durations_length,
+                self.target_sentence_durations,
+            ),
+            prosody_preset: self.prosody_preset.into(),
         }
     }
 }
@@ -249,6 +586,11 @@ impl Default for SharevoxSynthesisOptions {
         let options = voicevox_core::TtsOptions::default();
         Self {
             enable_interrogative_upspeak: options.enable_interrogative_upspeak,
+            interrogative_upspeak: null(),
+            pitch_contour: null(),
+            target_duration: 0.,
+            target_sentence_durations_length: 0,
+            target_sentence_durations: null(),
         }
     }
 }
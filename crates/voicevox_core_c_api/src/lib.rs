@@ -12,8 +12,14 @@ use std::os::raw::c_char;
 use std::ptr::null;
 use std::sync::{Mutex, MutexGuard};
 use tracing_subscriber::EnvFilter;
+use voicevox_core::AccentPhraseModel;
 use voicevox_core::AudioQueryModel;
+use voicevox_core::FramePitch;
+use voicevox_core::NjdNodeModel;
 use voicevox_core::Result;
+use voicevox_core::Score;
+use voicevox_core::TargetDuration;
+use voicevox_core::TextAnalysisModel;
 use voicevox_core::VoicevoxCore;
 
 #[cfg(test)]
@@ -91,6 +97,8 @@ pub struct SharevoxInitializeOptions {
     load_all_models: bool,
     /// open_jtalkの辞書ディレクトリ
     open_jtalk_dict_dir: *const c_char,
+    /// open_jtalk_dict_dirの辞書を初期化時には読み込まず、テキストの解析で初めて必要になったときに読み込む
+    load_open_jtalk_dict_lazily: bool,
 }
 
 /// デフォルトの初期化オプションを生成する
@@ -138,6 +146,31 @@ pub extern "C" fn sharevox_load_model(speaker_id: u32) -> SharevoxResultCode {
     into_result_code_with_error(lock_internal().load_model(speaker_id).map_err(Into::into))
 }
 
+/// open_jtalkの辞書を読み込む
+/// kanaからの音声合成には辞書は不要なので、初期化時に辞書を指定しなかった場合でも後から読み込める
+/// @param [in] open_jtalk_dict_dir open_jtalkの辞書ディレクトリ
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param open_jtalk_dict_dir NUL-terminatedな文字列を指す、有効なポインタであること
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_load_openjtalk_dict(
+    open_jtalk_dict_dir: *const c_char,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let open_jtalk_dict_dir = ensure_utf8(CStr::from_ptr(open_jtalk_dict_dir))?.as_ref();
+        lock_internal().load_openjtalk_dict(open_jtalk_dict_dir)?;
+        Ok(())
+    })())
+}
+
+/// open_jtalkの辞書が読み込まれているか判定する
+/// @return 辞書が読み込まれているのであればtrue、そうでないならfalse
+#[no_mangle]
+pub extern "C" fn sharevox_is_openjtalk_dict_loaded() -> bool {
+    lock_internal().is_openjtalk_dict_loaded()
+}
+
 /// ハードウェアアクセラレーションがGPUモードか判定する
 /// @return GPUモードならtrue、そうでないならfalse
 #[no_mangle]
@@ -279,11 +312,71 @@ pub unsafe extern "C" fn sharevox_decode_data_free(decode_data: *mut f32) {
     libc::free(decode_data as *mut c_void);
 }
 
+/// フレームごとの音高でdecodeを実行する
+/// SynthesisSystem が V2 のモデルでのみ使える
+/// @param [in] length phoneme_vector, duration_vector のデータ長
+/// @param [in] phoneme_vector 音素データ
+/// @param [in] frame_pitch_length frame_pitch_vector のデータ長。duration_vector から求まるフレーム数と同じでなければならない
+/// @param [in] frame_pitch_vector 1秒あたり93.75フレームのピッチデータ
+/// @param [in] duration_vector 音素長データ
+/// @param [in] speaker_id 話者ID
+/// @param [out] output_decode_data_length 出力先データのサイズ
+/// @param [out] output_decode_data データ出力先
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param phoneme_vector 必ず length の長さだけデータがある状態で渡すこと
+/// @param frame_pitch_vector 必ず frame_pitch_length の長さだけデータがある状態で渡すこと
+/// @param duration_vector 必ず length の長さだけデータがある状態で渡すこと
+/// @param output_decode_data_length uintptr_t 分のメモリ領域が割り当てられていること
+/// @param output_decode_data 成功後にメモリ領域が割り当てられるので ::sharevox_decode_data_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_decode_with_frame_pitch(
+    length: usize,
+    phoneme_vector: *mut i64,
+    frame_pitch_length: usize,
+    frame_pitch_vector: *mut f32,
+    duration_vector: *mut f32,
+    speaker_id: u32,
+    output_decode_data_length: *mut usize,
+    output_decode_data: *mut *mut f32,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let output_vec = lock_internal().decode_with_frame_pitch(
+            std::slice::from_raw_parts_mut(phoneme_vector, length),
+            std::slice::from_raw_parts_mut(frame_pitch_vector, frame_pitch_length),
+            std::slice::from_raw_parts_mut(duration_vector, length),
+            speaker_id,
+        )?;
+        write_decode_to_ptr(output_decode_data, output_decode_data_length, &output_vec);
+        Ok(())
+    })())
+}
+
+/// 音声合成の前にアクセント句に適用する話し方の変換
+#[repr(i32)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub enum SharevoxProsodyPreset {
+    /// 変換しない
+    SHAREVOX_PROSODY_PRESET_NONE = 0,
+    /// 有声のモーラの音高を全て平均の音高にし、抑揚をなくす
+    SHAREVOX_PROSODY_PRESET_MONOTONE = 1,
+    /// 全てのモーラを無声にし、ささやき声にする
+    SHAREVOX_PROSODY_PRESET_WHISPER = 2,
+    /// 有声のモーラの音高を全て、平均の音高に最も近い平均律の音にする
+    SHAREVOX_PROSODY_PRESET_ROBOT = 3,
+}
+
 /// Audio query のオプション
 #[repr(C)]
 pub struct SharevoxAudioQueryOptions {
     /// aquestalk形式のkanaとしてテキストを解釈する
     kana: bool,
+    /// kanaでアクセントの指定がないアクセント句を許容し、アクセント位置を推定する
+    lenient_kana: bool,
+    /// AudioQuery に設定する、音声合成の前に適用する話し方の変換
+    prosody_preset: SharevoxProsodyPreset,
 }
 
 /// デフォルトの AudioQuery のオプションを生成する
@@ -318,11 +411,290 @@ pub unsafe extern "C" fn sharevox_audio_query(
     })())
 }
 
+/// 音声合成モデルを使わずに、テキストの読みとアクセントを解析する
+/// 解析結果のアクセント句の音素の長さと音高は全て0となる
+/// @param [in] text テキスト
+/// @param [out] output_text_analysis_json 解析結果のアクセント句とkanaを json でフォーマットしたもの
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param text null終端文字列であること
+/// @param output_text_analysis_json 自動でheapメモリが割り当てられるので ::sharevox_text_analysis_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_analyze_text(
+    text: *const c_char,
+    output_text_analysis_json: *mut *mut c_char,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let text = ensure_utf8(CStr::from_ptr(text))?;
+        let text_analysis = lock_internal().analyze_text(text)?;
+        write_json_to_ptr(
+            output_text_analysis_json,
+            &text_analysis_model_to_json(&text_analysis),
+        );
+        Ok(())
+    })())
+}
+
+/// テキストをOpenJTalkで解析し、NJDのノードを返す
+/// 各ノードは表層形、品詞、読み、発音、アクセント型、モーラ数、前の単語と同じアクセント句にまとめられるかを持つ
+/// @param [in] text テキスト
+/// @param [out] output_njd_nodes_json NJDのノードの配列を json でフォーマットしたもの
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param text null終端文字列であること
+/// @param output_njd_nodes_json 自動でheapメモリが割り当てられるので ::sharevox_njd_nodes_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_extract_njd_nodes(
+    text: *const c_char,
+    output_njd_nodes_json: *mut *mut c_char,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let text = ensure_utf8(CStr::from_ptr(text))?;
+        let njd_nodes = lock_internal().extract_njd_nodes(text)?;
+        write_json_to_ptr(output_njd_nodes_json, &njd_nodes_to_json(&njd_nodes));
+        Ok(())
+    })())
+}
+
+/// アクセント句の音素の長さを、指定した話者で推論し直す
+/// @param [in] accent_phrases_json jsonフォーマットされたアクセント句の配列
+/// @param [in] speaker_id 話者ID
+/// @param [out] output_accent_phrases_json 音素の長さを推論し直したアクセント句の配列を json でフォーマットしたもの
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param accent_phrases_json null終端文字列であること
+/// @param output_accent_phrases_json 自動でheapメモリが割り当てられるので ::sharevox_accent_phrases_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_mora_length(
+    accent_phrases_json: *const c_char,
+    speaker_id: u32,
+    output_accent_phrases_json: *mut *mut c_char,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let accent_phrases_json = CStr::from_ptr(accent_phrases_json);
+        let accent_phrases =
+            &replace_accent_phrases(accent_phrases_json, speaker_id, Internal::mora_length)?;
+        write_json_to_ptr(output_accent_phrases_json, accent_phrases);
+        Ok(())
+    })())
+}
+
+/// アクセント句の音高を、指定した話者で推論し直す
+/// @param [in] accent_phrases_json jsonフォーマットされたアクセント句の配列
+/// @param [in] speaker_id 話者ID
+/// @param [out] output_accent_phrases_json 音高を推論し直したアクセント句の配列を json でフォーマットしたもの
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param accent_phrases_json null終端文字列であること
+/// @param output_accent_phrases_json 自動でheapメモリが割り当てられるので ::sharevox_accent_phrases_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_mora_pitch(
+    accent_phrases_json: *const c_char,
+    speaker_id: u32,
+    output_accent_phrases_json: *mut *mut c_char,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let accent_phrases_json = CStr::from_ptr(accent_phrases_json);
+        let accent_phrases =
+            &replace_accent_phrases(accent_phrases_json, speaker_id, Internal::mora_pitch)?;
+        write_json_to_ptr(output_accent_phrases_json, accent_phrases);
+        Ok(())
+    })())
+}
+
+/// アクセント句の音素の長さと音高を、指定した話者で推論し直す
+/// @param [in] accent_phrases_json jsonフォーマットされたアクセント句の配列
+/// @param [in] speaker_id 話者ID
+/// @param [out] output_accent_phrases_json 音素の長さと音高を推論し直したアクセント句の配列を json でフォーマットしたもの
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param accent_phrases_json null終端文字列であること
+/// @param output_accent_phrases_json 自動でheapメモリが割り当てられるので ::sharevox_accent_phrases_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_mora_data(
+    accent_phrases_json: *const c_char,
+    speaker_id: u32,
+    output_accent_phrases_json: *mut *mut c_char,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let accent_phrases_json = CStr::from_ptr(accent_phrases_json);
+        let accent_phrases =
+            &replace_accent_phrases(accent_phrases_json, speaker_id, Internal::mora_data)?;
+        write_json_to_ptr(output_accent_phrases_json, accent_phrases);
+        Ok(())
+    })())
+}
+
+/// アクセント句の配列を編集し、編集したアクセント句の音素の長さと音高を指定した話者で推論し直す
+/// 推論は編集の影響を受けるアクセント句と前後の数個のアクセント句だけで行い、編集の影響を受けないアクセント句の値は変更しない
+/// @param [in] accent_phrases_json jsonフォーマットされたアクセント句の配列
+/// @param [in] edit_json jsonフォーマットされた編集操作
+/// @param [in] speaker_id 話者ID
+/// @param [out] output_accent_phrases_json 編集したアクセント句の配列を json でフォーマットしたもの
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param accent_phrases_json null終端文字列であること
+/// @param edit_json null終端文字列であること
+/// @param output_accent_phrases_json 自動でheapメモリが割り当てられるので ::sharevox_accent_phrases_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_edit_accent_phrases(
+    accent_phrases_json: *const c_char,
+    edit_json: *const c_char,
+    speaker_id: u32,
+    output_accent_phrases_json: *mut *mut c_char,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let accent_phrases_json = ensure_utf8(CStr::from_ptr(accent_phrases_json))?;
+        let accent_phrases: Vec<AccentPhraseModel> = serde_json::from_str(accent_phrases_json)
+            .map_err(CApiError::InvalidAccentPhrasesJson)?;
+        let edit_json = ensure_utf8(CStr::from_ptr(edit_json))?;
+        let edit =
+            serde_json::from_str(edit_json).map_err(CApiError::InvalidAccentPhraseEditJson)?;
+        let accent_phrases =
+            lock_internal().edit_accent_phrases(&accent_phrases, &edit, speaker_id)?;
+        let accent_phrases =
+            CString::new(serde_json::to_string(&accent_phrases).expect("should be always valid"))
+                .expect("should not contain '\\0'");
+        write_json_to_ptr(output_accent_phrases_json, &accent_phrases);
+        Ok(())
+    })())
+}
+
+/// AudioQuery を別の話者で推論し直す際のオプション
+#[repr(C)]
+pub struct SharevoxRetargetOptions {
+    /// 固定したモーラの音高を、話者ごとの平均の音高の差だけずらす
+    shift_locked_pitch: bool,
+    /// 元の話者ID
+    /// shift_locked_pitch が true のときに用いる
+    source_speaker_id: u32,
+}
+
+/// デフォルトの AudioQuery を別の話者で推論し直す際のオプションを生成する
+/// @return デフォルト値が設定された AudioQuery を別の話者で推論し直す際のオプション
+#[no_mangle]
+pub extern "C" fn sharevox_make_default_retarget_options() -> SharevoxRetargetOptions {
+    voicevox_core::RetargetOptions::default().into()
+}
+
+/// AudioQuery の音素の長さと音高を別の話者で推論し直す
+/// 固定したモーラの値と、話速などの AudioQuery 全体の値は変更しない
+/// @param [in] audio_query_json jsonフォーマットされた AudioQuery
+/// @param [in] speaker_id 推論し直す話者ID
+/// @param [in] options AudioQuery を別の話者で推論し直す際のオプション
+/// @param [out] output_audio_query_json 推論し直した AudioQuery を json でフォーマットしたもの
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param audio_query_json null終端文字列であること
+/// @param output_audio_query_json 自動でheapメモリが割り当てられるので ::sharevox_audio_query_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_retarget_audio_query(
+    audio_query_json: *const c_char,
+    speaker_id: u32,
+    options: SharevoxRetargetOptions,
+    output_audio_query_json: *mut *mut c_char,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let audio_query_json = ensure_utf8(CStr::from_ptr(audio_query_json))?;
+        let audio_query =
+            &serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
+        let audio_query =
+            lock_internal().retarget_audio_query(audio_query, speaker_id, options.into())?;
+        let audio_query = CString::new(audio_query_model_to_json(&audio_query))
+            .expect("should not contain '\\0'");
+        write_json_to_ptr(output_audio_query_json, &audio_query);
+        Ok(())
+    })())
+}
+
+/// 疑問文の語尾の抑揚の付け方
+#[repr(i32)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub enum SharevoxUpspeakContour {
+    /// 最後のモーラの母音を繰り返したモーラを追加し、その音高を上げる
+    SHAREVOX_UPSPEAK_CONTOUR_APPEND_MORA = 0,
+    /// モーラを追加せずに、最後のモーラの音高を上げる
+    SHAREVOX_UPSPEAK_CONTOUR_RAISE_LAST_MORA = 1,
+}
+
+/// 疑問文の調整のパラメータ
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SharevoxInterrogativeUpspeak {
+    /// 語尾の抑揚の付け方
+    contour: SharevoxUpspeakContour,
+    /// 語尾の母音の長さ
+    vowel_length: f32,
+    /// 語尾の音高に加える値
+    pitch_delta: f32,
+    /// 語尾の音高の上限
+    max_pitch: f32,
+}
+
+/// デフォルトの疑問文の調整のパラメータを生成する
+/// @return デフォルト値が設定された疑問文の調整のパラメータ
+#[no_mangle]
+pub extern "C" fn sharevox_make_default_interrogative_upspeak() -> SharevoxInterrogativeUpspeak {
+    voicevox_core::InterrogativeUpspeak::default().into()
+}
+
+/// モーラの音高の間の補間の方法
+#[repr(i32)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub enum SharevoxPitchInterpolation {
+    /// 線形補間
+    SHAREVOX_PITCH_INTERPOLATION_LINEAR = 0,
+    /// 前後のモーラの音高の間をコサイン曲線で繋ぐ
+    SHAREVOX_PITCH_INTERPOLATION_COSINE = 1,
+    /// 前後のモーラの音高の傾きを用いた3次エルミートスプライン
+    SHAREVOX_PITCH_INTERPOLATION_SPLINE = 2,
+}
+
+/// モーラの音高を滑らかに繋いだフレームごとの音高の作り方
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SharevoxPitchContour {
+    /// モーラの音高の間の補間の方法
+    interpolation: SharevoxPitchInterpolation,
+    /// 最初の有声のモーラから1秒あたりに下げる音高。下げた音高は約30Hzより低くしない
+    declination: f32,
+}
+
+/// デフォルトのモーラの音高を滑らかに繋いだフレームごとの音高の作り方を生成する
+/// @return デフォルト値が設定されたモーラの音高を滑らかに繋いだフレームごとの音高の作り方
+#[no_mangle]
+pub extern "C" fn sharevox_make_default_pitch_contour() -> SharevoxPitchContour {
+    voicevox_core::PitchContour::default().into()
+}
+
 /// `sharevox_synthesis` のオプション
 #[repr(C)]
 pub struct SharevoxSynthesisOptions {
     /// 疑問文の調整を有効にする
     enable_interrogative_upspeak: bool,
+    /// 疑問文の調整のパラメータ
+    /// NULLのときは話者のメタ情報、またはデフォルト値が用いられる
+    interrogative_upspeak: *const SharevoxInterrogativeUpspeak,
+    /// モーラの音高を滑らかに繋いだフレームごとの音高で音声合成する
+    /// NULLのときは音素ごとの音高を用いる。SynthesisSystem が V2 のモデルでのみ使える
+    pitch_contour: *const SharevoxPitchContour,
+    /// 母音とポーズの長さを伸縮させ、音声全体をこの秒数に合わせる
+    /// 0以下のときは合わせない
+    target_duration: f32,
+    /// target_sentence_durations のデータ長
+    target_sentence_durations_length: usize,
+    /// 母音とポーズの長さを伸縮させ、文ごとの長さをこの秒数の配列に合わせる
+    /// NULLでないときは target_duration より優先される
+    target_sentence_durations: *const f32,
 }
 
 /// デフォルトの `sharevox_synthesis` のオプションを生成する
@@ -341,6 +713,9 @@ pub extern "C" fn sharevox_make_default_synthesis_options() -> SharevoxSynthesis
 /// @return 結果コード #SharevoxResultCode
 ///
 /// # Safety
+/// @param options.interrogative_upspeak NULLでないときは有効な SharevoxInterrogativeUpspeak を指していること
+/// @param options.pitch_contour NULLでないときは有効な SharevoxPitchContour を指していること
+/// @param options.target_sentence_durations NULLでないときは必ず options.target_sentence_durations_length の長さだけデータがある状態で渡すこと
 /// @param output_wav_length 出力先の領域が確保された状態でpointerに渡されていること
 /// @param output_wav 自動で output_wav_length 分のデータが割り当てられるので ::sharevox_wav_free で解放する必要がある
 #[no_mangle]
@@ -357,7 +732,276 @@ pub unsafe extern "C" fn sharevox_synthesis(
             .map_err(|_| CApiError::InvalidUtf8Input)?;
         let audio_query =
             &serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
-        let wav = &lock_internal().synthesis(audio_query, speaker_id, options.into())?;
+        let wav = &lock_internal().synthesis(audio_query, speaker_id, options.into_options())?;
+        write_wav_to_ptr(output_wav, output_wav_length, wav);
+        Ok(())
+    })())
+}
+
+/// AudioQuery の母音とポーズの長さを伸縮させ、音声合成したときの長さを目標の長さに合わせる
+/// 子音の長さと先頭と末尾の無音の長さ、固定したモーラの長さは変更しない
+/// @param [in] audio_query_json jsonフォーマットされた AudioQuery
+/// @param [in] speaker_id 話者ID
+/// @param [in] target_durations_length target_durations のデータ長
+/// @param [in] target_durations 目標の長さ(秒)の配列
+/// @param [in] per_sentence true のときは target_durations を文ごとの長さとし、false のときは target_durations の最初の値を音声全体の長さとする
+/// @param [in] options AudioQueryから音声合成オプション。疑問文の調整だけを用いる
+/// @param [out] output_audio_query_json 長さを合わせた AudioQuery を json でフォーマットしたもの
+/// @param [out] output_duration 長さを合わせた AudioQuery から音声合成したときの、音声全体の長さ(秒)
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param audio_query_json null終端文字列であること
+/// @param target_durations NULLでなく、必ず target_durations_length の長さだけデータがある状態で渡すこと
+/// @param options.interrogative_upspeak NULLでないときは有効な SharevoxInterrogativeUpspeak を指していること
+/// @param output_audio_query_json 自動でheapメモリが割り当てられるので ::sharevox_audio_query_json_free で解放する必要がある
+/// @param output_duration 出力先の領域が確保された状態でpointerに渡されていること
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn sharevox_fit_duration(
+    audio_query_json: *const c_char,
+    speaker_id: u32,
+    target_durations_length: usize,
+    target_durations: *const f32,
+    per_sentence: bool,
+    options: SharevoxSynthesisOptions,
+    output_audio_query_json: *mut *mut c_char,
+    output_duration: *mut f32,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let audio_query_json = ensure_utf8(CStr::from_ptr(audio_query_json))?;
+        let audio_query =
+            &serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
+        if target_durations.is_null() {
+            return Err(CApiError::NullTargetDurations);
+        }
+        let target_durations =
+            std::slice::from_raw_parts(target_durations, target_durations_length);
+        let target_duration = if per_sentence {
+            TargetDuration::Sentences(target_durations.to_vec())
+        } else {
+            TargetDuration::Total(target_durations.first().copied().unwrap_or_default())
+        };
+        let fit = lock_internal().fit_duration(
+            audio_query,
+            speaker_id,
+            &target_duration,
+            &options.into_options(),
+        )?;
+        let audio_query = CString::new(audio_query_model_to_json(&fit.audio_query))
+            .expect("should not contain '\\0'");
+        write_json_to_ptr(output_audio_query_json, &audio_query);
+        output_duration.write(fit.duration);
+        Ok(())
+    })())
+}
+
+/// 同じ話者で AudioQuery を繰り返し音声合成する際に、変更のない区間の合成結果を再利用するセッション
+/// ::sharevox_synthesis_session_new で作り、 ::sharevox_synthesis_session_delete で破棄する
+pub struct SharevoxSynthesisSession {
+    inner: voicevox_core::SynthesisSession,
+}
+
+/// 音声合成のセッションのオプション
+#[repr(C)]
+pub struct SharevoxSynthesisSessionOptions {
+    /// 変更のあったアクセント句の前後で、合わせて推論し直すアクセント句の数
+    context_margin: usize,
+    /// 推論し直した区間と前回の合成結果を繋ぐクロスフェードの秒数
+    crossfade_length: f32,
+}
+
+/// デフォルトの音声合成のセッションのオプションを生成する
+/// @return デフォルト値が設定された音声合成のセッションのオプション
+#[no_mangle]
+pub extern "C" fn sharevox_make_default_synthesis_session_options(
+) -> SharevoxSynthesisSessionOptions {
+    voicevox_core::SynthesisSessionOptions::default().into()
+}
+
+/// 変更のない区間の合成結果を再利用して音声合成するセッションを作る
+/// options.pitch_contour と AudioQuery のビブラートは、 ::sharevox_synthesis と同じくフレームごとの音高で音声合成する。options.target_duration は用いない
+/// @param [in] speaker_id 話者ID
+/// @param [in] options AudioQueryから音声合成オプション
+/// @param [in] session_options 音声合成のセッションのオプション
+/// @param [out] output_session 作ったセッションの出力先
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param options.interrogative_upspeak NULLでないときは有効な SharevoxInterrogativeUpspeak を指していること
+/// @param options.pitch_contour NULLでないときは有効な SharevoxPitchContour を指していること
+/// @param output_session 自動でheapメモリが割り当てられるので ::sharevox_synthesis_session_delete で破棄する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_synthesis_session_new(
+    speaker_id: u32,
+    options: SharevoxSynthesisOptions,
+    session_options: SharevoxSynthesisSessionOptions,
+    output_session: *mut *mut SharevoxSynthesisSession,
+) -> SharevoxResultCode {
+    let inner = lock_internal().new_synthesis_session(
+        speaker_id,
+        options.into_options(),
+        session_options.into(),
+    );
+    output_session.write(Box::into_raw(Box::new(SharevoxSynthesisSession { inner })));
+    into_result_code_with_error(Ok(()))
+}
+
+/// セッションの前回の音声合成から変更のあったアクセント句だけを推論し直して音声合成する
+/// @param [in] session ::sharevox_synthesis_session_new で作ったセッション
+/// @param [in] audio_query_json jsonフォーマットされた AudioQuery
+/// @param [out] output_wav_length 出力する wav データのサイズ
+/// @param [out] output_wav wav データの出力先
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param session ::sharevox_synthesis_session_new で作り、破棄していないセッションであること
+/// @param audio_query_json null終端文字列であること
+/// @param output_wav_length 出力先の領域が確保された状態でpointerに渡されていること
+/// @param output_wav 自動で output_wav_length 分のデータが割り当てられるので ::sharevox_wav_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_synthesis_with_session(
+    session: *mut SharevoxSynthesisSession,
+    audio_query_json: *const c_char,
+    output_wav_length: *mut usize,
+    output_wav: *mut *mut u8,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let audio_query_json = ensure_utf8(CStr::from_ptr(audio_query_json))?;
+        let audio_query =
+            &serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
+        let wav = &lock_internal().synthesis_with_session(&mut (*session).inner, audio_query)?;
+        write_wav_to_ptr(output_wav, output_wav_length, wav);
+        Ok(())
+    })())
+}
+
+/// 音声合成のセッションを破棄する
+/// @param [in] session 破棄するセッション
+///
+/// # Safety
+/// @param session ::sharevox_synthesis_session_new で作ったセッションであること。破棄した後は使えない
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_synthesis_session_delete(session: *mut SharevoxSynthesisSession) {
+    if !session.is_null() {
+        drop(Box::from_raw(session));
+    }
+}
+
+/// AudioQuery の音素の長さと、フレームごとの音高から音声合成する
+/// AudioQuery のモーラの音高と抑揚は用いず、音高の調整だけをフレームごとの音高に適用する
+/// @param [in] audio_query_json jsonフォーマットされた AudioQuery
+/// @param [in] speaker_id  話者ID
+/// @param [in] frame_pitch_length frame_pitch のデータ長。AudioQuery の音素の長さから求まるフレーム数と同じでなければならない
+/// @param [in] frame_pitch 1秒あたり93.75フレームのピッチデータ
+/// @param [out] output_wav_length 出力する wav データのサイズ
+/// @param [out] output_wav wav データの出力先
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param frame_pitch 必ず frame_pitch_length の長さだけデータがある状態で渡すこと
+/// @param output_wav_length 出力先の領域が確保された状態でpointerに渡されていること
+/// @param output_wav 自動で output_wav_length 分のデータが割り当てられるので ::sharevox_wav_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_synthesis_with_frame_pitch(
+    audio_query_json: *const c_char,
+    speaker_id: u32,
+    frame_pitch_length: usize,
+    frame_pitch: *const f32,
+    output_wav_length: *mut usize,
+    output_wav: *mut *mut u8,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let audio_query_json = CStr::from_ptr(audio_query_json)
+            .to_str()
+            .map_err(|_| CApiError::InvalidUtf8Input)?;
+        let audio_query =
+            &serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
+        let frame_pitch = FramePitch::Frames(
+            std::slice::from_raw_parts(frame_pitch, frame_pitch_length).to_vec(),
+        );
+        let wav =
+            &lock_internal().synthesis_with_frame_pitch(audio_query, speaker_id, &frame_pitch)?;
+        write_wav_to_ptr(output_wav, output_wav_length, wav);
+        Ok(())
+    })())
+}
+
+/// 楽譜から歌声を合成する際のオプション
+#[repr(C)]
+pub struct SharevoxSingOptions {
+    /// 楽譜の音高をずらす半音の数
+    key_shift: i32,
+    /// 最初の音符より前の無音の長さ(秒)
+    pre_phoneme_length: f32,
+    /// 最後の音符より後の無音の長さ(秒)
+    post_phoneme_length: f32,
+}
+
+/// デフォルトの楽譜から歌声を合成する際のオプションを生成する
+/// @return デフォルト値が設定された楽譜から歌声を合成する際のオプション
+#[no_mangle]
+pub extern "C" fn sharevox_make_default_sing_options() -> SharevoxSingOptions {
+    voicevox_core::SingOptions::default().into()
+}
+
+/// Standard MIDI File の楽譜を歌う音声を合成する
+/// 歌詞のメタイベントを含む最初のトラックを用いる。歌詞はUTF-8のかなでなければならない
+/// @param [in] midi_length midi のデータ長
+/// @param [in] midi Standard MIDI File のデータ
+/// @param [in] speaker_id 話者ID
+/// @param [in] options 楽譜から歌声を合成する際のオプション
+/// @param [out] output_wav_length 出力する wav データのサイズ
+/// @param [out] output_wav wav データの出力先
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param midi 必ず midi_length の長さだけデータがある状態で渡すこと
+/// @param output_wav_length 出力先の領域が確保された状態でpointerに渡されていること
+/// @param output_wav 自動で output_wav_length 分のデータが割り当てられるので ::sharevox_wav_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_sing_midi(
+    midi_length: usize,
+    midi: *const u8,
+    speaker_id: u32,
+    options: SharevoxSingOptions,
+    output_wav_length: *mut usize,
+    output_wav: *mut *mut u8,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let midi = std::slice::from_raw_parts(midi, midi_length);
+        let score = Score::from_midi(midi).map_err(voicevox_core::Error::from)?;
+        let wav = &lock_internal().sing(&score, speaker_id, options.into())?;
+        write_wav_to_ptr(output_wav, output_wav_length, wav);
+        Ok(())
+    })())
+}
+
+/// partwise形式の MusicXML の楽譜を歌う音声を合成する
+/// 最初のパートの最初の声部を用いる。歌詞はかなでなければならない
+/// @param [in] music_xml MusicXML の文字列
+/// @param [in] speaker_id 話者ID
+/// @param [in] options 楽譜から歌声を合成する際のオプション
+/// @param [out] output_wav_length 出力する wav データのサイズ
+/// @param [out] output_wav wav データの出力先
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param music_xml null終端文字列であること
+/// @param output_wav_length 出力先の領域が確保された状態でpointerに渡されていること
+/// @param output_wav 自動で output_wav_length 分のデータが割り当てられるので ::sharevox_wav_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_sing_music_xml(
+    music_xml: *const c_char,
+    speaker_id: u32,
+    options: SharevoxSingOptions,
+    output_wav_length: *mut usize,
+    output_wav: *mut *mut u8,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let music_xml = ensure_utf8(CStr::from_ptr(music_xml))?;
+        let score = Score::from_music_xml(music_xml).map_err(voicevox_core::Error::from)?;
+        let wav = &lock_internal().sing(&score, speaker_id, options.into())?;
         write_wav_to_ptr(output_wav, output_wav_length, wav);
         Ok(())
     })())
@@ -368,15 +1012,33 @@ pub unsafe extern "C" fn sharevox_synthesis(
 pub struct SharevoxTtsOptions {
     /// aquestalk形式のkanaとしてテキストを解釈する
     kana: bool,
+    /// kanaでアクセントの指定がないアクセント句を許容し、アクセント位置を推定する
+    lenient_kana: bool,
     /// 疑問文の調整を有効にする
     enable_interrogative_upspeak: bool,
+    /// 疑問文の調整のパラメータ
+    /// NULLのときは話者のメタ情報、またはデフォルト値が用いられる
+    interrogative_upspeak: *const SharevoxInterrogativeUpspeak,
+    /// モーラの音高を滑らかに繋いだフレームごとの音高で音声合成する
+    /// NULLのときは音素ごとの音高を用いる。SynthesisSystem が V2 のモデルでのみ使える
+    pitch_contour: *const SharevoxPitchContour,
+    /// 母音とポーズの長さを伸縮させ、音声全体をこの秒数に合わせる
+    /// 0以下のときは合わせない
+    target_duration: f32,
+    /// target_sentence_durations のデータ長
+    target_sentence_durations_length: usize,
+    /// 母音とポーズの長さを伸縮させ、文ごとの長さをこの秒数の配列に合わせる
+    /// NULLでないときは target_duration より優先される
+    target_sentence_durations: *const f32,
+    /// 音声合成の前に適用する話し方の変換
+    prosody_preset: SharevoxProsodyPreset,
 }
 
 /// デフォルトのテキスト音声合成オプションを生成する
 /// @return テキスト音声合成オプション
 #[no_mangle]
 pub extern "C" fn sharevox_make_default_tts_options() -> SharevoxTtsOptions {
-    voicevox_core::TtsOptions::default().into()
+    SharevoxTtsOptions::default()
 }
 
 /// テキスト音声合成を実行する
@@ -388,6 +1050,9 @@ pub extern "C" fn sharevox_make_default_tts_options() -> SharevoxTtsOptions {
 /// @return 結果コード #SharevoxResultCode
 ///
 /// # Safety
+/// @param options.interrogative_upspeak NULLでないときは有効な SharevoxInterrogativeUpspeak を指していること
+/// @param options.pitch_contour NULLでないときは有効な SharevoxPitchContour を指していること
+/// @param options.target_sentence_durations NULLでないときは必ず options.target_sentence_durations_length の長さだけデータがある状態で渡すこと
 /// @param output_wav_length 出力先の領域が確保された状態でpointerに渡されていること
 /// @param output_wav は自動で output_wav_length 分のデータが割り当てられるので ::sharevox_wav_free で解放する必要がある
 #[no_mangle]
@@ -400,12 +1065,118 @@ pub unsafe extern "C" fn sharevox_tts(
 ) -> SharevoxResultCode {
     into_result_code_with_error((|| {
         let text = ensure_utf8(CStr::from_ptr(text))?;
-        let output = lock_internal().tts(text, speaker_id, options.into())?;
+        let output = lock_internal().tts(text, speaker_id, options.into_options())?;
         write_wav_to_ptr(output_wav, output_wav_length, output.as_slice());
         Ok(())
     })())
 }
 
+/// 複数の音声を繋げる際のオプション
+#[repr(C)]
+pub struct SharevoxConnectWavesOptions {
+    /// 出力のサンプリングレート
+    /// 0を指定すると最初の音声に合わせる
+    output_sampling_rate: u32,
+    /// 出力のチャンネル数
+    /// 0を指定すると最初の音声に合わせる
+    output_num_channels: u16,
+    /// 音声の間に挟む無音の秒数
+    gap_length: f32,
+    /// 隣り合う音声を重ねてクロスフェードする秒数
+    /// 正の値を指定すると gap_length より優先される
+    crossfade_length: f32,
+}
+
+/// デフォルトの音声を繋げる際のオプションを生成する
+/// @return デフォルト値が設定された音声を繋げる際のオプション
+#[no_mangle]
+pub extern "C" fn sharevox_make_default_connect_waves_options() -> SharevoxConnectWavesOptions {
+    voicevox_core::ConnectWavesOptions::default().into()
+}
+
+/// 複数の wav データを一つに繋げる
+/// @param [in] waves_count wav データの数
+/// @param [in] waves wav データの配列
+/// @param [in] wave_lengths 各 wav データのサイズの配列
+/// @param [in] options 音声を繋げる際のオプション
+/// @param [out] output_wav_length 出力する wav データのサイズ
+/// @param [out] output_wav wav データの出力先
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param waves 必ず waves_count の長さだけデータがある状態で渡すこと
+/// @param wave_lengths 必ず waves_count の長さだけデータがある状態で渡すこと
+/// @param output_wav_length 出力先の領域が確保された状態でpointerに渡されていること
+/// @param output_wav 自動で output_wav_length 分のデータが割り当てられるので ::sharevox_wav_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_connect_waves(
+    waves_count: usize,
+    waves: *const *const u8,
+    wave_lengths: *const usize,
+    options: SharevoxConnectWavesOptions,
+    output_wav_length: *mut usize,
+    output_wav: *mut *mut u8,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let waves = std::iter::zip(
+            std::slice::from_raw_parts(waves, waves_count),
+            std::slice::from_raw_parts(wave_lengths, waves_count),
+        )
+        .map(|(&wave, &length)| std::slice::from_raw_parts(wave, length))
+        .collect::<Vec<_>>();
+        let wav = &lock_internal().connect_waves(&waves, options.into())?;
+        write_wav_to_ptr(output_wav, output_wav_length, wav);
+        Ok(())
+    })())
+}
+
+/// 複数の AudioQuery から音声合成し、一つの wav データに繋げる
+/// @param [in] audio_queries_count AudioQuery の数
+/// @param [in] audio_query_jsons jsonフォーマットされた AudioQuery の配列
+/// @param [in] speaker_ids 各 AudioQuery の話者IDの配列
+/// @param [in] synthesis_options AudioQueryから音声合成オプション
+/// @param [in] connect_options 音声を繋げる際のオプション
+/// @param [out] output_wav_length 出力する wav データのサイズ
+/// @param [out] output_wav wav データの出力先
+/// @return 結果コード #SharevoxResultCode
+///
+/// # Safety
+/// @param audio_query_jsons 必ず audio_queries_count の長さだけ、null終端文字列がある状態で渡すこと
+/// @param speaker_ids 必ず audio_queries_count の長さだけデータがある状態で渡すこと
+/// @param output_wav_length 出力先の領域が確保された状態でpointerに渡されていること
+/// @param output_wav 自動で output_wav_length 分のデータが割り当てられるので ::sharevox_wav_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_synthesis_connected(
+    audio_queries_count: usize,
+    audio_query_jsons: *const *const c_char,
+    speaker_ids: *const u32,
+    synthesis_options: SharevoxSynthesisOptions,
+    connect_options: SharevoxConnectWavesOptions,
+    output_wav_length: *mut usize,
+    output_wav: *mut *mut u8,
+) -> SharevoxResultCode {
+    into_result_code_with_error((|| {
+        let audio_queries = std::iter::zip(
+            std::slice::from_raw_parts(audio_query_jsons, audio_queries_count),
+            std::slice::from_raw_parts(speaker_ids, audio_queries_count),
+        )
+        .map(|(&audio_query_json, &speaker_id)| {
+            let audio_query_json = ensure_utf8(CStr::from_ptr(audio_query_json))?;
+            let audio_query =
+                serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
+            Ok((audio_query, speaker_id))
+        })
+        .collect::<CApiResult<Vec<_>>>()?;
+        let wav = &lock_internal().synthesis_connected(
+            &audio_queries,
+            synthesis_options.into_options(),
+            connect_options.into(),
+        )?;
+        write_wav_to_ptr(output_wav, output_wav_length, wav);
+        Ok(())
+    })())
+}
+
 /// jsonフォーマットされた AudioQuery データのメモリを解放する
 /// @param [in] audio_query_json 解放する json フォーマットされた AudioQuery データ
 ///
@@ -416,6 +1187,36 @@ pub unsafe extern "C" fn sharevox_audio_query_json_free(audio_query_json: *mut c
     libc::free(audio_query_json as *mut c_void);
 }
 
+/// jsonフォーマットされたテキストの解析結果のメモリを解放する
+/// @param [in] text_analysis_json 解放する json フォーマットされたテキストの解析結果
+///
+/// # Safety
+/// @param text_analysis_json 確保したメモリ領域が破棄される
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_text_analysis_json_free(text_analysis_json: *mut c_char) {
+    libc::free(text_analysis_json as *mut c_void);
+}
+
+/// jsonフォーマットされたNJDのノードの配列のメモリを解放する
+/// @param [in] njd_nodes_json 解放する json フォーマットされたNJDのノードの配列
+///
+/// # Safety
+/// @param njd_nodes_json 確保したメモリ領域が破棄される
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_njd_nodes_json_free(njd_nodes_json: *mut c_char) {
+    libc::free(njd_nodes_json as *mut c_void);
+}
+
+/// jsonフォーマットされたアクセント句の配列のメモリを解放する
+/// @param [in] accent_phrases_json 解放する json フォーマットされたアクセント句の配列
+///
+/// # Safety
+/// @param accent_phrases_json 確保したメモリ領域が破棄される
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_accent_phrases_json_free(accent_phrases_json: *mut c_char) {
+    libc::free(accent_phrases_json as *mut c_void);
+}
+
 /// wav データのメモリを解放する
 /// @param [in] wav 解放する wav データ
 ///
@@ -426,6 +1227,64 @@ pub unsafe extern "C" fn sharevox_wav_free(wav: *mut u8) {
     libc::free(wav as *mut c_void);
 }
 
+/// kanaの解析エラーの種類
+#[repr(i32)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub enum SharevoxKanaParseErrorKind {
+    /// アクセント句の先頭にアクセントがある
+    SHAREVOX_KANA_PARSE_ERROR_ACCENT_AT_BEGINNING = 0,
+    /// 一つのアクセント句に複数のアクセントがある
+    SHAREVOX_KANA_PARSE_ERROR_MULTIPLE_ACCENTS = 1,
+    /// アクセント句にアクセントがない
+    SHAREVOX_KANA_PARSE_ERROR_ACCENT_NOT_FOUND = 2,
+    /// 解釈できない文字がある
+    SHAREVOX_KANA_PARSE_ERROR_UNKNOWN_TEXT = 3,
+    /// 空のアクセント句がある
+    SHAREVOX_KANA_PARSE_ERROR_EMPTY_ACCENT_PHRASE = 4,
+    /// 疑問符がアクセント句の末尾以外にある
+    SHAREVOX_KANA_PARSE_ERROR_MISPLACED_INTERROGATIVE_MARK = 5,
+    /// ポーズの長さの表記が不正
+    SHAREVOX_KANA_PARSE_ERROR_INVALID_PAUSE_LENGTH = 6,
+    /// ポーズの長さの括弧が閉じられていない
+    SHAREVOX_KANA_PARSE_ERROR_UNCLOSED_PAUSE_LENGTH = 7,
+    /// アクセント句が長すぎる
+    SHAREVOX_KANA_PARSE_ERROR_TOO_LONG_ACCENT_PHRASE = 8,
+}
+
+/// kanaの解析エラーの詳細
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SharevoxKanaParseErrorDetails {
+    /// エラーの種類
+    kind: SharevoxKanaParseErrorKind,
+    /// 入力のうちエラーとなった箇所の開始位置(バイト単位ではなく文字単位)
+    offset: usize,
+    /// 入力のうちエラーとなった箇所の文字数
+    length: usize,
+    /// エラーとなったアクセント句の番号
+    accent_phrase_index: usize,
+}
+
+/// 同じスレッドでの直前の呼び出しで発生したkanaの解析エラーの詳細を取得する
+/// 直前の呼び出しが #SHAREVOX_RESULT_PARSE_KANA_ERROR 以外を返したときは何も書き込まない
+/// @param [out] output_details エラーの詳細の出力先
+/// @return エラーの詳細を書き込んだかどうか
+///
+/// # Safety
+/// @param output_details 出力先の領域が確保された状態でpointerに渡されていること
+#[no_mangle]
+pub unsafe extern "C" fn sharevox_get_last_kana_parse_error_details(
+    output_details: *mut SharevoxKanaParseErrorDetails,
+) -> bool {
+    if let Some(details) = last_kana_parse_error_details() {
+        output_details.write(details);
+        true
+    } else {
+        false
+    }
+}
+
 /// エラー結果をメッセージに変換する
 /// @param [in] result_code メッセージに変換する result_code
 /// @return 結果コードを元に変換されたメッセージ文字列
@@ -467,4 +1326,22 @@ mod tests {
         let actual = into_result_code_with_error(result.map_err(Into::into));
         assert_eq!(expected, actual);
     }
+
+    #[rstest]
+    fn last_kana_parse_error_details_is_thread_local() {
+        let err = voicevox_core::parse_kana("ア_ア").unwrap_err();
+        let result_code = into_result_code_with_error(Err(Error::ParseKana(err).into()));
+        assert_eq!(
+            SharevoxResultCode::SHAREVOX_RESULT_PARSE_KANA_ERROR,
+            result_code
+        );
+        assert!(last_kana_parse_error_details().is_some());
+
+        std::thread::spawn(|| assert_eq!(None, last_kana_parse_error_details()))
+            .join()
+            .unwrap();
+
+        into_result_code_with_error(Ok(()));
+        assert_eq!(None, last_kana_parse_error_details());
+    }
 }
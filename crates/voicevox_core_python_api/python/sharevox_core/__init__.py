@@ -3,21 +3,62 @@ from . import _load_dlls  # noqa: F401
 from ._models import (  # noqa: F401
     AccelerationMode,
     AccentPhrase,
+    AccentPhraseEdit,
+    AccentPhraseEditType,
+    AccentPhraseProsody,
     AudioQuery,
+    DurationFit,
+    InterrogativeUpspeak,
+    KanaParseErrorKind,
     Meta,
     Mora,
+    NjdNode,
+    PitchBreakpoint,
+    PitchContour,
+    PitchInterpolation,
+    ProsodyPreset,
+    ProsodyVariation,
     SupportedDevices,
+    TextAnalysis,
+    TextSpan,
+    UpspeakContour,
+    Vibrato,
+)
+from ._rust import (  # noqa: F401
+    SUPPORTED_DEVICES,
+    KanaParseError,
+    SharevoxCore,
+    SharevoxError,
+    SynthesisSession,
 )
-from ._rust import SUPPORTED_DEVICES, SharevoxCore  # noqa: F401
 
 
 __all__ = [
     "SUPPORTED_DEVICES",
     "AccelerationMode",
     "AccentPhrase",
+    "AccentPhraseEdit",
+    "AccentPhraseEditType",
+    "AccentPhraseProsody",
     "AudioQuery",
+    "DurationFit",
+    "InterrogativeUpspeak",
+    "KanaParseError",
+    "KanaParseErrorKind",
     "Meta",
     "Mora",
+    "NjdNode",
+    "PitchBreakpoint",
+    "PitchContour",
+    "PitchInterpolation",
+    "ProsodyPreset",
+    "ProsodyVariation",
     "SharevoxCore",
+    "SharevoxError",
     "SupportedDevices",
+    "SynthesisSession",
+    "TextAnalysis",
+    "TextSpan",
+    "UpspeakContour",
+    "Vibrato",
 ]
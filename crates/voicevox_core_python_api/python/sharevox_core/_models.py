@@ -1,13 +1,107 @@
+import dataclasses
 from enum import Enum
-from typing import List, Optional
+from typing import Dict, List, Optional
 
 import pydantic
 
 
+class UpspeakContour(str, Enum):
+    """
+    疑問文の語尾の抑揚の付け方。
+    """
+
+    APPEND_MORA = "append_mora"
+    RAISE_LAST_MORA = "raise_last_mora"
+
+
+@pydantic.dataclasses.dataclass
+class InterrogativeUpspeak:
+    """疑問文の調整のパラメータ。"""
+
+    contour: UpspeakContour = UpspeakContour.APPEND_MORA
+    vowel_length: float = 0.15
+    pitch_delta: float = 0.3
+    max_pitch: float = 6.5
+
+
+class PitchInterpolation(str, Enum):
+    """
+    モーラの音高の間の補間の方法。
+    """
+
+    LINEAR = "linear"
+    COSINE = "cosine"
+    SPLINE = "spline"
+
+
+@pydantic.dataclasses.dataclass
+class PitchContour:
+    """モーラの音高を滑らかに繋いだフレームごとの音高の作り方。"""
+
+    interpolation: PitchInterpolation = PitchInterpolation.LINEAR
+    declination: float = 0.0
+    """最初の有声のモーラから1秒あたりに下げる音高。下げた音高は約30Hzより低くしない。"""
+
+
+@pydantic.dataclasses.dataclass
+class PitchBreakpoint:
+    """音高曲線の制御点。"""
+
+    time: float
+    """音声の先頭からの時刻(秒)。"""
+
+    pitch: float
+    """音高。0のときは無声とする。"""
+
+
+@pydantic.dataclasses.dataclass
+class Vibrato:
+    """長く伸ばした母音の音高に加えるビブラートと細かな揺らぎ。
+
+    SynthesisSystem が V2 のモデルでのみ使え、それ以外のモデルでは無視する。
+    """
+
+    rate: float = 5.5
+    """1秒あたりの揺れの回数。"""
+
+    depth: float = 0.5
+    """揺れの深さ(半音)。"""
+
+    delay: float = 0.15
+    """母音が始まってから揺れ始めるまでの秒数。"""
+
+    min_length: float = 0.3
+    """ビブラートと揺らぎを加える母音の長さの最小値(秒)。"""
+
+    jitter: float = 0.05
+    """フレームごとに加える不規則な揺らぎの大きさ(半音)。"""
+
+
+@pydantic.dataclasses.dataclass
+class ProsodyVariation:
+    """推論したモーラの長さと音高、ポーズの長さに加える、シードから再現できる小さな揺らぎ。
+
+    同じシードと同じ AudioQuery からは、常に同じ揺らぎになる。
+    音高を平坦にする話し方の変換を指定したときは、揺らぎを加えた後に変換する。
+    """
+
+    seed: int = 0
+
+    length: float = 0.05
+    """モーラの子音と母音の長さを変える割合の最大値。"""
+
+    pitch: float = 0.2
+    """有声のモーラの音高をずらす最大値(半音)。12 未満とする。"""
+
+    pause_length: float = 0.15
+    """ポーズの長さを変える割合の最大値。"""
+
+
 @pydantic.dataclasses.dataclass
 class Style:
     name: str
     id: int
+    interrogative_upspeak: Optional[InterrogativeUpspeak] = None
 
 
 @pydantic.dataclasses.dataclass
@@ -39,6 +133,43 @@ class AccelerationMode(str, Enum):
     GPU = "GPU"
 
 
+class KanaParseErrorKind(str, Enum):
+    """
+    kanaの解析エラーの種類。
+    """
+
+    ACCENT_AT_BEGINNING = "accent_at_beginning"
+    MULTIPLE_ACCENTS = "multiple_accents"
+    ACCENT_NOT_FOUND = "accent_not_found"
+    UNKNOWN_TEXT = "unknown_text"
+    EMPTY_ACCENT_PHRASE = "empty_accent_phrase"
+    MISPLACED_INTERROGATIVE_MARK = "misplaced_interrogative_mark"
+    INVALID_PAUSE_LENGTH = "invalid_pause_length"
+    UNCLOSED_PAUSE_LENGTH = "unclosed_pause_length"
+    TOO_LONG_ACCENT_PHRASE = "too_long_accent_phrase"
+
+
+class ProsodyPreset(str, Enum):
+    """
+    音声合成の前にアクセント句に適用する話し方の変換。
+    """
+
+    MONOTONE = "monotone"
+    """有声のモーラの音高を全て平均の音高にし、抑揚をなくす。"""
+    WHISPER = "whisper"
+    """全てのモーラを無声にし、ささやき声にする。"""
+    ROBOT = "robot"
+    """有声のモーラの音高を全て、平均の音高に最も近い平均律の音にする。"""
+
+
+@pydantic.dataclasses.dataclass
+class TextSpan:
+    """入力テキスト中の範囲。位置は文字単位で数え、 ``end`` の位置の文字は含まない。"""
+
+    start: int
+    end: int
+
+
 @pydantic.dataclasses.dataclass
 class Mora:
     text: str
@@ -47,6 +178,23 @@ class Mora:
     vowel: str
     vowel_length: float
     pitch: float
+    source_span: Optional[TextSpan] = None
+    is_locked: bool = False
+    vibrato: Optional[Vibrato] = None
+
+
+@pydantic.dataclasses.dataclass
+class AccentPhraseProsody:
+    """
+    アクセント句ごとに指定する話速、音高、抑揚、音量。
+
+    指定した値は、そのアクセント句で ``AudioQuery`` の同じ名前の値の代わりに用いる。
+    """
+
+    speed_scale: Optional[float] = None
+    pitch_scale: Optional[float] = None
+    intonation_scale: Optional[float] = None
+    volume_scale: Optional[float] = None
 
 
 @pydantic.dataclasses.dataclass
@@ -55,6 +203,42 @@ class AccentPhrase:
     accent: int
     pause_mora: Optional[Mora]
     is_interrogative: bool
+    is_pause_length_explicit: bool = False
+    pause_punctuation: Optional[str] = None
+    source_span: Optional[TextSpan] = None
+    prosody: AccentPhraseProsody = dataclasses.field(
+        default_factory=AccentPhraseProsody
+    )
+
+
+class AccentPhraseEditType(str, Enum):
+    """
+    アクセント句の列に対する編集操作の種類。
+    """
+
+    CHANGE_ACCENT = "change_accent"
+    """アクセント位置を ``accent`` に変更する。"""
+    MERGE = "merge"
+    """アクセント句を次のアクセント句と結合する。"""
+    SPLIT = "split"
+    """アクセント句を ``mora_index`` 番目のモーラの前で分割する。"""
+    TOGGLE_PAUSE = "toggle_pause"
+    """アクセント句の後の息継ぎの有無を切り替える。"""
+    TOGGLE_INTERROGATIVE = "toggle_interrogative"
+    """アクセント句を疑問文の語尾として扱うかを切り替える。"""
+
+
+@pydantic.dataclasses.dataclass
+class AccentPhraseEdit:
+    """アクセント句の列に対する編集操作。"""
+
+    type: AccentPhraseEditType
+    accent_phrase_index: int
+    """編集するアクセント句の位置。"""
+    accent: Optional[int] = None
+    """``CHANGE_ACCENT`` で指定するアクセント位置。"""
+    mora_index: Optional[int] = None
+    """``SPLIT`` で指定する分割する位置のモーラの位置。"""
 
 
 @pydantic.dataclasses.dataclass
@@ -69,3 +253,65 @@ class AudioQuery:
     output_sampling_rate: int
     output_stereo: bool
     kana: Optional[str]
+    pause_length: Optional[float] = None
+    pause_length_scale: float = 1.0
+    punctuation_pause_lengths: Dict[str, float] = dataclasses.field(
+        default_factory=dict
+    )
+    vibrato: Optional[Vibrato] = None
+    pitch_shift: float = 0.0
+    """有声の音高をずらす半音の数。1セントは0.01となる。-24 以上 24 以下とする。"""
+    target_mean_f0: Optional[float] = None
+    """指定したとき、有声のフレームの平均の基本周波数がこの値(Hz)になるように音高をずらす。50 以上 1000 以下とする。
+
+    ``pitch_scale`` を適用した後の、音素の長さで重み付けした音高の平均を合わせ、その後に ``pitch_shift`` だけずらす。
+    """
+    prosody_preset: Optional[ProsodyPreset] = None
+    """音声合成の前にアクセント句に適用する話し方の変換。"""
+    prosody_variation: Optional[ProsodyVariation] = None
+    """推論したモーラの長さと音高、ポーズの長さに加える、シードから再現できる揺らぎ。"""
+
+
+@pydantic.dataclasses.dataclass
+class DurationFit:
+    """目標の長さに合わせた AudioQuery と、その音声の長さ。"""
+
+    audio_query: AudioQuery
+    duration: float
+    """先頭と末尾の無音を含む、音声全体の長さ(秒)。"""
+    sentence_durations: List[float]
+    """文ごとの長さ(秒)。"""
+
+
+@pydantic.dataclasses.dataclass
+class NjdNode:
+    """OpenJTalkのNJDのノード。1つの単語にあたる。"""
+
+    surface: str
+    """表層形。"""
+
+    part_of_speech: str
+    """品詞。"""
+
+    reading: str
+    """読み。"""
+
+    pronunciation: str
+    """発音。"""
+
+    accent_type: int
+    """アクセント型。0は平板型。"""
+
+    mora_count: int
+    """モーラ数。"""
+
+    chain_flag: bool
+    """前の単語と同じアクセント句にまとめられるか。"""
+
+
+@pydantic.dataclasses.dataclass
+class TextAnalysis:
+    """音声合成モデルを使わずにテキストを解析した結果。"""
+
+    accent_phrases: List[AccentPhrase]
+    kana: str
@@ -1,15 +1,55 @@
 from pathlib import Path
-from typing import Final, List, Literal, Union
+from typing import Final, List, Literal, Optional, Tuple, Union
 
 import numpy as np
 from numpy.typing import NDArray
 
-from sharevox_core import AccelerationMode, AudioQuery, Meta, SupportedDevices
+from sharevox_core import (
+    AccelerationMode,
+    AccentPhrase,
+    AccentPhraseEdit,
+    AudioQuery,
+    DurationFit,
+    InterrogativeUpspeak,
+    KanaParseErrorKind,
+    Meta,
+    NjdNode,
+    PitchBreakpoint,
+    PitchContour,
+    ProsodyPreset,
+    SupportedDevices,
+    TextAnalysis,
+)
 
 # METAS: Final[List[Meta]]
 SUPPORTED_DEVICES: Final[SupportedDevices]
 __version__: str
 
+class SharevoxError(Exception):
+    """sharevox_core のエラー。"""
+
+    ...
+
+class KanaParseError(SharevoxError):
+    """kanaの解析に失敗したときのエラー。
+
+    Attributes
+    ----------
+    kind
+        エラーの種類。
+    offset
+        入力のうちエラーとなった箇所の、文字単位の開始位置。
+    length
+        入力のうちエラーとなった箇所の文字数。
+    accent_phrase_index
+        エラーとなったアクセント句の番号。
+    """
+
+    kind: KanaParseErrorKind
+    offset: int
+    length: int
+    accent_phrase_index: int
+
 class SharevoxCore:
     def __init__(
         self,
@@ -20,6 +60,7 @@ class SharevoxCore:
         cpu_num_threads: int = 0,
         load_all_models: bool = False,
         open_jtalk_dict_dir: Union[Path, str, None] = None,
+        load_open_jtalk_dict_lazily: bool = False,
     ) -> None:
         """
         Parameters
@@ -31,7 +72,9 @@ class SharevoxCore:
         load_all_models
             全てのモデルを読み込む。
         open_jtalk_dict_dir
-            open_jtalkの辞書ディレクトリ。
+            open_jtalkの辞書ディレクトリ。kanaからの音声合成のみを行う場合は不要。
+        load_open_jtalk_dict_lazily
+            ``open_jtalk_dict_dir`` の辞書を初期化時には読み込まず、テキストの解析で初めて必要になったときに読み込む。
         """
         ...
     def __repr__(self) -> str: ...
@@ -69,6 +112,26 @@ class SharevoxCore:
         モデルが読み込まれているのであればtrue、そうでないならfalse
         """
         ...
+    def load_openjtalk_dict(self, open_jtalk_dict_dir: Union[Path, str]) -> None:
+        """open_jtalkの辞書を読み込む。
+
+        kanaからの音声合成には辞書は不要なので、初期化時に辞書を指定しなかった場合でも後から読み込める。
+
+        Parameters
+        ----------
+        open_jtalk_dict_dir
+            open_jtalkの辞書ディレクトリ。
+        """
+        ...
+    @property
+    def is_openjtalk_dict_loaded(self) -> bool:
+        """open_jtalkの辞書が読み込まれているか判定する。
+
+        Returns
+        -------
+        辞書が読み込まれているのであればtrue、そうでないならfalse
+        """
+        ...
     def predict_pitch_and_duration(
         self,
         phoneme_vector: NDArray[np.int64],
@@ -111,6 +174,33 @@ class SharevoxCore:
         speaker_id
             話者ID。
 
+        Returns
+        -------
+        decode結果
+        """
+        ...
+    def decode_with_frame_pitch(
+        self,
+        phoneme_vector: NDArray[np.int64],
+        frame_pitch_vector: NDArray[np.float32],
+        duration_vector: NDArray[np.float32],
+        speaker_id: int,
+    ) -> NDArray[np.float32]:
+        """音素ごとではなく、フレームごとの音高でdecodeを実行する。
+
+        SynthesisSystem が V2 のモデルでのみ使える。
+
+        Parameters
+        ----------
+        phoneme_vector
+            音素データ。
+        frame_pitch_vector
+            1秒あたり93.75フレームの音高。 ``duration_vector`` から求まるフレーム数と同じ長さでなければならない。
+        duration_vector
+            音素長データ。
+        speaker_id
+            話者ID。
+
         Returns
         -------
         decode結果
@@ -121,6 +211,8 @@ class SharevoxCore:
         text: str,
         speaker_id: int,
         kana: bool = False,
+        lenient_kana: bool = False,
+        prosody_preset: Optional[ProsodyPreset] = None,
     ) -> AudioQuery:
         """AudioQuery を実行する。
 
@@ -132,17 +224,174 @@ class SharevoxCore:
             話者ID。
         kana
             aquestalk形式のkanaとしてテキストを解釈する。
+        lenient_kana
+            kanaでアクセントの指定がないアクセント句を許容し、アクセント位置を推定する。
+        prosody_preset
+            AudioQuery に設定する、音声合成の前に適用する話し方の変換。
+
+        Returns
+        -------
+        :class:`AudioQuery`
+        """
+        ...
+    def retarget_audio_query(
+        self,
+        audio_query: AudioQuery,
+        speaker_id: int,
+        source_speaker_id: Optional[int] = None,
+    ) -> AudioQuery:
+        """AudioQuery の音素の長さと音高を別の話者で推論し直す。
+
+        ``is_locked`` が真のモーラの値と、話速などの AudioQuery 全体の値は変更しない。
+
+        Parameters
+        ----------
+        audio_query
+            AudioQuery。
+        speaker_id
+            推論し直す話者ID。
+        source_speaker_id
+            元の話者ID。指定したときは、固定したモーラの音高を話者ごとの平均の音高の差だけずらす。
+
+        Returns
+        -------
+        :class:`AudioQuery`
+        """
+        ...
+    def audio_query_from_labels(
+        self,
+        labels: str,
+        speaker_id: int,
+        pitches: Optional[List[float]] = None,
+    ) -> AudioQuery:
+        """HTSラベルから、ラベルの時刻を音素の長さとした AudioQuery を生成する。
+
+        Parameters
+        ----------
+        labels
+            モノラベル、またはフルコンテキストラベルの ``開始時刻 終了時刻 ラベル`` の行。
+        speaker_id
+            話者ID。
+        pitches
+            ラベルごとの音高。Noneのときは話者の音高を推論する。
 
         Returns
         -------
         :class:`AudioQuery`
         """
         ...
+    def analyze_text(self, text: str) -> TextAnalysis:
+        """音声合成モデルを使わずに、テキストの読みとアクセントを解析する。
+
+        解析結果のアクセント句の音素の長さと音高は全て0となる。
+
+        Parameters
+        ----------
+        text
+            テキスト。
+
+        Returns
+        -------
+        :class:`TextAnalysis`
+        """
+        ...
+    def extract_njd_nodes(self, text: str) -> List[NjdNode]:
+        """テキストをOpenJTalkで解析し、NJDのノードを返す。
+
+        Parameters
+        ----------
+        text
+            テキスト。
+
+        Returns
+        -------
+        :class:`NjdNode` のリスト
+        """
+        ...
+    def mora_length(
+        self, accent_phrases: List[AccentPhrase], speaker_id: int
+    ) -> List[AccentPhrase]:
+        """アクセント句の音素の長さを、指定した話者で推論し直す。
+
+        Parameters
+        ----------
+        accent_phrases
+            アクセント句のリスト。
+        speaker_id
+            話者ID。
+
+        Returns
+        -------
+        :class:`AccentPhrase` のリスト
+        """
+        ...
+    def mora_pitch(
+        self, accent_phrases: List[AccentPhrase], speaker_id: int
+    ) -> List[AccentPhrase]:
+        """アクセント句の音高を、指定した話者で推論し直す。
+
+        Parameters
+        ----------
+        accent_phrases
+            アクセント句のリスト。
+        speaker_id
+            話者ID。
+
+        Returns
+        -------
+        :class:`AccentPhrase` のリスト
+        """
+        ...
+    def mora_data(
+        self, accent_phrases: List[AccentPhrase], speaker_id: int
+    ) -> List[AccentPhrase]:
+        """アクセント句の音素の長さと音高を、指定した話者で推論し直す。
+
+        Parameters
+        ----------
+        accent_phrases
+            アクセント句のリスト。
+        speaker_id
+            話者ID。
+
+        Returns
+        -------
+        :class:`AccentPhrase` のリスト
+        """
+        ...
+    def edit_accent_phrases(
+        self,
+        accent_phrases: List[AccentPhrase],
+        edit: AccentPhraseEdit,
+        speaker_id: int,
+    ) -> List[AccentPhrase]:
+        """アクセント句の列を編集し、編集したアクセント句の音素の長さと音高を指定した話者で推論し直す。
+
+        推論は編集の影響を受けるアクセント句と前後の数個のアクセント句だけで行い、
+        編集の影響を受けないアクセント句の値は変更しない。
+
+        Parameters
+        ----------
+        accent_phrases
+            アクセント句のリスト。
+        edit
+            編集操作。
+        speaker_id
+            話者ID。
+
+        Returns
+        -------
+        :class:`AccentPhrase` のリスト
+        """
+        ...
     def synthesis(
         self,
         audio_query: AudioQuery,
         speaker_id: int,
         enable_interrogative_upspeak: bool = True,
+        interrogative_upspeak: Optional[InterrogativeUpspeak] = None,
+        pitch_contour: Optional[PitchContour] = None,
+        target_duration: Union[float, List[float], None] = None,
     ) -> bytes:
         """AudioQuery から音声合成する。
 
@@ -154,6 +403,221 @@ class SharevoxCore:
             話者ID。
         enable_interrogative_upspeak
             疑問文の調整を有効にする。
+        interrogative_upspeak
+            疑問文の調整のパラメータ。Noneのときは話者のメタ情報、またはデフォルト値を用いる。
+        pitch_contour
+            モーラの音高を滑らかに繋いだフレームごとの音高で音声合成する。SynthesisSystem が V2 のモデルでのみ使える。
+        target_duration
+            母音とポーズの長さを伸縮させ、音声をこの長さ(秒)に合わせる。数値のときは音声全体の長さ、リストのときは文ごとの長さとする。
+
+        Returns
+        -------
+        wavデータ
+        """
+        ...
+    def fit_duration(
+        self,
+        audio_query: AudioQuery,
+        speaker_id: int,
+        target_duration: Union[float, List[float]],
+        enable_interrogative_upspeak: bool = True,
+        interrogative_upspeak: Optional[InterrogativeUpspeak] = None,
+    ) -> DurationFit:
+        """AudioQuery の母音とポーズの長さを伸縮させ、音声合成したときの長さを目標の長さに合わせる。
+
+        子音の長さと先頭と末尾の無音の長さ、``is_locked`` が真のモーラの長さは変更しない。
+        ポーズは母音より大きな割合で伸縮させる。
+
+        Parameters
+        ----------
+        audio_query
+            AudioQuery。
+        speaker_id
+            話者ID。
+        target_duration
+            目標の長さ(秒)。数値のときは先頭と末尾の無音を含む音声全体の長さ、リストのときは文末の句読点で区切った文ごとの長さとする。
+        enable_interrogative_upspeak
+            疑問文の調整を有効にする。音声合成するときと同じ値を指定する。
+        interrogative_upspeak
+            疑問文の調整のパラメータ。Noneのときは話者のメタ情報、またはデフォルト値を用いる。
+
+        Returns
+        -------
+        :class:`DurationFit`
+        """
+        ...
+    def new_synthesis_session(
+        self,
+        speaker_id: int,
+        enable_interrogative_upspeak: bool = True,
+        interrogative_upspeak: Optional[InterrogativeUpspeak] = None,
+        pitch_contour: Optional[PitchContour] = None,
+        context_margin: int = 1,
+        crossfade_length: float = 0.01,
+    ) -> "SynthesisSession":
+        """変更のない区間の合成結果を再利用して音声合成するセッションを作る。
+
+        ``pitch_contour`` と AudioQuery のビブラートは、 :meth:`synthesis` と同じくフレームごとの音高で音声合成する。
+
+        Parameters
+        ----------
+        speaker_id
+            話者ID。
+        enable_interrogative_upspeak
+            疑問文の調整を有効にする。
+        interrogative_upspeak
+            疑問文の調整のパラメータ。Noneのときは話者のメタ情報、またはデフォルト値を用いる。
+        pitch_contour
+            モーラの音高を滑らかに繋いだフレームごとの音高で音声合成する。SynthesisSystem が V2 のモデルでのみ使える。
+        context_margin
+            変更のあったアクセント句の前後で、合わせて推論し直すアクセント句の数。
+        crossfade_length
+            推論し直した区間と前回の合成結果を繋ぐクロスフェードの秒数。
+
+        Returns
+        -------
+        :class:`SynthesisSession`
+        """
+        ...
+    def synthesis_with_session(
+        self, session: "SynthesisSession", audio_query: AudioQuery
+    ) -> bytes:
+        """セッションの前回の音声合成から変更のあったアクセント句だけを推論し直して音声合成する。
+
+        Parameters
+        ----------
+        session
+            :meth:`new_synthesis_session` で作ったセッション。
+        audio_query
+            AudioQuery。
+
+        Returns
+        -------
+        wavデータ
+        """
+        ...
+    def synthesis_with_frame_pitch(
+        self,
+        audio_query: AudioQuery,
+        speaker_id: int,
+        frame_pitch: NDArray[np.float32],
+    ) -> bytes:
+        """AudioQuery の音素の長さと、フレームごとの音高から音声合成する。
+
+        AudioQuery のモーラの音高と抑揚は用いず、音高の調整だけを ``frame_pitch`` に適用する。
+
+        Parameters
+        ----------
+        audio_query
+            AudioQuery。
+        speaker_id
+            話者ID。
+        frame_pitch
+            1秒あたり93.75フレームの音高。AudioQuery の音素の長さから求まるフレーム数と同じ長さでなければならない。
+
+        Returns
+        -------
+        wavデータ
+        """
+        ...
+    def synthesis_with_pitch_curve(
+        self,
+        audio_query: AudioQuery,
+        speaker_id: int,
+        breakpoints: List[PitchBreakpoint],
+    ) -> bytes:
+        """AudioQuery の音素の長さと、制御点の間を線形補間した音高曲線から音声合成する。
+
+        Parameters
+        ----------
+        audio_query
+            AudioQuery。
+        speaker_id
+            話者ID。
+        breakpoints
+            時刻順に並べた音高曲線の制御点。有声と無声の間は補間しない。
+
+        Returns
+        -------
+        wavデータ
+        """
+        ...
+    def synthesis_from_labels(
+        self,
+        labels: str,
+        speaker_id: int,
+        pitches: Optional[List[float]] = None,
+    ) -> bytes:
+        """HTSラベルの時刻を音素の長さとして音声合成する。
+
+        Parameters
+        ----------
+        labels
+            モノラベル、またはフルコンテキストラベルの ``開始時刻 終了時刻 ラベル`` の行。
+        speaker_id
+            話者ID。
+        pitches
+            ラベルごとの音高。Noneのときは話者の音高を推論する。
+
+        Returns
+        -------
+        wavデータ
+        """
+        ...
+    def sing_midi(
+        self,
+        midi: bytes,
+        speaker_id: int,
+        key_shift: int = 0,
+        pre_phoneme_length: float = 0.1,
+        post_phoneme_length: float = 0.1,
+    ) -> bytes:
+        """Standard MIDI Fileの楽譜を歌う音声を合成する。
+
+        歌詞のメタイベントを含む最初のトラックを用いる。
+
+        Parameters
+        ----------
+        midi
+            Standard MIDI Fileのデータ。歌詞はUTF-8のかなでなければならない。
+        speaker_id
+            話者ID。
+        key_shift
+            楽譜の音高をずらす半音の数。
+        pre_phoneme_length
+            最初の音符より前の無音の長さ(秒)。
+        post_phoneme_length
+            最後の音符より後の無音の長さ(秒)。
+
+        Returns
+        -------
+        wavデータ
+        """
+        ...
+    def sing_music_xml(
+        self,
+        music_xml: str,
+        speaker_id: int,
+        key_shift: int = 0,
+        pre_phoneme_length: float = 0.1,
+        post_phoneme_length: float = 0.1,
+    ) -> bytes:
+        """partwise形式のMusicXMLの楽譜を歌う音声を合成する。
+
+        最初のパートの最初の声部を用いる。
+
+        Parameters
+        ----------
+        music_xml
+            MusicXMLの文字列。歌詞はかなでなければならない。
+        speaker_id
+            話者ID。
+        key_shift
+            楽譜の音高をずらす半音の数。
+        pre_phoneme_length
+            最初の音符より前の無音の長さ(秒)。
+        post_phoneme_length
+            最後の音符より後の無音の長さ(秒)。
 
         Returns
         -------
@@ -165,7 +629,12 @@ class SharevoxCore:
         text: str,
         speaker_id: int,
         kana: bool = False,
+        lenient_kana: bool = False,
         enable_interrogative_upspeak: bool = True,
+        interrogative_upspeak: Optional[InterrogativeUpspeak] = None,
+        pitch_contour: Optional[PitchContour] = None,
+        target_duration: Union[float, List[float], None] = None,
+        prosody_preset: Optional[ProsodyPreset] = None,
     ) -> bytes:
         """テキスト音声合成を実行する。
 
@@ -177,7 +646,93 @@ class SharevoxCore:
             話者ID。
         kana
             aquestalk形式のkanaとしてテキストを解釈する。
+        lenient_kana
+            kanaでアクセントの指定がないアクセント句を許容し、アクセント位置を推定する。
+        enable_interrogative_upspeak
+            疑問文の調整を有効にする。
+        interrogative_upspeak
+            疑問文の調整のパラメータ。Noneのときは話者のメタ情報、またはデフォルト値を用いる。
+        pitch_contour
+            モーラの音高を滑らかに繋いだフレームごとの音高で音声合成する。SynthesisSystem が V2 のモデルでのみ使える。
+        target_duration
+            母音とポーズの長さを伸縮させ、音声をこの長さ(秒)に合わせる。数値のときは音声全体の長さ、リストのときは文ごとの長さとする。
+        prosody_preset
+            音声合成の前に適用する話し方の変換。
+        """
+        ...
+    def connect_waves(
+        self,
+        waves: List[bytes],
+        output_sampling_rate: Optional[int] = None,
+        output_num_channels: Optional[int] = None,
+        gap_length: float = 0.0,
+        crossfade_length: float = 0.0,
+    ) -> bytes:
+        """複数のwavデータを一つに繋げる。
+
+        Parameters
+        ----------
+        waves
+            wavデータのリスト。
+        output_sampling_rate
+            出力のサンプリングレート。Noneのときは最初の音声に合わせる。
+        output_num_channels
+            出力のチャンネル数。Noneのときは最初の音声に合わせる。
+        gap_length
+            音声の間に挟む無音の秒数。
+        crossfade_length
+            隣り合う音声を重ねてクロスフェードする秒数。正のときは gap_length より優先される。
+
+        Returns
+        -------
+        wavデータ
+        """
+        ...
+    def synthesis_connected(
+        self,
+        audio_queries: List[Tuple[AudioQuery, int]],
+        enable_interrogative_upspeak: bool = True,
+        interrogative_upspeak: Optional[InterrogativeUpspeak] = None,
+        pitch_contour: Optional[PitchContour] = None,
+        output_sampling_rate: Optional[int] = None,
+        output_num_channels: Optional[int] = None,
+        gap_length: float = 0.0,
+        crossfade_length: float = 0.0,
+    ) -> bytes:
+        """複数の AudioQuery から音声合成し、一つのwavデータに繋げる。
+
+        Parameters
+        ----------
+        audio_queries
+            AudioQuery と話者IDの組のリスト。
         enable_interrogative_upspeak
             疑問文の調整を有効にする。
+        interrogative_upspeak
+            疑問文の調整のパラメータ。Noneのときは話者のメタ情報、またはデフォルト値を用いる。
+        pitch_contour
+            モーラの音高を滑らかに繋いだフレームごとの音高で音声合成する。SynthesisSystem が V2 のモデルでのみ使える。
+        output_sampling_rate
+            出力のサンプリングレート。Noneのときは最初の AudioQuery に合わせる。
+        output_num_channels
+            出力のチャンネル数。Noneのときは最初の AudioQuery に合わせる。
+        gap_length
+            音声の間に挟む無音の秒数。
+        crossfade_length
+            隣り合う音声を重ねてクロスフェードする秒数。正のときは gap_length より優先される。
+
+        Returns
+        -------
+        wavデータ
         """
         ...
+
+class SynthesisSession:
+    """同じ話者で AudioQuery を繰り返し音声合成する際に、変更のない区間の合成結果を再利用するセッション。
+
+    :meth:`SharevoxCore.new_synthesis_session` で作る。
+    """
+
+    @property
+    def speaker_id(self) -> int:
+        """話者ID。"""
+        ...
@@ -11,12 +11,13 @@ use pyo3::{
     exceptions::PyException,
     pyclass, pymethods, pymodule,
     types::{PyBytes, PyList, PyModule},
-    FromPyObject as _, PyAny, PyResult, Python,
+    FromPyObject as _, PyAny, PyErr, PyRefMut, PyResult, Python,
 };
 use serde::{de::DeserializeOwned, Serialize};
 use voicevox_core::{
-    AccelerationMode, AudioQueryModel, AudioQueryOptions, InitializeOptions, SynthesisOptions,
-    TtsOptions,
+    AccelerationMode, AudioQueryModel, AudioQueryOptions, ConnectWavesOptions, FramePitch,
+    InitializeOptions, InterrogativeUpspeak, PitchContour, ProsodyPreset, RetargetOptions, Score,
+    SingOptions, SynthesisOptions, SynthesisSessionOptions, TargetDuration, TtsOptions,
 };
 
 type VarianceForward<'py> = PyResult<(&'py PyArray<f32, Ix1>, &'py PyArray<f32, Ix1>)>;
@@ -47,7 +48,11 @@ fn rust(py: Python<'_>, module: &PyModule) -> PyResult<()> {
 
     module.add("__version__", voicevox_core::VoicevoxCore::get_version())?;
 
-    module.add_class::<SharevoxCore>()
+    module.add("SharevoxError", py.get_type::<SharevoxError>())?;
+    module.add("KanaParseError", py.get_type::<KanaParseError>())?;
+
+    module.add_class::<SharevoxCore>()?;
+    module.add_class::<SynthesisSession>()
 }
 
 create_exception!(
@@ -57,11 +62,38 @@ create_exception!(
     "sharevox_core Error."
 );
 
+create_exception!(
+    voicevox_core,
+    KanaParseError,
+    SharevoxError,
+    "kanaの解析に失敗したときのエラー。"
+);
+
 #[pyclass]
 struct SharevoxCore {
     inner: voicevox_core::VoicevoxCore,
 }
 
+#[pyclass]
+struct SynthesisSession {
+    inner: voicevox_core::SynthesisSession,
+}
+
+#[pymethods]
+impl SynthesisSession {
+    #[getter]
+    fn speaker_id(&self) -> u32 {
+        self.inner.speaker_id()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SynthesisSession {{ speaker_id: {}, .. }}",
+            self.inner.speaker_id()
+        )
+    }
+}
+
 #[pymethods]
 impl SharevoxCore {
     #[new]
@@ -69,7 +101,8 @@ impl SharevoxCore {
         acceleration_mode = "InitializeOptions::default().acceleration_mode",
         cpu_num_threads = "InitializeOptions::default().cpu_num_threads",
         load_all_models = "InitializeOptions::default().load_all_models",
-        open_jtalk_dict_dir = "None"
+        open_jtalk_dict_dir = "None",
+        load_open_jtalk_dict_lazily = "InitializeOptions::default().load_open_jtalk_dict_lazily"
     )]
     fn new(
         root_dir_path: String,
@@ -77,6 +110,7 @@ impl SharevoxCore {
         cpu_num_threads: u16,
         load_all_models: bool,
         #[pyo3(from_py_with = "from_optional_utf8_path")] open_jtalk_dict_dir: Option<String>,
+        load_open_jtalk_dict_lazily: bool,
     ) -> PyResult<Self> {
         let inner = voicevox_core::VoicevoxCore::new_with_initialize(
             Path::new(&root_dir_path),
@@ -85,6 +119,7 @@ impl SharevoxCore {
                 cpu_num_threads,
                 load_all_models,
                 open_jtalk_dict_dir: open_jtalk_dict_dir.map(Into::into),
+                load_open_jtalk_dict_lazily,
             },
         )
         .into_py_result()?;
@@ -121,6 +156,20 @@ impl SharevoxCore {
         self.inner.is_model_loaded(speaker_id)
     }
 
+    fn load_openjtalk_dict(
+        &mut self,
+        #[pyo3(from_py_with = "from_utf8_path")] open_jtalk_dict_dir: String,
+    ) -> PyResult<()> {
+        self.inner
+            .load_openjtalk_dict(Path::new(&open_jtalk_dict_dir))
+            .into_py_result()
+    }
+
+    #[getter]
+    fn is_openjtalk_dict_loaded(&self) -> bool {
+        self.inner.is_openjtalk_dict_loaded()
+    }
+
     fn predict_pitch_and_duration<'py>(
         &mut self,
         phoneme_vector: &'py PyArray<i64, Ix1>,
@@ -162,17 +211,75 @@ impl SharevoxCore {
         Ok(PyArray::from_vec(py, decoded))
     }
 
-    #[args(kana = "AudioQueryOptions::default().kana")]
+    fn decode_with_frame_pitch<'py>(
+        &mut self,
+        phoneme_vector: &'py PyArray<i64, Ix1>,
+        frame_pitch_vector: &'py PyArray<f32, Ix1>,
+        duration_vector: &'py PyArray<f32, Ix1>,
+        speaker_id: u32,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyArray<f32, Ix1>> {
+        let decoded = self
+            .inner
+            .decode_with_frame_pitch(
+                &phoneme_vector.to_vec()?,
+                &frame_pitch_vector.to_vec()?,
+                &duration_vector.to_vec()?,
+                speaker_id,
+            )
+            .into_py_result()?;
+        Ok(PyArray::from_vec(py, decoded))
+    }
+
+    #[args(
+        kana = "AudioQueryOptions::default().kana",
+        lenient_kana = "AudioQueryOptions::default().lenient_kana",
+        prosody_preset = "None"
+    )]
     fn audio_query<'py>(
         &mut self,
         text: &str,
         speaker_id: u32,
         kana: bool,
+        lenient_kana: bool,
+        #[pyo3(from_py_with = "from_optional_prosody_preset")] prosody_preset: Option<
+            ProsodyPreset,
+        >,
         py: Python<'py>,
     ) -> PyResult<&'py PyAny> {
         let audio_query = &self
             .inner
-            .audio_query(text, speaker_id, AudioQueryOptions { kana })
+            .audio_query(
+                text,
+                speaker_id,
+                AudioQueryOptions {
+                    kana,
+                    lenient_kana,
+                    prosody_preset,
+                },
+            )
+            .into_py_result_with_kana_details(py)?;
+        to_pydantic_dataclass(
+            audio_query,
+            py.import("sharevox_core")?.getattr("AudioQuery")?,
+        )
+    }
+
+    #[args(source_speaker_id = "None")]
+    fn retarget_audio_query<'py>(
+        &mut self,
+        #[pyo3(from_py_with = "from_dataclass")] audio_query: AudioQueryModel,
+        speaker_id: u32,
+        source_speaker_id: Option<u32>,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyAny> {
+        let audio_query = &self
+            .inner
+            .retarget_audio_query(
+                &audio_query,
+                speaker_id,
+                RetargetOptions { source_speaker_id },
+            )
             .into_py_result()?;
         to_pydantic_dataclass(
             audio_query,
@@ -180,12 +287,126 @@ impl SharevoxCore {
         )
     }
 
-    #[args(enable_interrogative_upspeak = "TtsOptions::default().enable_interrogative_upspeak")]
+    #[args(pitches = "None")]
+    fn audio_query_from_labels<'py>(
+        &mut self,
+        labels: &str,
+        speaker_id: u32,
+        pitches: Option<Vec<f32>>,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyAny> {
+        let audio_query = &self
+            .inner
+            .audio_query_from_labels(labels, speaker_id, pitches.as_deref())
+            .into_py_result()?;
+        to_pydantic_dataclass(
+            audio_query,
+            py.import("sharevox_core")?.getattr("AudioQuery")?,
+        )
+    }
+
+    fn analyze_text<'py>(&mut self, text: &str, py: Python<'py>) -> PyResult<&'py PyAny> {
+        let text_analysis = &self.inner.analyze_text(text).into_py_result()?;
+        to_pydantic_dataclass(
+            text_analysis,
+            py.import("sharevox_core")?.getattr("TextAnalysis")?,
+        )
+    }
+
+    fn extract_njd_nodes<'py>(&mut self, text: &str, py: Python<'py>) -> PyResult<Vec<&'py PyAny>> {
+        let njd_nodes = &self.inner.extract_njd_nodes(text).into_py_result()?;
+        to_pydantic_dataclasses(njd_nodes, py.import("sharevox_core")?.getattr("NjdNode")?)
+    }
+
+    fn mora_length<'py>(
+        &mut self,
+        accent_phrases: Vec<&PyAny>,
+        speaker_id: u32,
+        py: Python<'py>,
+    ) -> PyResult<Vec<&'py PyAny>> {
+        let accent_phrases = &from_dataclasses(accent_phrases)?;
+        let accent_phrases = &self
+            .inner
+            .mora_length(accent_phrases, speaker_id)
+            .into_py_result()?;
+        to_pydantic_dataclasses(
+            accent_phrases,
+            py.import("sharevox_core")?.getattr("AccentPhrase")?,
+        )
+    }
+
+    fn mora_pitch<'py>(
+        &mut self,
+        accent_phrases: Vec<&PyAny>,
+        speaker_id: u32,
+        py: Python<'py>,
+    ) -> PyResult<Vec<&'py PyAny>> {
+        let accent_phrases = &from_dataclasses(accent_phrases)?;
+        let accent_phrases = &self
+            .inner
+            .mora_pitch(accent_phrases, speaker_id)
+            .into_py_result()?;
+        to_pydantic_dataclasses(
+            accent_phrases,
+            py.import("sharevox_core")?.getattr("AccentPhrase")?,
+        )
+    }
+
+    fn mora_data<'py>(
+        &mut self,
+        accent_phrases: Vec<&PyAny>,
+        speaker_id: u32,
+        py: Python<'py>,
+    ) -> PyResult<Vec<&'py PyAny>> {
+        let accent_phrases = &from_dataclasses(accent_phrases)?;
+        let accent_phrases = &self
+            .inner
+            .mora_data(accent_phrases, speaker_id)
+            .into_py_result()?;
+        to_pydantic_dataclasses(
+            accent_phrases,
+            py.import("sharevox_core")?.getattr("AccentPhrase")?,
+        )
+    }
+
+    fn edit_accent_phrases<'py>(
+        &mut self,
+        accent_phrases: Vec<&PyAny>,
+        edit: &PyAny,
+        speaker_id: u32,
+        py: Python<'py>,
+    ) -> PyResult<Vec<&'py PyAny>> {
+        let accent_phrases = &from_dataclasses(accent_phrases)?;
+        let edit = &from_dataclass(edit)?;
+        let accent_phrases = &self
+            .inner
+            .edit_accent_phrases(accent_phrases, edit, speaker_id)
+            .into_py_result()?;
+        to_pydantic_dataclasses(
+            accent_phrases,
+            py.import("sharevox_core")?.getattr("AccentPhrase")?,
+        )
+    }
+
+    #[args(
+        enable_interrogative_upspeak = "TtsOptions::default().enable_interrogative_upspeak",
+        interrogative_upspeak = "None",
+        pitch_contour = "None",
+        target_duration = "None"
+    )]
+    #[allow(clippy::too_many_arguments)]
     fn synthesis<'py>(
         &mut self,
         #[pyo3(from_py_with = "from_dataclass")] audio_query: AudioQueryModel,
         speaker_id: u32,
         enable_interrogative_upspeak: bool,
+        #[pyo3(from_py_with = "from_optional_dataclass")] interrogative_upspeak: Option<
+            InterrogativeUpspeak,
+        >,
+        #[pyo3(from_py_with = "from_optional_dataclass")] pitch_contour: Option<PitchContour>,
+        #[pyo3(from_py_with = "from_optional_target_duration")] target_duration: Option<
+            TargetDuration,
+        >,
         py: Python<'py>,
     ) -> PyResult<&'py PyBytes> {
         let wav = &self
@@ -195,22 +416,237 @@ impl SharevoxCore {
                 speaker_id,
                 SynthesisOptions {
                     enable_interrogative_upspeak,
+                    interrogative_upspeak,
+                    pitch_contour,
+                    target_duration,
+                },
+            )
+            .into_py_result()?;
+        Ok(PyBytes::new(py, wav))
+    }
+
+    #[args(
+        enable_interrogative_upspeak = "TtsOptions::default().enable_interrogative_upspeak",
+        interrogative_upspeak = "None"
+    )]
+    fn fit_duration<'py>(
+        &mut self,
+        #[pyo3(from_py_with = "from_dataclass")] audio_query: AudioQueryModel,
+        speaker_id: u32,
+        #[pyo3(from_py_with = "from_target_duration")] target_duration: TargetDuration,
+        enable_interrogative_upspeak: bool,
+        #[pyo3(from_py_with = "from_optional_dataclass")] interrogative_upspeak: Option<
+            InterrogativeUpspeak,
+        >,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyAny> {
+        let fit = &self
+            .inner
+            .fit_duration(
+                &audio_query,
+                speaker_id,
+                &target_duration,
+                &SynthesisOptions {
+                    enable_interrogative_upspeak,
+                    interrogative_upspeak,
+                    pitch_contour: None,
+                    target_duration: None,
+                },
+            )
+            .into_py_result()?;
+        to_pydantic_dataclass(fit, py.import("sharevox_core")?.getattr("DurationFit")?)
+    }
+
+    #[args(pitches = "None")]
+    fn synthesis_from_labels<'py>(
+        &mut self,
+        labels: &str,
+        speaker_id: u32,
+        pitches: Option<Vec<f32>>,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyBytes> {
+        let wav = &self
+            .inner
+            .synthesis_from_labels(labels, speaker_id, pitches.as_deref())
+            .into_py_result()?;
+        Ok(PyBytes::new(py, wav))
+    }
+
+    #[args(
+        key_shift = "0",
+        pre_phoneme_length = "0.1",
+        post_phoneme_length = "0.1"
+    )]
+    fn sing_midi<'py>(
+        &mut self,
+        midi: &[u8],
+        speaker_id: u32,
+        key_shift: i32,
+        pre_phoneme_length: f32,
+        post_phoneme_length: f32,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyBytes> {
+        let score = Score::from_midi(midi)
+            .map_err(voicevox_core::Error::from)
+            .into_py_result()?;
+        let wav = &self
+            .inner
+            .sing(
+                &score,
+                speaker_id,
+                SingOptions {
+                    key_shift,
+                    pre_phoneme_length,
+                    post_phoneme_length,
+                },
+            )
+            .into_py_result()?;
+        Ok(PyBytes::new(py, wav))
+    }
+
+    #[args(
+        key_shift = "0",
+        pre_phoneme_length = "0.1",
+        post_phoneme_length = "0.1"
+    )]
+    fn sing_music_xml<'py>(
+        &mut self,
+        music_xml: &str,
+        speaker_id: u32,
+        key_shift: i32,
+        pre_phoneme_length: f32,
+        post_phoneme_length: f32,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyBytes> {
+        let score = Score::from_music_xml(music_xml)
+            .map_err(voicevox_core::Error::from)
+            .into_py_result()?;
+        let wav = &self
+            .inner
+            .sing(
+                &score,
+                speaker_id,
+                SingOptions {
+                    key_shift,
+                    pre_phoneme_length,
+                    post_phoneme_length,
                 },
             )
             .into_py_result()?;
         Ok(PyBytes::new(py, wav))
     }
 
+    #[args(
+        enable_interrogative_upspeak = "TtsOptions::default().enable_interrogative_upspeak",
+        interrogative_upspeak = "None",
+        pitch_contour = "None",
+        context_margin = "SynthesisSessionOptions::default().context_margin",
+        crossfade_length = "SynthesisSessionOptions::default().crossfade_length"
+    )]
+    fn new_synthesis_session(
+        &self,
+        speaker_id: u32,
+        enable_interrogative_upspeak: bool,
+        #[pyo3(from_py_with = "from_optional_dataclass")] interrogative_upspeak: Option<
+            InterrogativeUpspeak,
+        >,
+        #[pyo3(from_py_with = "from_optional_dataclass")] pitch_contour: Option<PitchContour>,
+        context_margin: usize,
+        crossfade_length: f32,
+    ) -> SynthesisSession {
+        let inner = self.inner.new_synthesis_session(
+            speaker_id,
+            SynthesisOptions {
+                enable_interrogative_upspeak,
+                interrogative_upspeak,
+                pitch_contour,
+                target_duration: None,
+            },
+            SynthesisSessionOptions {
+                context_margin,
+                crossfade_length,
+            },
+        );
+        SynthesisSession { inner }
+    }
+
+    fn synthesis_with_session<'py>(
+        &mut self,
+        mut session: PyRefMut<'_, SynthesisSession>,
+        #[pyo3(from_py_with = "from_dataclass")] audio_query: AudioQueryModel,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyBytes> {
+        let wav = &self
+            .inner
+            .synthesis_with_session(&mut session.inner, &audio_query)
+            .into_py_result()?;
+        Ok(PyBytes::new(py, wav))
+    }
+
+    fn synthesis_with_frame_pitch<'py>(
+        &mut self,
+        #[pyo3(from_py_with = "from_dataclass")] audio_query: AudioQueryModel,
+        speaker_id: u32,
+        frame_pitch: &'py PyArray<f32, Ix1>,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyBytes> {
+        let wav = &self
+            .inner
+            .synthesis_with_frame_pitch(
+                &audio_query,
+                speaker_id,
+                &FramePitch::Frames(frame_pitch.to_vec()?),
+            )
+            .into_py_result()?;
+        Ok(PyBytes::new(py, wav))
+    }
+
+    fn synthesis_with_pitch_curve<'py>(
+        &mut self,
+        #[pyo3(from_py_with = "from_dataclass")] audio_query: AudioQueryModel,
+        speaker_id: u32,
+        breakpoints: Vec<&PyAny>,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyBytes> {
+        let breakpoints = from_dataclasses(breakpoints)?;
+        let wav = &self
+            .inner
+            .synthesis_with_frame_pitch(
+                &audio_query,
+                speaker_id,
+                &FramePitch::Breakpoints(breakpoints),
+            )
+            .into_py_result()?;
+        Ok(PyBytes::new(py, wav))
+    }
+
     #[args(
         kana = "TtsOptions::default().kana",
-        enable_interrogative_upspeak = "TtsOptions::default().enable_interrogative_upspeak"
+        lenient_kana = "TtsOptions::default().lenient_kana",
+        enable_interrogative_upspeak = "TtsOptions::default().enable_interrogative_upspeak",
+        interrogative_upspeak = "None",
+        pitch_contour = "None",
+        target_duration = "None",
+        prosody_preset = "None"
     )]
+    #[allow(clippy::too_many_arguments)]
     fn tts<'py>(
         &mut self,
         text: &str,
         speaker_id: u32,
         kana: bool,
+        lenient_kana: bool,
         enable_interrogative_upspeak: bool,
+        #[pyo3(from_py_with = "from_optional_dataclass")] interrogative_upspeak: Option<
+            InterrogativeUpspeak,
+        >,
+        #[pyo3(from_py_with = "from_optional_dataclass")] pitch_contour: Option<PitchContour>,
+        #[pyo3(from_py_with = "from_optional_target_duration")] target_duration: Option<
+            TargetDuration,
+        >,
+        #[pyo3(from_py_with = "from_optional_prosody_preset")] prosody_preset: Option<
+            ProsodyPreset,
+        >,
         py: Python<'py>,
     ) -> PyResult<&'py PyBytes> {
         let wav = &self
@@ -220,7 +656,91 @@ impl SharevoxCore {
                 speaker_id,
                 TtsOptions {
                     kana,
+                    lenient_kana,
                     enable_interrogative_upspeak,
+                    interrogative_upspeak,
+                    pitch_contour,
+                    target_duration,
+                    prosody_preset,
+                },
+            )
+            .into_py_result_with_kana_details(py)?;
+        Ok(PyBytes::new(py, wav))
+    }
+
+    #[args(
+        output_sampling_rate = "ConnectWavesOptions::default().output_sampling_rate",
+        output_num_channels = "ConnectWavesOptions::default().output_num_channels",
+        gap_length = "ConnectWavesOptions::default().gap_length",
+        crossfade_length = "ConnectWavesOptions::default().crossfade_length"
+    )]
+    fn connect_waves<'py>(
+        &self,
+        waves: Vec<&[u8]>,
+        output_sampling_rate: Option<u32>,
+        output_num_channels: Option<u16>,
+        gap_length: f32,
+        crossfade_length: f32,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyBytes> {
+        let wav = &self
+            .inner
+            .connect_waves(
+                &waves,
+                ConnectWavesOptions {
+                    output_sampling_rate,
+                    output_num_channels,
+                    gap_length,
+                    crossfade_length,
+                },
+            )
+            .into_py_result()?;
+        Ok(PyBytes::new(py, wav))
+    }
+
+    #[args(
+        enable_interrogative_upspeak = "TtsOptions::default().enable_interrogative_upspeak",
+        interrogative_upspeak = "None",
+        pitch_contour = "None",
+        output_sampling_rate = "ConnectWavesOptions::default().output_sampling_rate",
+        output_num_channels = "ConnectWavesOptions::default().output_num_channels",
+        gap_length = "ConnectWavesOptions::default().gap_length",
+        crossfade_length = "ConnectWavesOptions::default().crossfade_length"
+    )]
+    #[allow(clippy::too_many_arguments)]
+    fn synthesis_connected<'py>(
+        &mut self,
+        audio_queries: Vec<(&PyAny, u32)>,
+        enable_interrogative_upspeak: bool,
+        #[pyo3(from_py_with = "from_optional_dataclass")] interrogative_upspeak: Option<
+            InterrogativeUpspeak,
+        >,
+        #[pyo3(from_py_with = "from_optional_dataclass")] pitch_contour: Option<PitchContour>,
+        output_sampling_rate: Option<u32>,
+        output_num_channels: Option<u16>,
+        gap_length: f32,
+        crossfade_length: f32,
+        py: Python<'py>,
+    ) -> PyResult<&'py PyBytes> {
+        let audio_queries = audio_queries
+            .into_iter()
+            .map(|(audio_query, speaker_id)| Ok((from_dataclass(audio_query)?, speaker_id)))
+            .collect::<PyResult<Vec<_>>>()?;
+        let wav = &self
+            .inner
+            .synthesis_connected(
+                &audio_queries,
+                SynthesisOptions {
+                    enable_interrogative_upspeak,
+                    interrogative_upspeak,
+                    pitch_contour,
+                    target_duration: None,
+                },
+                ConnectWavesOptions {
+                    output_sampling_rate,
+                    output_num_channels,
+                    gap_length,
+                    crossfade_length,
                 },
             )
             .into_py_result()?;
@@ -245,15 +765,26 @@ fn from_acceleration_mode(ob: &PyAny) -> PyResult<AccelerationMode> {
     }
 }
 
+fn from_optional_prosody_preset(ob: &PyAny) -> PyResult<Option<ProsodyPreset>> {
+    if ob.is_none() {
+        return Ok(None);
+    }
+    let preset = serde_json::Value::String(ob.extract()?);
+    serde_json::from_value(preset).into_py_result().map(Some)
+}
+
 fn from_optional_utf8_path(ob: &PyAny) -> PyResult<Option<String>> {
     if ob.is_none() {
         return Ok(None);
     }
 
+    from_utf8_path(ob).map(Some)
+}
+
+fn from_utf8_path(ob: &PyAny) -> PyResult<String> {
     PathBuf::extract(ob)?
         .into_os_string()
         .into_string()
-        .map(Some)
         .map_err(|s| SharevoxError::new_err(format!("{s:?} cannot be encoded to UTF-8")))
 }
 
@@ -268,6 +799,32 @@ fn from_dataclass<T: DeserializeOwned>(ob: &PyAny) -> PyResult<T> {
     serde_json::from_str(json).into_py_result()
 }
 
+fn from_optional_dataclass<T: DeserializeOwned>(ob: &PyAny) -> PyResult<Option<T>> {
+    if ob.is_none() {
+        return Ok(None);
+    }
+    from_dataclass(ob).map(Some)
+}
+
+/// 数値は音声全体の長さ、数値のリストは文ごとの長さとする
+fn from_target_duration(ob: &PyAny) -> PyResult<TargetDuration> {
+    if let Ok(durations) = ob.extract() {
+        return Ok(TargetDuration::Sentences(durations));
+    }
+    ob.extract().map(TargetDuration::Total)
+}
+
+fn from_optional_target_duration(ob: &PyAny) -> PyResult<Option<TargetDuration>> {
+    if ob.is_none() {
+        return Ok(None);
+    }
+    from_target_duration(ob).map(Some)
+}
+
+fn from_dataclasses<T: DeserializeOwned>(obs: Vec<&PyAny>) -> PyResult<Vec<T>> {
+    obs.into_iter().map(from_dataclass).collect()
+}
+
 fn to_pydantic_dataclass(x: impl Serialize, class: &PyAny) -> PyResult<&PyAny> {
     let py = class.py();
 
@@ -276,6 +833,13 @@ fn to_pydantic_dataclass(x: impl Serialize, class: &PyAny) -> PyResult<&PyAny> {
     class.call((), Some(x))
 }
 
+fn to_pydantic_dataclasses<'py>(
+    xs: &[impl Serialize],
+    class: &'py PyAny,
+) -> PyResult<Vec<&'py PyAny>> {
+    xs.iter().map(|x| to_pydantic_dataclass(x, class)).collect()
+}
+
 impl Drop for SharevoxCore {
     fn drop(&mut self) {
         debug!("Destructing a SharevoxCore");
@@ -289,3 +853,35 @@ impl<T, E: Display> Result<T, E> {
         self.map_err(|e| SharevoxError::new_err(e.to_string()))
     }
 }
+
+#[ext(VoicevoxResultExt)]
+impl<T> voicevox_core::Result<T> {
+    /// kanaの解析エラーを、エラーの詳細を属性に持つ`KanaParseError`に変換する
+    fn into_py_result_with_kana_details(self, py: Python<'_>) -> PyResult<T> {
+        self.map_err(|e| match &e {
+            voicevox_core::Error::ParseKana(details) => {
+                to_kana_parse_error(e.to_string(), details, py).unwrap_or_else(|e| e)
+            }
+            _ => SharevoxError::new_err(e.to_string()),
+        })
+    }
+}
+
+fn to_kana_parse_error(
+    message: String,
+    details: &voicevox_core::KanaParseError,
+    py: Python<'_>,
+) -> PyResult<PyErr> {
+    let err = KanaParseError::new_err(message);
+    let value = err.value(py);
+    let kind = serde_json::to_value(details.kind()).into_py_result()?;
+    let kind = py
+        .import("sharevox_core")?
+        .getattr("KanaParseErrorKind")?
+        .call1((kind.as_str(),))?;
+    value.setattr("kind", kind)?;
+    value.setattr("offset", details.offset())?;
+    value.setattr("length", details.length())?;
+    value.setattr("accent_phrase_index", details.accent_phrase_index())?;
+    Ok(err)
+}